@@ -3,7 +3,7 @@
 use crate::abi::{Event, Function, ParamType};
 use crate::errors::ParseParamTypeError;
 use crate::hash::{self, H32};
-use serde_json::json;
+use crate::human_readable;
 
 /// Extension trait for `ethabi::Function`.
 pub trait FunctionExt {
@@ -55,9 +55,10 @@ impl EventExt for Event {
 
 /// An extension trait for Solidity parameter types.
 pub trait ParamTypeExt {
-    /// Parses a parameter type from a string value.
+    /// Parses a parameter type from a string value, including tuples and
+    /// arrays such as `(uint256,address)[]`.
     fn from_str(s: &str) -> Result<ParamType, ParseParamTypeError> {
-        serde_json::from_value(json!(s)).map_err(|_| ParseParamTypeError(s.into()))
+        human_readable::parse_param_type(s).map_err(|_| ParseParamTypeError(s.into()))
     }
 }
 
@@ -9,12 +9,18 @@
 //! sources, and parse them using different formats.
 
 use crate::contract::{Documentation, Network};
+use crate::errors::ArtifactError;
 use crate::{Abi, Bytecode, Contract};
+use serde::de::Error as _;
+use serde::{Deserialize, Deserializer};
+use serde_json::Value;
 use std::collections::hash_map::Entry;
 use std::collections::HashMap;
 use std::ops::Deref;
 
+pub mod forge;
 pub mod hardhat;
+pub mod solc;
 pub mod truffle;
 
 /// An entity that contains compiled contracts.
@@ -109,6 +115,31 @@ impl Artifact {
         }
     }
 
+    /// Records a deployment of a contract on the network with the given ID.
+    ///
+    /// The contract is inserted into the artifact if it is not there yet.
+    /// If the contract was already deployed on this network, the deployment
+    /// is replaced and the old one is returned.
+    pub fn record_deployment(
+        &mut self,
+        contract: &Contract,
+        network_id: impl Into<String>,
+        network: Network,
+    ) -> Result<Option<Network>, ArtifactError> {
+        let mut contract = match self.get_mut(&contract.name) {
+            Some(existing_contract) => {
+                if existing_contract.abi != contract.abi {
+                    return Err(ArtifactError::AbiMismatch(contract.name.clone()));
+                }
+
+                existing_contract
+            }
+            None => self.insert(contract.clone()).inserted_contract,
+        };
+
+        Ok(contract.networks_mut().insert(network_id.into(), network))
+    }
+
     /// Removes contract from the artifact.
     ///
     /// Returns removed contract or [`None`] if contract with the given name
@@ -159,6 +190,11 @@ impl<'a> ContractMut<'a> {
         &mut self.0.bytecode
     }
 
+    /// Returns mutable reference to contract's deployed bytecode.
+    pub fn deployed_bytecode_mut(&mut self) -> &mut Bytecode {
+        &mut self.0.deployed_bytecode
+    }
+
     /// Returns mutable reference to contract's networks.
     pub fn networks_mut(&mut self) -> &mut HashMap<String, Network> {
         &mut self.0.networks
@@ -183,9 +219,32 @@ impl Deref for ContractMut<'_> {
     }
 }
 
+/// Parses a contract ABI from a JSON value, ignoring custom errors which are
+/// not supported by `ethabi`. ABIs encoded as JSON strings, as some compiler
+/// outputs do, are also supported.
+pub(crate) fn abi_from_value(value: Value) -> serde_json::Result<Abi> {
+    let value = match value {
+        Value::String(json) => serde_json::from_str(&json)?,
+        value => value,
+    };
+
+    let mut abi = Vec::<Value>::deserialize(value)?;
+    abi.retain(|item| item.get("type").and_then(Value::as_str) != Some("error"));
+    serde_json::from_value(Value::Array(abi))
+}
+
+/// Deserializes a contract ABI with [`abi_from_value`].
+pub(crate) fn deserialize_abi<'de, D>(deserializer: D) -> Result<Abi, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    abi_from_value(Value::deserialize(deserializer)?).map_err(D::Error::custom)
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
+    use crate::{Address, DeploymentInformation};
 
     fn make_contract(name: &str) -> Contract {
         let mut contract = Contract::empty();
@@ -250,4 +309,39 @@ mod test {
         assert!(!artifact.contains("C1"));
         assert!(artifact.contains("C2"));
     }
+
+    #[test]
+    fn record_deployment() {
+        let mut artifact = Artifact::new();
+        let contract = make_contract("C1");
+        let network = |address: u8, block: u64| Network {
+            address: Address::repeat_byte(address),
+            deployment_information: Some(block.into()),
+        };
+
+        let old = artifact
+            .record_deployment(&contract, "1", network(1, 42))
+            .unwrap();
+        assert!(old.is_none());
+        assert!(artifact.contains("C1"));
+
+        let old = artifact
+            .record_deployment(&contract, "1", network(2, 1337))
+            .unwrap();
+        assert_eq!(old.unwrap().address, Address::repeat_byte(1));
+
+        let networks = &artifact.get("C1").unwrap().networks;
+        assert_eq!(networks["1"].address, Address::repeat_byte(2));
+        assert_eq!(
+            networks["1"].deployment_information,
+            Some(DeploymentInformation::BlockNumber(1337))
+        );
+
+        let mut other = make_contract("C1");
+        other.abi.receive = true;
+        assert!(matches!(
+            artifact.record_deployment(&other, "1", network(3, 0)),
+            Err(ArtifactError::AbiMismatch(name)) if name == "C1"
+        ));
+    }
 }
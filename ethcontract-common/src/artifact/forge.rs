@@ -0,0 +1,546 @@
+//! Implements the artifact format generated by [Foundry]'s `forge build`.
+//!
+//! Forge writes an artifact for each compiled contract to
+//! `out/<File>.sol/<Contract>.json`. Each artifact is a JSON file containing
+//! information about a single contract. We parse the following fields:
+//!
+//! - `abi`: information about contract's interface, custom errors are ignored;
+//! - `bytecode.object`, `bytecode.linkReferences`: contract's compiled
+//!   bytecode and locations of libraries in it (optional);
+//! - `deployedBytecode.object`, `deployedBytecode.linkReferences`,
+//!   `deployedBytecode.immutableReferences`: contract's compiled runtime
+//!   bytecode, and locations of libraries and immutables in it (optional);
+//! - `metadata`: compiler metadata, used for the contract name and its
+//!   `devdoc` and `userdoc` documentation (optional).
+//!
+//! Forge artifacts contain no deployment information.
+//!
+//! [Foundry]: https://github.com/foundry-rs/foundry
+
+use crate::artifact::{deserialize_abi, Artifact};
+use crate::contract::{Documentation, ImmutableReference, LinkReferences};
+use crate::errors::ArtifactError;
+use crate::{Abi, Bytecode, Contract};
+use serde::Deserialize;
+use serde_json::{from_reader, from_slice, from_str, from_value, Value};
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufReader, Read};
+use std::path::Path;
+
+/// Loads forge artifacts.
+pub struct ForgeLoader {
+    /// Override for artifact's origin. If `None`, origin
+    /// will be derived automatically.
+    pub origin: Option<String>,
+
+    /// Override for contract's name.
+    ///
+    /// By default, the name of a contract loaded from a single artifact is
+    /// taken from its compiler metadata.
+    pub name: Option<String>,
+
+    /// List of allowed contract names.
+    ///
+    /// When loading artifact, loader will only load contracts if their names
+    /// are present in this list.
+    ///
+    /// Empty list means that all contracts are allowed.
+    pub contracts_allow_list: Vec<String>,
+
+    /// List of denied contract names.
+    ///
+    /// When loading artifact, loader will not load contracts if their names
+    /// are present in this list.
+    ///
+    /// Empty list means that no contracts are denied.
+    ///
+    /// Deny list takes precedence over allow list. That is, if contract
+    /// appears in both, it will be denied.
+    pub contracts_deny_list: Vec<String>,
+}
+
+impl ForgeLoader {
+    /// Creates a new forge loader.
+    pub fn new() -> Self {
+        ForgeLoader {
+            origin: None,
+            name: None,
+            contracts_allow_list: Vec::new(),
+            contracts_deny_list: Vec::new(),
+        }
+    }
+
+    /// Creates a new forge loader and sets an override for artifact's origins.
+    pub fn with_origin(origin: impl Into<String>) -> Self {
+        ForgeLoader {
+            origin: Some(origin.into()),
+            name: None,
+            contracts_allow_list: Vec::new(),
+            contracts_deny_list: Vec::new(),
+        }
+    }
+
+    /// Sets new override for artifact's origin. See [`origin`] for more info.
+    ///
+    /// [`origin`]: #structfield.origin
+    pub fn origin(mut self, origin: impl Into<String>) -> Self {
+        self.origin = Some(origin.into());
+        self
+    }
+
+    /// Sets new override for contract's name. See [`name`] for more info.
+    ///
+    /// [`name`]: #structfield.name
+    pub fn name(mut self, name: impl Into<String>) -> Self {
+        self.name = Some(name.into());
+        self
+    }
+
+    /// Adds contract name to the list of [`allowed contracts`].
+    ///
+    /// [`allowed contracts`]: #structfield.contracts_allow_list
+    pub fn allow_contract(mut self, contract: impl Into<String>) -> Self {
+        self.contracts_allow_list.push(contract.into());
+        self
+    }
+
+    /// Adds contract name to the list of [`denied contracts`].
+    ///
+    /// [`denied contracts`]: #structfield.contracts_deny_list
+    pub fn deny_contract(mut self, contract: impl Into<String>) -> Self {
+        self.contracts_deny_list.push(contract.into());
+        self
+    }
+
+    /// Loads an artifact from a JSON value.
+    pub fn load_from_reader(&self, v: impl Read) -> Result<Artifact, ArtifactError> {
+        self.load_artifact("<unknown>", v, from_reader)
+    }
+
+    /// Loads an artifact from bytes of JSON text.
+    pub fn load_from_slice(&self, v: &[u8]) -> Result<Artifact, ArtifactError> {
+        self.load_artifact("<unknown>", v, from_slice)
+    }
+
+    /// Loads an artifact from string of JSON text.
+    pub fn load_from_str(&self, v: &str) -> Result<Artifact, ArtifactError> {
+        self.load_artifact("<unknown>", v, from_str)
+    }
+
+    /// Loads an artifact from a loaded JSON value.
+    pub fn load_from_value(&self, v: Value) -> Result<Artifact, ArtifactError> {
+        self.load_artifact("<unknown>", v, from_value)
+    }
+
+    /// Loads an artifact from disk.
+    pub fn load_from_file(&self, p: impl AsRef<Path>) -> Result<Artifact, ArtifactError> {
+        let path = p.as_ref();
+        let file = File::open(path)?;
+        let reader = BufReader::new(file);
+        self.load_artifact(path.display(), reader, from_reader)
+    }
+
+    /// Loads a contract from a JSON value.
+    pub fn load_contract_from_reader(&self, v: impl Read) -> Result<Contract, ArtifactError> {
+        self.load_contract(v, from_reader)
+    }
+
+    /// Loads a contract from bytes of JSON text.
+    pub fn load_contract_from_slice(&self, v: &[u8]) -> Result<Contract, ArtifactError> {
+        self.load_contract(v, from_slice)
+    }
+
+    /// Loads a contract from string of JSON text.
+    pub fn load_contract_from_str(&self, v: &str) -> Result<Contract, ArtifactError> {
+        self.load_contract(v, from_str)
+    }
+
+    /// Loads a contract from a loaded JSON value.
+    pub fn load_contract_from_value(&self, v: Value) -> Result<Contract, ArtifactError> {
+        self.load_contract(v, from_value)
+    }
+
+    /// Loads a contract from disk.
+    pub fn load_contract_from_file(&self, p: impl AsRef<Path>) -> Result<Contract, ArtifactError> {
+        let path = p.as_ref();
+        let file = File::open(path)?;
+        let reader = BufReader::new(file);
+        self.load_contract(reader, from_reader)
+    }
+
+    /// Loads an artifact from forge's `out` directory.
+    ///
+    /// The `out` directory contains a directory for each source file, which
+    /// in turn contains an artifact for each contract in the source file:
+    ///
+    /// ```text
+    /// out
+    ///  |
+    ///  +-- Token.sol
+    ///  |    |
+    ///  |    +-- IToken.json
+    ///  |    |
+    ///  |    +-- Token.json
+    ///  |
+    ///  +-- Math.sol
+    ///  |    |
+    ///  |    +-- Math.json
+    ///  |
+    ///  +-- build-info
+    ///  |
+    ///  ...
+    /// ```
+    ///
+    /// Contracts are named after their artifact files. Forge adds the compiler
+    /// version to the file name when a contract is compiled with several
+    /// versions (for example `Token.0.8.19.json`); the version is not part of
+    /// the contract name. Contracts with the same name in different source
+    /// files must have the same ABI, in which case only one of them is loaded.
+    pub fn load_from_directory(&self, p: impl AsRef<Path>) -> Result<Artifact, ArtifactError> {
+        self._load_from_directory(p.as_ref())
+    }
+
+    /// Helper for `load_from_directory`. We use this helper function to avoid
+    /// making a big chunk of code generic over `AsRef<Path>`.
+    fn _load_from_directory(&self, p: &Path) -> Result<Artifact, ArtifactError> {
+        let origin = self
+            .origin
+            .clone()
+            .unwrap_or_else(|| p.display().to_string());
+        let mut artifact = Artifact::with_origin(origin);
+
+        let mut source_paths = p
+            .read_dir()?
+            .map(|entry| Ok(entry?.path()))
+            .collect::<Result<Vec<_>, ArtifactError>>()?;
+        source_paths.sort();
+
+        for source_path in source_paths {
+            if !source_path.is_dir() || source_path.ends_with("build-info") {
+                continue;
+            }
+
+            let mut contract_paths = source_path
+                .read_dir()?
+                .map(|entry| Ok(entry?.path()))
+                .collect::<Result<Vec<_>, ArtifactError>>()?;
+            contract_paths.sort();
+
+            for contract_path in contract_paths {
+                if !contract_path.is_file()
+                    || contract_path.extension().and_then(|ext| ext.to_str()) != Some("json")
+                {
+                    continue;
+                }
+
+                let contract_name = contract_path
+                    .file_name()
+                    .expect("file has a name")
+                    .to_string_lossy()
+                    .split('.')
+                    .next()
+                    .expect("split always yields a string")
+                    .to_string();
+
+                if !self.contract_allowed(&contract_name) {
+                    continue;
+                }
+
+                let mut contract = {
+                    let file = File::open(&contract_path)?;
+                    let reader = BufReader::new(file);
+                    from_reader::<_, ForgeContract>(reader)?.into_contract()
+                };
+                contract.name = contract_name;
+
+                match artifact.get(&contract.name) {
+                    Some(existing_contract) if existing_contract.abi != contract.abi => {
+                        return Err(ArtifactError::DuplicateContract(contract.name));
+                    }
+                    Some(_) => continue,
+                    None => {
+                        artifact.insert(contract);
+                    }
+                }
+            }
+        }
+
+        Ok(artifact)
+    }
+
+    fn load_artifact<T>(
+        &self,
+        origin: impl ToString,
+        source: T,
+        loader: impl FnOnce(T) -> serde_json::Result<ForgeContract>,
+    ) -> Result<Artifact, ArtifactError> {
+        let origin = self.origin.clone().unwrap_or_else(|| origin.to_string());
+        let mut artifact = Artifact::with_origin(origin);
+
+        let contract = self.load_contract(source, loader)?;
+        if self.contract_allowed(&contract.name) {
+            artifact.insert(contract);
+        }
+
+        Ok(artifact)
+    }
+
+    fn load_contract<T>(
+        &self,
+        source: T,
+        loader: impl FnOnce(T) -> serde_json::Result<ForgeContract>,
+    ) -> Result<Contract, ArtifactError> {
+        let mut contract = loader(source)?.into_contract();
+
+        if let Some(name) = &self.name {
+            contract.name = name.clone();
+        }
+
+        Ok(contract)
+    }
+
+    fn contract_allowed(&self, name: &str) -> bool {
+        !self.contract_explicitly_denied(name)
+            && (self.contracts_allow_list.is_empty() || self.contract_explicitly_allowed(name))
+    }
+
+    fn contract_explicitly_allowed(&self, name: &str) -> bool {
+        self.contracts_allow_list.iter().any(|x| x == name)
+    }
+
+    fn contract_explicitly_denied(&self, name: &str) -> bool {
+        self.contracts_deny_list.iter().any(|x| x == name)
+    }
+}
+
+impl Default for ForgeLoader {
+    fn default() -> Self {
+        ForgeLoader::new()
+    }
+}
+
+#[derive(Deserialize)]
+struct ForgeContract {
+    #[serde(deserialize_with = "deserialize_abi")]
+    abi: Abi,
+    #[serde(default)]
+    bytecode: ForgeBytecode,
+    #[serde(rename = "deployedBytecode", default)]
+    deployed_bytecode: ForgeBytecode,
+    #[serde(default)]
+    metadata: Option<ForgeMetadata>,
+}
+
+impl ForgeContract {
+    fn into_contract(self) -> Contract {
+        let metadata = self.metadata.unwrap_or_default();
+
+        // NOTE: The compilation target maps the source file to the name of
+        //   the contract the artifact is for.
+        let name = metadata
+            .settings
+            .compilation_target
+            .into_iter()
+            .next()
+            .map(|(_, name)| name)
+            .unwrap_or_default();
+
+        let mut contract = Contract::with_name(name);
+        contract.abi = self.abi;
+        contract.bytecode = self.bytecode.object;
+        contract.link_references = self.bytecode.link_references;
+        contract.deployed_bytecode = self.deployed_bytecode.object;
+        contract.deployed_link_references = self.deployed_bytecode.link_references;
+        contract.immutable_references = self.deployed_bytecode.immutable_references;
+        contract.devdoc = metadata.output.devdoc;
+        contract.userdoc = metadata.output.userdoc;
+        contract
+    }
+}
+
+#[derive(Default, Deserialize)]
+#[serde(default)]
+struct ForgeBytecode {
+    object: Bytecode,
+    #[serde(rename = "linkReferences")]
+    link_references: LinkReferences,
+    #[serde(rename = "immutableReferences")]
+    immutable_references: HashMap<String, Vec<ImmutableReference>>,
+}
+
+#[derive(Default, Deserialize)]
+#[serde(default)]
+struct ForgeMetadata {
+    output: ForgeMetadataOutput,
+    settings: ForgeMetadataSettings,
+}
+
+#[derive(Default, Deserialize)]
+#[serde(default)]
+struct ForgeMetadataOutput {
+    devdoc: Documentation,
+    userdoc: Documentation,
+}
+
+#[derive(Default, Deserialize)]
+#[serde(default)]
+struct ForgeMetadataSettings {
+    #[serde(rename = "compilationTarget")]
+    compilation_target: HashMap<String, String>,
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const TOKEN: &str = r#"
+      {
+        "abi": [
+          {
+            "type": "function",
+            "name": "balanceOf",
+            "inputs": [{ "name": "owner", "type": "address", "internalType": "address" }],
+            "outputs": [{ "name": "", "type": "uint256", "internalType": "uint256" }],
+            "stateMutability": "view"
+          },
+          {
+            "type": "error",
+            "name": "Unauthorized",
+            "inputs": []
+          }
+        ],
+        "bytecode": {
+          "object": "0x73__$7658e08c4e23aceed01ae97f6c6f1bccc3$__00",
+          "sourceMap": "",
+          "linkReferences": {
+            "lib.sol": { "L": [{ "start": 1, "length": 20 }] }
+          }
+        },
+        "deployedBytecode": {
+          "object": "0x6080",
+          "sourceMap": "",
+          "linkReferences": {},
+          "immutableReferences": {
+            "7": [{ "start": 1, "length": 32 }]
+          }
+        },
+        "methodIdentifiers": {
+          "balanceOf(address)": "70a08231"
+        },
+        "metadata": {
+          "compiler": { "version": "0.8.19+commit.7dd6d404" },
+          "language": "Solidity",
+          "output": {
+            "devdoc": {
+              "kind": "dev",
+              "details": "A token.",
+              "methods": {
+                "balanceOf(address)": { "details": "Returns the balance." }
+              },
+              "version": 1
+            },
+            "userdoc": { "kind": "user", "methods": {}, "version": 1 }
+          },
+          "settings": {
+            "compilationTarget": { "src/Token.sol": "Token" }
+          }
+        }
+      }
+    "#;
+
+    #[test]
+    fn load_single() {
+        let artifact = ForgeLoader::new().load_from_str(TOKEN).unwrap();
+
+        assert_eq!(artifact.len(), 1);
+
+        let token = artifact.get("Token").unwrap();
+        assert!(token.abi.function("balanceOf").is_ok());
+        assert_eq!(
+            token.bytecode.undefined_libraries().collect::<Vec<_>>(),
+            ["$7658e08c4e23aceed01ae97f6c6f1bccc3$"]
+        );
+        assert_eq!(token.link_references["lib.sol"]["L"][0].start, 1);
+        assert_eq!(token.deployed_bytecode.to_bytes().unwrap().0, [0x60, 0x80]);
+        assert_eq!(token.immutable_references["7"][0].length, 32);
+        assert_eq!(token.devdoc.details.as_deref(), Some("A token."));
+        assert_eq!(
+            token.devdoc.methods["balanceOf(address)"]
+                .details
+                .as_deref(),
+            Some("Returns the balance.")
+        );
+        assert!(token.networks.is_empty());
+    }
+
+    #[test]
+    fn load_single_without_metadata() {
+        let contract = ForgeLoader::new()
+            .load_contract_from_str(r#"{ "abi": [] }"#)
+            .unwrap();
+        assert_eq!(contract.name, "");
+        assert!(contract.bytecode.is_empty());
+
+        let contract = ForgeLoader::new()
+            .name("Renamed")
+            .load_contract_from_str(TOKEN)
+            .unwrap();
+        assert_eq!(contract.name, "Renamed");
+    }
+
+    #[test]
+    fn load_single_deny_contract_name() {
+        let artifact = ForgeLoader::new()
+            .deny_contract("Token")
+            .load_from_str(TOKEN)
+            .unwrap();
+        assert!(artifact.is_empty());
+
+        let artifact = ForgeLoader::new()
+            .allow_contract("Other")
+            .load_from_str(TOKEN)
+            .unwrap();
+        assert!(artifact.is_empty());
+    }
+
+    #[test]
+    fn load_from_directory() {
+        let dir = tempfile::tempdir().unwrap();
+        let write = |path: &str, contents: &str| {
+            let path = dir.path().join(path);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, contents).unwrap();
+        };
+        write("Token.sol/Token.json", TOKEN);
+        write("Token.sol/IToken.json", r#"{ "abi": [] }"#);
+        write("IToken.sol/IToken.0.8.19.json", r#"{ "abi": [] }"#);
+        write(
+            "Math.sol/Math.json",
+            r#"{ "abi": [{ "type": "receive" }] }"#,
+        );
+        write("build-info/0123.json", r#"{ "id": "0123" }"#);
+
+        let artifact = ForgeLoader::new().load_from_directory(dir.path());
+        let filtered = ForgeLoader::new()
+            .deny_contract("Math")
+            .load_from_directory(dir.path());
+        write("Other.sol/Math.json", r#"{ "abi": [] }"#);
+        let duplicate = ForgeLoader::new().load_from_directory(dir.path());
+
+        let artifact = artifact.unwrap();
+        assert_eq!(artifact.len(), 3);
+        assert_eq!(artifact.get("Token").unwrap().name, "Token");
+        assert!(artifact.contains("IToken"));
+        assert!(artifact.get("Math").unwrap().abi.receive);
+
+        let filtered = filtered.unwrap();
+        assert_eq!(filtered.len(), 2);
+        assert!(!filtered.contains("Math"));
+
+        assert!(matches!(
+            duplicate,
+            Err(ArtifactError::DuplicateContract(name)) if name == "Math"
+        ));
+    }
+}
@@ -20,19 +20,20 @@
 //! contracts.
 //!
 //! All three formats are supported by [`HardHatLoader`], see its documentation
-//! for info and limitations.
+//! for info and limitations. [`HardHatLoader`] can also save artifacts in
+//! all three formats.
 //!
 //! [hardhat-deploy]: https://github.com/wighawag/hardhat-deploy
 
 use crate::artifact::Artifact;
-use crate::contract::Network;
+use crate::contract::{Documentation, LinkReferences, Network};
 use crate::errors::ArtifactError;
-use crate::{Address, Contract, DeploymentInformation, TransactionHash};
-use serde::Deserialize;
-use serde_json::{from_reader, from_slice, from_str, from_value, Value};
-use std::collections::HashMap;
-use std::fs::File;
-use std::io::{BufReader, Read};
+use crate::{Abi, Address, Bytecode, Contract, DeploymentInformation, TransactionHash};
+use serde::{Deserialize, Serialize};
+use serde_json::{from_reader, from_slice, from_str, from_value, to_string, to_writer, Value};
+use std::collections::{BTreeMap, HashMap};
+use std::fs::{self, File};
+use std::io::{BufReader, BufWriter, Read, Write};
 use std::path::Path;
 
 /// Loads hardhat artifacts generated via `--export` and `--export-all`.
@@ -218,6 +219,133 @@ impl HardHatLoader {
         self._load_from_directory(p.as_ref())
     }
 
+    /// Serializes the deployment of a contract on the network with the given
+    /// chain ID in the format of a contract file in hardhat-deploy's
+    /// `deployments` directory.
+    ///
+    /// Deployments with a block number are serialized with a partial
+    /// deployment receipt containing the block number.
+    pub fn save_deployment_to_string(
+        contract: &Contract,
+        chain_id: &str,
+    ) -> Result<String, ArtifactError> {
+        to_string(&HardHatDeployment::new(contract, chain_id)?).map_err(Into::into)
+    }
+
+    /// Serializes the deployment of a contract on the network with the given
+    /// chain ID to disk. See [`save_deployment_to_string`] for more info.
+    ///
+    /// [`save_deployment_to_string`]: #method.save_deployment_to_string
+    pub fn save_deployment_to_file(
+        contract: &Contract,
+        chain_id: &str,
+        p: impl AsRef<Path>,
+    ) -> Result<(), ArtifactError> {
+        let deployment = HardHatDeployment::new(contract, chain_id)?;
+        let file = File::create(p)?;
+        let mut writer = BufWriter::new(file);
+        to_writer(&mut writer, &deployment)?;
+        writer.flush()?;
+        Ok(())
+    }
+
+    /// Serializes contracts deployed on the given networks in the specified
+    /// export format.
+    ///
+    /// Only contract addresses, ABIs and deployment information are exported.
+    /// [`Format::SingleExport`] requires exactly one network.
+    pub fn save_to_string(
+        f: Format,
+        artifact: &Artifact,
+        networks: &[HardHatNetwork],
+    ) -> Result<String, ArtifactError> {
+        let mut buffer = Vec::new();
+        HardHatLoader::save_to_writer(f, artifact, networks, &mut buffer)?;
+        Ok(String::from_utf8(buffer).expect("JSON is valid UTF-8"))
+    }
+
+    /// Serializes contracts deployed on the given networks in the specified
+    /// export format into a writer. See [`save_to_string`] for more info.
+    ///
+    /// [`save_to_string`]: #method.save_to_string
+    pub fn save_to_writer(
+        f: Format,
+        artifact: &Artifact,
+        networks: &[HardHatNetwork],
+        w: impl Write,
+    ) -> Result<(), ArtifactError> {
+        match f {
+            Format::SingleExport => {
+                let network = match networks {
+                    [network] => network,
+                    _ => return Err(ArtifactError::SingleExportNetworks(networks.len())),
+                };
+                to_writer(w, &HardHatExportRef::new(artifact, network)?)?;
+            }
+            Format::MultiExport => {
+                let mut export = BTreeMap::<_, BTreeMap<_, _>>::new();
+                for network in networks {
+                    export.entry(network.chain_id.as_str()).or_default().insert(
+                        network.name.as_str(),
+                        HardHatExportRef::new(artifact, network)?,
+                    );
+                }
+                to_writer(w, &export)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Serializes contracts deployed on the given networks in the specified
+    /// export format to disk. See [`save_to_string`] for more info.
+    ///
+    /// [`save_to_string`]: #method.save_to_string
+    pub fn save_to_file(
+        f: Format,
+        artifact: &Artifact,
+        networks: &[HardHatNetwork],
+        p: impl AsRef<Path>,
+    ) -> Result<(), ArtifactError> {
+        let file = File::create(p)?;
+        let mut writer = BufWriter::new(file);
+        HardHatLoader::save_to_writer(f, artifact, networks, &mut writer)?;
+        writer.flush()?;
+        Ok(())
+    }
+
+    /// Saves contracts deployed on the given networks into a `deployments`
+    /// directory, creating it if it does not exist. See
+    /// [`load_from_directory`] for the directory layout.
+    ///
+    /// Existing files of contracts that are not deployed on a network are
+    /// left untouched.
+    ///
+    /// [`load_from_directory`]: #method.load_from_directory
+    pub fn save_to_directory(
+        artifact: &Artifact,
+        networks: &[HardHatNetwork],
+        p: impl AsRef<Path>,
+    ) -> Result<(), ArtifactError> {
+        for network in networks {
+            let network_path = p.as_ref().join(&network.name);
+            fs::create_dir_all(&network_path)?;
+            fs::write(network_path.join(".chainId"), &network.chain_id)?;
+
+            for contract in artifact.iter() {
+                if contract.networks.contains_key(&network.chain_id) {
+                    HardHatLoader::save_deployment_to_file(
+                        contract,
+                        &network.chain_id,
+                        network_path.join(format!("{}.json", contract.name)),
+                    )?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
     /// Helper for `load_from_directory`. We use this helper function to avoid
     /// making a big chunk of code generic over `AsRef<Path>`.
     ///
@@ -323,6 +451,7 @@ impl HardHatLoader {
                 let HardHatContract {
                     address,
                     transaction_hash,
+                    receipt,
                     mut contract,
                 } = {
                     let file = File::open(contract_path)?;
@@ -337,7 +466,7 @@ impl HardHatLoader {
                     contract,
                     chain_id.clone(),
                     address,
-                    transaction_hash,
+                    deployment_information(transaction_hash, receipt),
                 )?;
             }
         }
@@ -381,6 +510,7 @@ impl HardHatLoader {
                 let HardHatContract {
                     address,
                     transaction_hash,
+                    receipt,
                     mut contract,
                 } = contract;
 
@@ -395,7 +525,7 @@ impl HardHatLoader {
                     contract,
                     export.chain_id.clone(),
                     address,
-                    transaction_hash,
+                    deployment_information(transaction_hash, receipt),
                 )?;
             }
         }
@@ -423,7 +553,7 @@ impl HardHatLoader {
         contract: Contract,
         chain_id: String,
         address: Address,
-        transaction_hash: Option<TransactionHash>,
+        deployment_information: Option<DeploymentInformation>,
     ) -> Result<(), ArtifactError> {
         let mut contract = match artifact.get_mut(&contract.name) {
             Some(existing_contract) => {
@@ -436,8 +566,6 @@ impl HardHatLoader {
             None => artifact.insert(contract).inserted_contract,
         };
 
-        let deployment_information = transaction_hash.map(DeploymentInformation::TransactionHash);
-
         if contract.networks.contains_key(&chain_id) {
             Err(ArtifactError::DuplicateChain(chain_id))
         } else {
@@ -520,6 +648,27 @@ impl NetworkEntry {
     }
 }
 
+/// A hardhat network for saving artifacts, identified by its name specified
+/// in `hardhat.config.js` and its chain ID.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct HardHatNetwork {
+    /// Network name.
+    pub name: String,
+
+    /// Network chain ID.
+    pub chain_id: String,
+}
+
+impl HardHatNetwork {
+    /// Creates a new network with the given name and chain ID.
+    pub fn new(name: impl Into<String>, chain_id: impl Into<String>) -> Self {
+        HardHatNetwork {
+            name: name.into(),
+            chain_id: chain_id.into(),
+        }
+    }
+}
+
 #[derive(Deserialize)]
 struct HardHatMultiExport {
     #[serde(flatten)]
@@ -540,10 +689,128 @@ struct HardHatContract {
     address: Address,
     #[serde(rename = "transactionHash")]
     transaction_hash: Option<TransactionHash>,
+    receipt: Option<HardHatReceipt>,
     #[serde(flatten)]
     contract: Contract,
 }
 
+#[derive(Deserialize, Serialize)]
+struct HardHatReceipt {
+    #[serde(rename = "blockNumber")]
+    block_number: Option<u64>,
+}
+
+/// A contract deployment in the format of hardhat-deploy's `deployments`
+/// directory. Exports only contain the address, ABI and deployment information
+/// of contracts, so code and documentation are optional.
+#[derive(Serialize)]
+struct HardHatDeployment<'a> {
+    address: Address,
+    #[serde(rename = "transactionHash", skip_serializing_if = "Option::is_none")]
+    transaction_hash: Option<TransactionHash>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    receipt: Option<HardHatReceipt>,
+    abi: &'a Abi,
+    #[serde(flatten)]
+    code: Option<HardHatCode<'a>>,
+}
+
+/// The code and documentation of a deployed contract.
+#[derive(Serialize)]
+struct HardHatCode<'a> {
+    bytecode: &'a Bytecode,
+    #[serde(rename = "deployedBytecode")]
+    deployed_bytecode: &'a Bytecode,
+    #[serde(rename = "linkReferences")]
+    link_references: &'a LinkReferences,
+    #[serde(rename = "deployedLinkReferences")]
+    deployed_link_references: &'a LinkReferences,
+    devdoc: &'a Documentation,
+    userdoc: &'a Documentation,
+}
+
+impl<'a> HardHatDeployment<'a> {
+    fn new(contract: &'a Contract, chain_id: &str) -> Result<Self, ArtifactError> {
+        let network = contract.networks.get(chain_id).ok_or_else(|| {
+            ArtifactError::NotDeployed(contract.name.clone(), chain_id.to_string())
+        })?;
+        let (transaction_hash, receipt) = match network.deployment_information {
+            Some(DeploymentInformation::TransactionHash(hash)) => (Some(hash), None),
+            Some(DeploymentInformation::BlockNumber(block)) => (
+                None,
+                Some(HardHatReceipt {
+                    block_number: Some(block),
+                }),
+            ),
+            None => (None, None),
+        };
+
+        Ok(HardHatDeployment {
+            address: network.address,
+            transaction_hash,
+            receipt,
+            abi: &contract.abi,
+            code: Some(HardHatCode {
+                bytecode: &contract.bytecode,
+                deployed_bytecode: &contract.deployed_bytecode,
+                link_references: &contract.link_references,
+                deployed_link_references: &contract.deployed_link_references,
+                devdoc: &contract.devdoc,
+                userdoc: &contract.userdoc,
+            }),
+        })
+    }
+
+    /// Removes the contract code and documentation from the deployment, as
+    /// they are not part of hardhat exports.
+    fn without_code(self) -> Self {
+        HardHatDeployment { code: None, ..self }
+    }
+}
+
+/// A hardhat export for serialization.
+#[derive(Serialize)]
+struct HardHatExportRef<'a> {
+    name: &'a str,
+    #[serde(rename = "chainId")]
+    chain_id: &'a str,
+    contracts: BTreeMap<&'a str, HardHatDeployment<'a>>,
+}
+
+impl<'a> HardHatExportRef<'a> {
+    fn new(artifact: &'a Artifact, network: &'a HardHatNetwork) -> Result<Self, ArtifactError> {
+        let contracts = artifact
+            .iter()
+            .filter(|contract| contract.networks.contains_key(&network.chain_id))
+            .map(|contract| {
+                let deployment = HardHatDeployment::new(contract, &network.chain_id)?;
+                Ok((contract.name.as_str(), deployment.without_code()))
+            })
+            .collect::<Result<_, ArtifactError>>()?;
+
+        Ok(HardHatExportRef {
+            name: &network.name,
+            chain_id: &network.chain_id,
+            contracts,
+        })
+    }
+}
+
+/// Returns the deployment information of a hardhat contract, preferring the
+/// transaction hash over the block number from the deployment receipt.
+fn deployment_information(
+    transaction_hash: Option<TransactionHash>,
+    receipt: Option<HardHatReceipt>,
+) -> Option<DeploymentInformation> {
+    transaction_hash
+        .map(DeploymentInformation::TransactionHash)
+        .or_else(|| {
+            receipt
+                .and_then(|receipt| receipt.block_number)
+                .map(DeploymentInformation::BlockNumber)
+        })
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -961,6 +1228,7 @@ mod test {
 
         let a = artifact.get("DeployedContract").unwrap();
         assert_eq!(a.name, "DeployedContract");
+        assert!(!a.deployed_bytecode.is_empty());
         assert_eq!(a.networks.len(), 2);
         assert_eq!(
             a.networks["4"].address,
@@ -991,4 +1259,169 @@ mod test {
             ))
         );
     }
+
+    #[test]
+    fn save_deployment_round_trip() {
+        let mut contract = Contract::with_name("A");
+        for (chain_id, deployment_information) in &[
+            (
+                "1",
+                Some(DeploymentInformation::TransactionHash(H256::repeat_byte(1))),
+            ),
+            ("4", Some(DeploymentInformation::BlockNumber(42))),
+            ("5", None),
+        ] {
+            contract.networks.insert(
+                chain_id.to_string(),
+                Network {
+                    address: address(0xA),
+                    deployment_information: *deployment_information,
+                },
+            );
+        }
+
+        for chain_id in &["1", "4", "5"] {
+            let deployment = HardHatLoader::save_deployment_to_string(&contract, chain_id).unwrap();
+            let json = format!(
+                r#"{{"name": "network", "chainId": "{}", "contracts": {{"A": {}}}}}"#,
+                chain_id, deployment
+            );
+
+            let artifact = HardHatLoader::new()
+                .load_from_str(Format::SingleExport, &json)
+                .unwrap();
+
+            let a = artifact.get("A").unwrap();
+            assert_eq!(a.abi, contract.abi);
+            assert_eq!(a.networks[*chain_id].address, address(0xA));
+            assert_eq!(
+                a.networks[*chain_id].deployment_information,
+                contract.networks[*chain_id].deployment_information
+            );
+        }
+
+        assert!(matches!(
+            HardHatLoader::save_deployment_to_string(&contract, "100"),
+            Err(ArtifactError::NotDeployed(name, chain_id)) if name == "A" && chain_id == "100"
+        ));
+    }
+
+    fn deployed_artifact() -> Artifact {
+        let mut artifact = Artifact::new();
+        for (name, chain_id, address_byte, deployment_information) in &[
+            ("A", "1", 0xA, Some(H256::repeat_byte(1).into())),
+            ("A", "4", 0xAA, Some(42.into())),
+            ("B", "1", 0xB, None),
+        ] {
+            let mut contract = Contract::with_name(*name);
+            contract.abi.receive = true;
+            artifact
+                .record_deployment(
+                    &contract,
+                    *chain_id,
+                    Network {
+                        address: address(*address_byte),
+                        deployment_information: *deployment_information,
+                    },
+                )
+                .unwrap();
+        }
+        artifact
+    }
+
+    fn assert_same_deployments(loaded: &Artifact, expected: &Artifact, chain_ids: &[&str]) {
+        for contract in expected.iter() {
+            let networks = contract
+                .networks
+                .iter()
+                .filter(|(chain_id, _)| chain_ids.contains(&chain_id.as_str()))
+                .collect::<Vec<_>>();
+            if networks.is_empty() {
+                assert!(!loaded.contains(&contract.name));
+                continue;
+            }
+
+            let loaded = loaded.get(&contract.name).unwrap();
+            assert_eq!(loaded.abi, contract.abi);
+            assert_eq!(loaded.networks.len(), networks.len());
+            for (chain_id, network) in networks {
+                assert_eq!(loaded.networks[chain_id].address, network.address);
+                assert_eq!(
+                    loaded.networks[chain_id].deployment_information,
+                    network.deployment_information
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn save_single_round_trip() {
+        let artifact = deployed_artifact();
+
+        for chain_id in &["1", "4"] {
+            let networks = [HardHatNetwork::new("network", *chain_id)];
+            let json =
+                HardHatLoader::save_to_string(Format::SingleExport, &artifact, &networks).unwrap();
+            let loaded = HardHatLoader::new()
+                .load_from_str(Format::SingleExport, &json)
+                .unwrap();
+
+            assert_same_deployments(&loaded, &artifact, &[chain_id]);
+        }
+
+        assert!(matches!(
+            HardHatLoader::save_to_string(Format::SingleExport, &artifact, &[]),
+            Err(ArtifactError::SingleExportNetworks(0))
+        ));
+    }
+
+    #[test]
+    fn save_multi_round_trip() {
+        let artifact = deployed_artifact();
+        let networks = [
+            HardHatNetwork::new("mainnet", "1"),
+            HardHatNetwork::new("rinkeby", "4"),
+        ];
+
+        let json =
+            HardHatLoader::save_to_string(Format::MultiExport, &artifact, &networks).unwrap();
+        let loaded = HardHatLoader::new()
+            .load_from_str(Format::MultiExport, &json)
+            .unwrap();
+        assert_same_deployments(&loaded, &artifact, &["1", "4"]);
+
+        let loaded = HardHatLoader::new()
+            .allow_network_by_name("rinkeby")
+            .load_from_str(Format::MultiExport, &json)
+            .unwrap();
+        assert_same_deployments(&loaded, &artifact, &["4"]);
+    }
+
+    #[test]
+    fn save_directory_round_trip() {
+        let mut artifact = deployed_artifact();
+        *artifact.get_mut("A").unwrap().deployed_bytecode_mut() =
+            Bytecode::from_hex_str("0x6080").unwrap();
+        let networks = [
+            HardHatNetwork::new("mainnet", "1"),
+            HardHatNetwork::new("rinkeby", "4"),
+        ];
+
+        let dir = tempfile::tempdir().unwrap();
+        HardHatLoader::save_to_directory(&artifact, &networks, dir.path()).unwrap();
+        let loaded = HardHatLoader::new()
+            .load_from_directory(dir.path())
+            .unwrap();
+        assert_same_deployments(&loaded, &artifact, &["1", "4"]);
+        assert_eq!(
+            loaded
+                .get("A")
+                .unwrap()
+                .deployed_bytecode
+                .to_bytes()
+                .unwrap()
+                .0,
+            [0x60, 0x80]
+        );
+    }
 }
@@ -0,0 +1,581 @@
+//! Implements loading the output of the `solc` compiler.
+//!
+//! There are two supported output formats.
+//!
+//! First is the standard JSON output, generated with `solc --standard-json`.
+//! It contains contracts keyed by source file and contract name. We parse the
+//! following fields of each contract:
+//!
+//! - `abi`: information about contract's interface, custom errors are ignored;
+//! - `evm.bytecode`: contract's compiled bytecode and locations of libraries
+//!   in it (optional);
+//! - `evm.deployedBytecode`: contract's compiled runtime bytecode, and
+//!   locations of libraries and immutables in it (optional);
+//! - `devdoc`, `userdoc`: natspec documentation (optional).
+//!
+//! Compiler errors in the output result in an error when loading it.
+//!
+//! Second is the combined JSON output, generated with
+//! `solc --combined-json abi,bin,bin-runtime,devdoc,userdoc`. It contains
+//! contracts keyed by `file:Name`, and we parse the `abi`, `bin`,
+//! `bin-runtime`, `devdoc` and `userdoc` fields. Since combined JSON does not
+//! contain link references, they are computed from the library placeholders
+//! of the contracts in the output.
+//!
+//! Contracts are named by their contract name, without the source file.
+//! Contracts with the same name in different source files must have the same
+//! ABI, in which case only one of them is loaded. Compiler output contains no
+//! deployment information.
+
+use crate::artifact::{deserialize_abi, Artifact};
+use crate::bytecode::library_placeholder;
+use crate::contract::{Documentation, ImmutableReference, LinkReference, LinkReferences};
+use crate::errors::ArtifactError;
+use crate::{Abi, Bytecode, Contract};
+use serde::de::Error as _;
+use serde::{Deserialize, Deserializer};
+use serde_json::{from_reader, from_slice, from_str, from_value, Value};
+use std::collections::{BTreeMap, HashMap};
+use std::fs::File;
+use std::io::{BufReader, Read};
+use std::path::Path;
+
+/// Loads `solc` compiler output.
+pub struct SolcLoader {
+    /// Override for artifact's origin. If `None`, origin
+    /// will be derived automatically.
+    pub origin: Option<String>,
+
+    /// List of allowed contract names.
+    ///
+    /// When loading artifact, loader will only load contracts if their names
+    /// are present in this list.
+    ///
+    /// Empty list means that all contracts are allowed.
+    pub contracts_allow_list: Vec<String>,
+
+    /// List of denied contract names.
+    ///
+    /// When loading artifact, loader will not load contracts if their names
+    /// are present in this list.
+    ///
+    /// Empty list means that no contracts are denied.
+    ///
+    /// Deny list takes precedence over allow list. That is, if contract
+    /// appears in both, it will be denied.
+    pub contracts_deny_list: Vec<String>,
+}
+
+impl SolcLoader {
+    /// Creates a new solc loader.
+    pub fn new() -> Self {
+        SolcLoader {
+            origin: None,
+            contracts_allow_list: Vec::new(),
+            contracts_deny_list: Vec::new(),
+        }
+    }
+
+    /// Creates a new solc loader and sets an override for artifact's origins.
+    pub fn with_origin(origin: impl Into<String>) -> Self {
+        SolcLoader {
+            origin: Some(origin.into()),
+            contracts_allow_list: Vec::new(),
+            contracts_deny_list: Vec::new(),
+        }
+    }
+
+    /// Sets new override for artifact's origin. See [`origin`] for more info.
+    ///
+    /// [`origin`]: #structfield.origin
+    pub fn origin(mut self, origin: impl Into<String>) -> Self {
+        self.origin = Some(origin.into());
+        self
+    }
+
+    /// Adds contract name to the list of [`allowed contracts`].
+    ///
+    /// [`allowed contracts`]: #structfield.contracts_allow_list
+    pub fn allow_contract(mut self, contract: impl Into<String>) -> Self {
+        self.contracts_allow_list.push(contract.into());
+        self
+    }
+
+    /// Adds contract name to the list of [`denied contracts`].
+    ///
+    /// [`denied contracts`]: #structfield.contracts_deny_list
+    pub fn deny_contract(mut self, contract: impl Into<String>) -> Self {
+        self.contracts_deny_list.push(contract.into());
+        self
+    }
+
+    /// Loads an artifact from a JSON value.
+    pub fn load_from_reader(&self, f: Format, v: impl Read) -> Result<Artifact, ArtifactError> {
+        self.load_artifact(f, "<unknown>", v, from_reader, from_reader)
+    }
+
+    /// Loads an artifact from bytes of JSON text.
+    pub fn load_from_slice(&self, f: Format, v: &[u8]) -> Result<Artifact, ArtifactError> {
+        self.load_artifact(f, "<unknown>", v, from_slice, from_slice)
+    }
+
+    /// Loads an artifact from string of JSON text.
+    pub fn load_from_str(&self, f: Format, v: &str) -> Result<Artifact, ArtifactError> {
+        self.load_artifact(f, "<unknown>", v, from_str, from_str)
+    }
+
+    /// Loads an artifact from a loaded JSON value.
+    pub fn load_from_value(&self, f: Format, v: Value) -> Result<Artifact, ArtifactError> {
+        self.load_artifact(f, "<unknown>", v, from_value, from_value)
+    }
+
+    /// Loads an artifact from disk.
+    pub fn load_from_file(
+        &self,
+        f: Format,
+        p: impl AsRef<Path>,
+    ) -> Result<Artifact, ArtifactError> {
+        let path = p.as_ref();
+        let file = File::open(path)?;
+        let reader = BufReader::new(file);
+        self.load_artifact(f, path.display(), reader, from_reader, from_reader)
+    }
+
+    fn load_artifact<T>(
+        &self,
+        format: Format,
+        origin: impl ToString,
+        source: T,
+        standard_json_loader: impl FnOnce(T) -> serde_json::Result<StandardJsonOutput>,
+        combined_json_loader: impl FnOnce(T) -> serde_json::Result<CombinedJsonOutput>,
+    ) -> Result<Artifact, ArtifactError> {
+        let origin = self.origin.clone().unwrap_or_else(|| origin.to_string());
+
+        let mut artifact = Artifact::with_origin(origin);
+
+        let contracts = match format {
+            Format::StandardJson => standard_json_loader(source)?.into_contracts()?,
+            Format::CombinedJson => combined_json_loader(source)?.into_contracts()?,
+        };
+        for contract in contracts {
+            if !self.contract_allowed(&contract.name) {
+                continue;
+            }
+
+            match artifact.get(&contract.name) {
+                Some(existing_contract) if existing_contract.abi != contract.abi => {
+                    return Err(ArtifactError::DuplicateContract(contract.name));
+                }
+                Some(_) => continue,
+                None => {
+                    artifact.insert(contract);
+                }
+            }
+        }
+
+        Ok(artifact)
+    }
+
+    fn contract_allowed(&self, name: &str) -> bool {
+        !self.contract_explicitly_denied(name)
+            && (self.contracts_allow_list.is_empty() || self.contract_explicitly_allowed(name))
+    }
+
+    fn contract_explicitly_allowed(&self, name: &str) -> bool {
+        self.contracts_allow_list.iter().any(|x| x == name)
+    }
+
+    fn contract_explicitly_denied(&self, name: &str) -> bool {
+        self.contracts_deny_list.iter().any(|x| x == name)
+    }
+}
+
+impl Default for SolcLoader {
+    fn default() -> Self {
+        SolcLoader::new()
+    }
+}
+
+/// Compiler output format.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Format {
+    /// Standard JSON output. Generated with `solc --standard-json`.
+    StandardJson,
+
+    /// Combined JSON output. Generated with `solc --combined-json`.
+    CombinedJson,
+}
+
+#[derive(Deserialize)]
+struct StandardJsonOutput {
+    #[serde(default)]
+    errors: Vec<StandardJsonError>,
+    #[serde(default)]
+    contracts: BTreeMap<String, BTreeMap<String, StandardJsonContract>>,
+}
+
+impl StandardJsonOutput {
+    fn into_contracts(self) -> Result<Vec<Contract>, ArtifactError> {
+        let errors = self
+            .errors
+            .into_iter()
+            .filter(|error| error.severity == "error")
+            .map(|error| error.formatted_message.unwrap_or(error.message))
+            .collect::<Vec<_>>();
+        if !errors.is_empty() {
+            return Err(ArtifactError::CompilerErrors(errors.join("\n")));
+        }
+
+        Ok(self
+            .contracts
+            .into_values()
+            .flatten()
+            .map(|(name, contract)| contract.into_contract(name))
+            .collect())
+    }
+}
+
+#[derive(Deserialize)]
+struct StandardJsonError {
+    severity: String,
+    message: String,
+    #[serde(rename = "formattedMessage")]
+    formatted_message: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct StandardJsonContract {
+    #[serde(deserialize_with = "deserialize_abi", default = "empty_abi")]
+    abi: Abi,
+    #[serde(default)]
+    evm: StandardJsonEvm,
+    #[serde(default)]
+    devdoc: Documentation,
+    #[serde(default)]
+    userdoc: Documentation,
+}
+
+impl StandardJsonContract {
+    fn into_contract(self, name: String) -> Contract {
+        let mut contract = Contract::with_name(name);
+        contract.abi = self.abi;
+        contract.bytecode = self.evm.bytecode.object;
+        contract.link_references = self.evm.bytecode.link_references;
+        contract.deployed_bytecode = self.evm.deployed_bytecode.object;
+        contract.deployed_link_references = self.evm.deployed_bytecode.link_references;
+        contract.immutable_references = self.evm.deployed_bytecode.immutable_references;
+        contract.devdoc = self.devdoc;
+        contract.userdoc = self.userdoc;
+        contract
+    }
+}
+
+#[derive(Default, Deserialize)]
+#[serde(default)]
+struct StandardJsonEvm {
+    bytecode: StandardJsonBytecode,
+    #[serde(rename = "deployedBytecode")]
+    deployed_bytecode: StandardJsonBytecode,
+}
+
+#[derive(Default, Deserialize)]
+#[serde(default)]
+struct StandardJsonBytecode {
+    object: Bytecode,
+    #[serde(rename = "linkReferences")]
+    link_references: LinkReferences,
+    #[serde(rename = "immutableReferences")]
+    immutable_references: HashMap<String, Vec<ImmutableReference>>,
+}
+
+#[derive(Deserialize)]
+struct CombinedJsonOutput {
+    contracts: BTreeMap<String, CombinedJsonContract>,
+}
+
+impl CombinedJsonOutput {
+    fn into_contracts(self) -> Result<Vec<Contract>, ArtifactError> {
+        // NOTE: Library placeholders are keyed by the fully qualified library
+        //   name, which is also how contracts are keyed in combined JSON.
+        let placeholders = self
+            .contracts
+            .keys()
+            .filter_map(|name| {
+                let (path, library) = name.rsplit_once(':')?;
+                Some((library_placeholder(name), path, library))
+            })
+            .collect::<Vec<_>>();
+        let link_references = |code: &str| {
+            let mut link_references = LinkReferences::new();
+            for (placeholder, path, library) in &placeholders {
+                let references = code
+                    .match_indices(placeholder.as_str())
+                    .map(|(offset, _)| LinkReference {
+                        start: offset / 2,
+                        length: 20,
+                    })
+                    .collect::<Vec<_>>();
+                if !references.is_empty() {
+                    link_references
+                        .entry(path.to_string())
+                        .or_default()
+                        .insert(library.to_string(), references);
+                }
+            }
+            link_references
+        };
+
+        self.contracts
+            .iter()
+            .map(|(name, contract)| {
+                let name = name.rsplit(':').next().unwrap_or(name);
+                let bin = contract.bin.strip_prefix("0x").unwrap_or(&contract.bin);
+                let bin_runtime = contract
+                    .bin_runtime
+                    .strip_prefix("0x")
+                    .unwrap_or(&contract.bin_runtime);
+
+                let mut result = Contract::with_name(name);
+                result.abi = contract.abi.clone();
+                result.bytecode = Bytecode::from_hex_str(bin).map_err(serde_json::Error::custom)?;
+                result.link_references = link_references(bin);
+                result.deployed_bytecode =
+                    Bytecode::from_hex_str(bin_runtime).map_err(serde_json::Error::custom)?;
+                result.deployed_link_references = link_references(bin_runtime);
+                result.devdoc = contract.devdoc.clone();
+                result.userdoc = contract.userdoc.clone();
+                Ok(result)
+            })
+            .collect::<Result<_, serde_json::Error>>()
+            .map_err(ArtifactError::from)
+    }
+}
+
+#[derive(Deserialize)]
+struct CombinedJsonContract {
+    #[serde(deserialize_with = "deserialize_abi", default = "empty_abi")]
+    abi: Abi,
+    #[serde(default)]
+    bin: String,
+    #[serde(rename = "bin-runtime", default)]
+    bin_runtime: String,
+    #[serde(deserialize_with = "deserialize_documentation", default)]
+    devdoc: Documentation,
+    #[serde(deserialize_with = "deserialize_documentation", default)]
+    userdoc: Documentation,
+}
+
+fn empty_abi() -> Abi {
+    Contract::empty().abi
+}
+
+/// Deserializes natspec documentation, which is encoded as a JSON string by
+/// older compiler versions.
+fn deserialize_documentation<'de, D>(deserializer: D) -> Result<Documentation, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    match Value::deserialize(deserializer)? {
+        Value::String(json) => from_str(&json),
+        value => from_value(value),
+    }
+    .map_err(D::Error::custom)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const STANDARD_JSON: &str = r#"
+      {
+        "errors": [
+          {
+            "severity": "warning",
+            "message": "Unused local variable.",
+            "formattedMessage": "Warning: Unused local variable."
+          }
+        ],
+        "sources": {
+          "contracts/Math.sol": { "id": 0 },
+          "contracts/Token.sol": { "id": 1 }
+        },
+        "contracts": {
+          "contracts/Math.sol": {
+            "Math": {
+              "abi": [],
+              "evm": {
+                "bytecode": { "object": "6080", "linkReferences": {} },
+                "deployedBytecode": { "object": "6080", "linkReferences": {} }
+              }
+            }
+          },
+          "contracts/Token.sol": {
+            "IToken": {
+              "abi": [
+                {
+                  "type": "function",
+                  "name": "balanceOf",
+                  "inputs": [{ "name": "owner", "type": "address" }],
+                  "outputs": [{ "name": "", "type": "uint256" }],
+                  "stateMutability": "view"
+                }
+              ],
+              "evm": {
+                "bytecode": { "object": "", "linkReferences": {} },
+                "deployedBytecode": { "object": "", "linkReferences": {} }
+              }
+            },
+            "Token": {
+              "abi": [
+                {
+                  "type": "function",
+                  "name": "balanceOf",
+                  "inputs": [{ "name": "owner", "type": "address" }],
+                  "outputs": [{ "name": "", "type": "uint256" }],
+                  "stateMutability": "view"
+                },
+                { "type": "error", "name": "Unauthorized", "inputs": [] }
+              ],
+              "devdoc": {
+                "kind": "dev",
+                "methods": {
+                  "balanceOf(address)": { "details": "Returns the balance." }
+                },
+                "version": 1
+              },
+              "userdoc": { "kind": "user", "methods": {}, "version": 1 },
+              "evm": {
+                "bytecode": {
+                  "object": "73__$6e1ecd3f8fa8dc9d0ecc0d0ca8a34e7e01$__00",
+                  "linkReferences": {
+                    "contracts/Math.sol": { "Math": [{ "start": 1, "length": 20 }] }
+                  }
+                },
+                "deployedBytecode": {
+                  "object": "7f00000000000000000000000000000000000000000000000000000000000000000000",
+                  "linkReferences": {},
+                  "immutableReferences": { "3": [{ "start": 1, "length": 32 }] }
+                }
+              }
+            }
+          }
+        }
+      }
+    "#;
+
+    #[test]
+    fn load_standard_json() {
+        let artifact = SolcLoader::new()
+            .load_from_str(Format::StandardJson, STANDARD_JSON)
+            .unwrap();
+
+        assert_eq!(artifact.len(), 3);
+
+        let token = artifact.get("Token").unwrap();
+        assert!(token.abi.function("balanceOf").is_ok());
+        assert!(token.bytecode.requires_linking());
+        assert_eq!(
+            token.link_references["contracts/Math.sol"]["Math"],
+            [LinkReference {
+                start: 1,
+                length: 20
+            }]
+        );
+        assert_eq!(token.immutable_references["3"][0].length, 32);
+        assert_eq!(
+            token.devdoc.methods["balanceOf(address)"]
+                .details
+                .as_deref(),
+            Some("Returns the balance.")
+        );
+
+        let interface = artifact.get("IToken").unwrap();
+        assert!(interface.bytecode.is_empty());
+    }
+
+    #[test]
+    fn load_standard_json_allow_deny_contract_name() {
+        let artifact = SolcLoader::new()
+            .allow_contract("Token")
+            .allow_contract("Math")
+            .deny_contract("Math")
+            .load_from_str(Format::StandardJson, STANDARD_JSON)
+            .unwrap();
+
+        assert_eq!(artifact.len(), 1);
+        assert!(artifact.contains("Token"));
+    }
+
+    #[test]
+    fn load_standard_json_compiler_errors() {
+        let json = r#"
+          {
+            "errors": [
+              {
+                "severity": "error",
+                "message": "Expected ';' but got '}'",
+                "formattedMessage": "ParserError: Expected ';' but got '}'"
+              }
+            ]
+          }
+        "#;
+
+        let err = SolcLoader::new()
+            .load_from_str(Format::StandardJson, json)
+            .err()
+            .unwrap();
+        assert!(matches!(
+            err,
+            ArtifactError::CompilerErrors(message) if message == "ParserError: Expected ';' but got '}'"
+        ));
+    }
+
+    #[test]
+    fn load_combined_json() {
+        let placeholder = library_placeholder("contracts/Math.sol:Math");
+        let json = serde_json::json!({
+            "contracts": {
+                "contracts/Math.sol:Math": {
+                    "abi": [],
+                    "bin": "6080",
+                    "bin-runtime": "6080",
+                },
+                "contracts/Token.sol:Token": {
+                    // NOTE: Compilers before 0.8 encode the ABI and natspec
+                    //   documentation as JSON strings.
+                    "abi": r#"[{"type":"receive","stateMutability":"payable"}]"#,
+                    "bin": format!("73{}73{}", placeholder, placeholder),
+                    "bin-runtime": "",
+                    "devdoc": r#"{"details":"A token.","methods":{}}"#,
+                    "userdoc": {"methods": {}},
+                },
+            },
+            "version": "0.7.6+commit.7338295f",
+        });
+
+        let artifact = SolcLoader::new()
+            .load_from_value(Format::CombinedJson, json)
+            .unwrap();
+
+        assert_eq!(artifact.len(), 2);
+
+        let token = artifact.get("Token").unwrap();
+        assert!(token.abi.receive);
+        assert_eq!(token.devdoc.details.as_deref(), Some("A token."));
+        assert_eq!(
+            token.link_references["contracts/Math.sol"]["Math"],
+            [
+                LinkReference {
+                    start: 1,
+                    length: 20
+                },
+                LinkReference {
+                    start: 22,
+                    length: 20
+                }
+            ]
+        );
+        assert!(token.deployed_link_references.is_empty());
+        assert!(artifact.get("Math").unwrap().link_references.is_empty());
+    }
+}
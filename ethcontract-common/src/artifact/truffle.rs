@@ -7,15 +7,19 @@
 //! - `contractName`: name of the contract (optional);
 //! - `abi`: information about contract's interface;
 //! - `bytecode`: contract's compiled bytecode (optional);
+//! - `deployedBytecode`: contract's compiled runtime bytecode (optional);
+//! - `immutableReferences`: locations of immutables in the runtime bytecode
+//!   (optional);
 //! - `networks`: info about known contract deployments (optional);
 //! - `devdoc`, `userdoc`: additional documentation for contract's methods.
 
 use crate::artifact::Artifact;
 use crate::errors::ArtifactError;
-use crate::Contract;
-use serde_json::{from_reader, from_slice, from_str, from_value, to_string, Value};
+use crate::{Contract, DeploymentInformation};
+use serde_json::{from_reader, from_slice, from_str, from_value, to_string, to_writer, Value};
+use std::borrow::Cow;
 use std::fs::File;
-use std::io::{BufReader, Read};
+use std::io::{BufReader, BufWriter, Read, Write};
 use std::path::Path;
 
 /// Loads truffle artifacts.
@@ -147,8 +151,29 @@ impl TruffleLoader {
     }
 
     /// Serializes a single contract.
+    ///
+    /// Contract deployments are serialized into `networks`, so recording a
+    /// deployment with [`Artifact::record_deployment`] and saving the contract
+    /// allows loading it as deployed later.
+    ///
+    /// Truffle only records deployments by transaction hash, so deployment
+    /// block numbers are omitted.
     pub fn save_to_string(contract: &Contract) -> Result<String, ArtifactError> {
-        to_string(contract).map_err(Into::into)
+        to_string(&truffle_contract(contract)).map_err(Into::into)
+    }
+
+    /// Serializes a single contract into a writer.
+    pub fn save_to_writer(contract: &Contract, w: impl Write) -> Result<(), ArtifactError> {
+        to_writer(w, &truffle_contract(contract)).map_err(Into::into)
+    }
+
+    /// Serializes a single contract to disk, replacing the file if it exists.
+    pub fn save_to_file(contract: &Contract, p: impl AsRef<Path>) -> Result<(), ArtifactError> {
+        let file = File::create(p)?;
+        let mut writer = BufWriter::new(file);
+        TruffleLoader::save_to_writer(contract, &mut writer)?;
+        writer.flush()?;
+        Ok(())
     }
 }
 
@@ -157,3 +182,70 @@ impl Default for TruffleLoader {
         TruffleLoader::new()
     }
 }
+
+/// Returns the contract with the deployment information that can not be
+/// represented in a truffle artifact removed.
+fn truffle_contract(contract: &Contract) -> Cow<'_, Contract> {
+    let has_block_numbers = contract.networks.values().any(|network| {
+        matches!(
+            network.deployment_information,
+            Some(DeploymentInformation::BlockNumber(_))
+        )
+    });
+    if !has_block_numbers {
+        return Cow::Borrowed(contract);
+    }
+
+    let mut contract = contract.clone();
+    for network in contract.networks.values_mut() {
+        if let Some(DeploymentInformation::BlockNumber(_)) = network.deployment_information {
+            network.deployment_information = None;
+        }
+    }
+    Cow::Owned(contract)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::contract::Network;
+    use crate::TransactionHash;
+    use web3::types::Address;
+
+    #[test]
+    fn save_omits_deployment_block_numbers() {
+        let mut contract = Contract::with_name("C");
+        for (network_id, deployment_information) in &[
+            ("1", DeploymentInformation::BlockNumber(42)),
+            (
+                "2",
+                DeploymentInformation::TransactionHash(TransactionHash::repeat_byte(1)),
+            ),
+        ] {
+            contract.networks.insert(
+                network_id.to_string(),
+                Network {
+                    address: Address::repeat_byte(1),
+                    deployment_information: Some(*deployment_information),
+                },
+            );
+        }
+
+        let json = TruffleLoader::save_to_string(&contract).unwrap();
+        let value: Value = from_str(&json).unwrap();
+        assert!(value["networks"]["1"].get("transactionHash").is_none());
+        assert_eq!(
+            value["networks"]["2"]["transactionHash"],
+            Value::String(format!("0x{}", "01".repeat(32)))
+        );
+
+        let loaded = TruffleLoader::new().load_contract_from_str(&json).unwrap();
+        assert_eq!(loaded.networks["1"].deployment_information, None);
+        assert_eq!(
+            loaded.networks["2"].deployment_information,
+            Some(DeploymentInformation::TransactionHash(
+                TransactionHash::repeat_byte(1)
+            ))
+        );
+    }
+}
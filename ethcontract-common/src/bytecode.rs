@@ -2,7 +2,14 @@
 //! linking. `Bytecode` is represented as a hex string with special placeholders
 //! for libraries that require linking.
 
+pub mod disasm;
+pub mod metadata;
+
+pub use self::disasm::Instruction;
+pub use self::metadata::{strip_metadata, Metadata};
+use crate::contract::{ImmutableReference, LinkReference, LinkReferences};
 use crate::errors::{BytecodeError, LinkError};
+use crate::hash::{self, H32};
 use serde::de::{Error as DeError, Visitor};
 use serde::{Deserialize, Deserializer, Serialize};
 use std::collections::HashSet;
@@ -51,32 +58,100 @@ impl Bytecode {
 
     /// Links a library into the current bytecode.
     ///
-    /// # Panics
-    ///
-    /// Panics if an invalid library name is used (for example if it is more
-    /// than 38 characters long).
+    /// Both hashed `__$<hash>$__` placeholders used by `solc` since version
+    /// 0.5, which are keyed by the fully qualified library name (for example
+    /// `contracts/Math.sol:Math`), and legacy `__Name___` placeholders are
+    /// supported.
     pub fn link<S>(&mut self, name: S, address: Address) -> Result<(), LinkError>
     where
         S: AsRef<str>,
     {
         let name = name.as_ref();
-        if name.len() > 38 {
-            panic!("invalid library name for linking");
-        }
 
         // NOTE(nlordell): solc linking works by string search and replace of
         //   '__$name__..__' with the library address; see generated bytecode for
         //   `LinkedContract` contract for and example of how it looks like
-        let placeholder = format!("__{:_<38}", name);
         let address = to_fixed_hex(&address);
-        if !self.0.contains(&placeholder) {
+        let mut found = false;
+        for placeholder in placeholders(name) {
+            if self.0.contains(&placeholder) {
+                self.0 = self.0.replace(&placeholder, &address);
+                found = true;
+            }
+        }
+        if !found {
             return Err(LinkError::NotFound(name.to_string()));
         }
-        self.0 = self.0.replace(&placeholder, &address);
 
         Ok(())
     }
 
+    /// Links a library into the current bytecode at the specified byte offsets,
+    /// as found in the `linkReferences` of `solc`, Hardhat and Foundry
+    /// artifacts.
+    pub fn link_at(
+        &mut self,
+        references: &[LinkReference],
+        address: Address,
+    ) -> Result<(), LinkError> {
+        let address = to_fixed_hex(&address);
+        for reference in references {
+            let invalid = || LinkError::InvalidReference(reference.start, reference.length);
+            if reference.length != 20 {
+                return Err(invalid());
+            }
+
+            // NOTE: Linked addresses can not be told apart from other code,
+            //   so only references that partially overlap a placeholder are
+            //   rejected as they would leave the bytecode invalid.
+            let start = 2 * reference.start;
+            if start + 40 > self.0.len() {
+                return Err(invalid());
+            }
+            let overlaps = self
+                .placeholder_offsets()
+                .any(|offset| offset != start && offset < start + 40 && start < offset + 40);
+            if overlaps {
+                return Err(invalid());
+            }
+
+            self.0.replace_range(start..start + 40, &address);
+        }
+
+        Ok(())
+    }
+
+    /// Links a library into the current bytecode, using its link references
+    /// if the library can be found in them and falling back to its
+    /// placeholders otherwise.
+    ///
+    /// Libraries are looked up in link references by their fully qualified
+    /// name, or by their name if it is unique across all source files.
+    pub fn link_with_references<S>(
+        &mut self,
+        name: S,
+        address: Address,
+        link_references: &LinkReferences,
+    ) -> Result<(), LinkError>
+    where
+        S: AsRef<str>,
+    {
+        let name = name.as_ref();
+        let (path, library) = match name.rsplit_once(':') {
+            Some((path, library)) => (Some(path), library),
+            None => (None, name),
+        };
+
+        let mut references = link_references
+            .iter()
+            .filter(|(file, _)| path.map(|path| path == *file).unwrap_or(true))
+            .filter_map(|(_, libraries)| libraries.get(library));
+        match (references.next(), references.next()) {
+            (Some(references), None) => self.link_at(references, address),
+            _ => self.link(name, address),
+        }
+    }
+
     /// Converts a bytecode into its byte representation.
     pub fn to_bytes(&self) -> Result<Bytes, LinkError> {
         match self.undefined_libraries().next() {
@@ -86,6 +161,10 @@ impl Bytecode {
     }
 
     /// Returns an iterator over all libraries remaining in the bytecode.
+    ///
+    /// Note that libraries with hashed placeholders are yielded as `$<hash>$`
+    /// since their names cannot be recovered from the bytecode alone; their
+    /// names can be found in the contract's link references instead.
     pub fn undefined_libraries(&self) -> LibIter<'_> {
         LibIter {
             cursor: &self.0,
@@ -102,6 +181,116 @@ impl Bytecode {
     pub fn is_empty(&self) -> bool {
         self.0.is_empty()
     }
+
+    /// Decodes the `solc` metadata trailer of the bytecode. Returns `None` if
+    /// the bytecode does not end with a valid metadata trailer.
+    pub fn metadata(&self) -> Option<Metadata> {
+        metadata::decode_metadata(&hex::decode(self.trailing_code()).expect("valid hex"))
+    }
+
+    /// Returns a copy of the bytecode with the `solc` metadata trailer
+    /// stripped. This allows comparing the code of contracts independently
+    /// from details of the compilation that do not affect the code itself.
+    pub fn without_metadata(&self) -> Bytecode {
+        let code = hex::decode(self.trailing_code()).expect("valid hex");
+        let trailer_len = code.len() - strip_metadata(&code).len();
+        Bytecode(self.0[..self.0.len() - 2 * trailer_len].to_string())
+    }
+
+    /// Returns an iterator over the offsets of link placeholders in the hex
+    /// code.
+    fn placeholder_offsets(&self) -> impl Iterator<Item = usize> + '_ {
+        let mut offset = 0;
+        std::iter::from_fn(move || {
+            let pos = offset + self.0[offset..].find("__")?;
+            offset = pos + 40;
+            Some(pos)
+        })
+    }
+
+    /// Returns the hex code following the last link placeholder.
+    fn trailing_code(&self) -> &str {
+        let mut cursor = self.0.as_str();
+        while let Some(pos) = cursor.find("__") {
+            cursor = &cursor[pos + 40..];
+        }
+        cursor
+    }
+
+    /// Returns true if the specified runtime code, as returned by
+    /// `eth_getCode`, matches this runtime bytecode.
+    ///
+    /// The `solc` metadata trailer is ignored, as it changes with unrelated
+    /// details of the compilation such as source file paths. Unlinked library
+    /// placeholders and the specified immutable references are also ignored,
+    /// since their values are only known once the contract is deployed.
+    pub fn matches_runtime_code<'a, I>(&self, code: &[u8], immutable_references: I) -> bool
+    where
+        I: IntoIterator<Item = &'a ImmutableReference>,
+    {
+        // NOTE: Placeholders and immutables are filled in on deployment, so
+        //   only compare the bytes that are known beforehand.
+        let (expected, mut known) = self.to_masked_bytes();
+        for reference in immutable_references {
+            for known in known
+                .iter_mut()
+                .skip(reference.start)
+                .take(reference.length)
+            {
+                *known = false;
+            }
+        }
+
+        let expected_len = strip_metadata(&expected).len();
+        let code = strip_metadata(code);
+        expected_len == code.len()
+            && expected
+                .iter()
+                .zip(&known)
+                .zip(code)
+                .all(|((expected, known), actual)| !known || expected == actual)
+    }
+
+    /// Disassembles the bytecode into its instructions. Unlinked library
+    /// placeholders are disassembled as zero addresses.
+    pub fn instructions(&self) -> Vec<Instruction> {
+        disasm::disassemble(&self.to_masked_bytes().0).collect()
+    }
+
+    /// Extracts the function selectors from the dispatcher of the bytecode.
+    /// See [`disasm::function_selectors`] for more details.
+    pub fn function_selectors(&self) -> Vec<H32> {
+        disasm::function_selectors(&self.to_masked_bytes().0)
+    }
+
+    /// Converts the bytecode into bytes along with a mask of which bytes are
+    /// known, with unlinked library placeholders being filled with zeros.
+    fn to_masked_bytes(&self) -> (Vec<u8>, Vec<bool>) {
+        let mut bytes = Vec::with_capacity(self.0.len() / 2);
+        let mut known = Vec::with_capacity(self.0.len() / 2);
+        let mut cursor = self.0.as_str();
+        loop {
+            let (block, tail) = match cursor.find("__") {
+                Some(pos) => (&cursor[..pos], Some(&cursor[pos + 40..])),
+                None => (cursor, None),
+            };
+
+            let block = hex::decode(block).expect("valid hex");
+            known.resize(known.len() + block.len(), true);
+            bytes.extend(block);
+
+            match tail {
+                Some(tail) => {
+                    known.resize(known.len() + 20, false);
+                    bytes.resize(bytes.len() + 20, 0);
+                    cursor = tail;
+                }
+                None => break,
+            }
+        }
+
+        (bytes, known)
+    }
 }
 
 /// Internal type for iterating though a bytecode's string code blocks skipping
@@ -183,6 +372,27 @@ impl<'de> Visitor<'de> for BytecodeVisitor {
     }
 }
 
+/// Returns the hashed placeholder used by `solc` since version 0.5 for the
+/// library with the specified fully qualified name.
+pub fn library_placeholder(name: &str) -> String {
+    format!(
+        "__${}$__",
+        &hex::encode(hash::keccak256(name.as_bytes()))[..34]
+    )
+}
+
+/// Returns all placeholders that may be used for the specified library.
+fn placeholders(name: &str) -> impl Iterator<Item = String> {
+    let legacy = if name.len() <= 38 {
+        Some(format!("__{:_<38}", name))
+    } else {
+        None
+    };
+    legacy
+        .into_iter()
+        .chain(std::iter::once(library_placeholder(name)))
+}
+
 fn to_fixed_hex(address: &Address) -> String {
     format!("{:040x}", address)
 }
@@ -249,6 +459,182 @@ mod tests {
         assert_eq!(bytes.0, expected);
     }
 
+    #[test]
+    fn bytecode_link_hashed_placeholder() {
+        let name = "contracts/Math.sol:Math";
+        let placeholder = library_placeholder(name);
+        assert_eq!(
+            library_placeholder("lib.sol:L"),
+            "__$7658e08c4e23aceed01ae97f6c6f1bccc3$__"
+        );
+
+        let mut bytecode =
+            Bytecode::from_hex_str(&format!("0x73{}73{}", placeholder, placeholder)).unwrap();
+        assert_eq!(
+            bytecode.undefined_libraries().collect::<Vec<_>>(),
+            [&placeholder[2..38]]
+        );
+
+        let address = Address::repeat_byte(0x42);
+        bytecode.link(name, address).unwrap();
+        assert_eq!(
+            bytecode.to_bytes().unwrap().0,
+            hex::decode(format!("73{}73{}", "42".repeat(20), "42".repeat(20))).unwrap()
+        );
+    }
+
+    #[test]
+    fn bytecode_link_at_references() {
+        let placeholder = library_placeholder("contracts/Math.sol:Math");
+        let mut bytecode =
+            Bytecode::from_hex_str(&format!("0x73{}73{}", placeholder, "00".repeat(20))).unwrap();
+
+        let references = [
+            LinkReference {
+                start: 1,
+                length: 20,
+            },
+            LinkReference {
+                start: 22,
+                length: 20,
+            },
+        ];
+        bytecode
+            .link_at(&references, Address::repeat_byte(0x42))
+            .unwrap();
+        assert_eq!(
+            bytecode.to_bytes().unwrap().0,
+            hex::decode(format!("73{}73{}", "42".repeat(20), "42".repeat(20))).unwrap()
+        );
+
+        for (start, length) in &[(0, 20), (1, 32), (23, 20)] {
+            let mut bytecode =
+                Bytecode::from_hex_str(&format!("0x73{}73{}", placeholder, "00".repeat(20)))
+                    .unwrap();
+            let reference = LinkReference {
+                start: *start,
+                length: *length,
+            };
+            assert!(matches!(
+                bytecode.link_at(&[reference], Address::zero()),
+                Err(LinkError::InvalidReference(..))
+            ));
+        }
+    }
+
+    #[test]
+    fn bytecode_link_with_references() {
+        let name = "contracts/Math.sol:Math";
+        let placeholder = library_placeholder(name);
+        let link_references = [(
+            "contracts/Math.sol".to_string(),
+            [(
+                "Math".to_string(),
+                vec![LinkReference {
+                    start: 1,
+                    length: 20,
+                }],
+            )]
+            .iter()
+            .cloned()
+            .collect(),
+        )]
+        .iter()
+        .cloned()
+        .collect::<LinkReferences>();
+
+        for name in &[name, "Math"] {
+            let mut bytecode = Bytecode::from_hex_str(&format!("0x73{}", placeholder)).unwrap();
+            bytecode
+                .link_with_references(name, Address::repeat_byte(0x42), &link_references)
+                .unwrap();
+            assert!(!bytecode.requires_linking());
+        }
+
+        let mut bytecode = Bytecode::from_hex_str(&format!("0x73{}", placeholder)).unwrap();
+        bytecode
+            .link_with_references(name, Address::repeat_byte(0x42), &LinkReferences::new())
+            .unwrap();
+        assert!(!bytecode.requires_linking());
+        assert!(matches!(
+            bytecode.link_with_references("Other", Address::zero(), &link_references),
+            Err(LinkError::NotFound(_))
+        ));
+    }
+
+    #[test]
+    fn strip_metadata_trailer() {
+        let code = hex::decode("6080a164736f6c6343000706000a").unwrap();
+        assert_eq!(strip_metadata(&code), &[0x60, 0x80]);
+
+        let code = hex::decode("608060400002").unwrap();
+        assert_eq!(strip_metadata(&code), &code[..]);
+        assert_eq!(strip_metadata(&[0x00]), &[0x00]);
+    }
+
+    #[test]
+    fn bytecode_metadata() {
+        let placeholder = format!("__{:_<38}", "name");
+        let bytecode =
+            Bytecode::from_hex_str(&format!("0x73{}fea164736f6c6343000706000a", placeholder))
+                .unwrap();
+
+        assert_eq!(
+            bytecode.metadata().and_then(|metadata| metadata.solc),
+            Some(metadata::CompilerVersion::Release(0, 7, 6))
+        );
+        assert_eq!(
+            bytecode.without_metadata().0,
+            format!("73{}fe", placeholder)
+        );
+
+        let bytecode = Bytecode::from_hex_str("0x6080").unwrap();
+        assert_eq!(bytecode.metadata(), None);
+        assert_eq!(bytecode.without_metadata().0, "6080");
+    }
+
+    #[test]
+    fn bytecode_function_selectors() {
+        let placeholder = format!("__{:_<38}", "name");
+        let bytecode =
+            Bytecode::from_hex_str(&format!("0x73{}8063a9059cbb1400", placeholder)).unwrap();
+
+        assert_eq!(bytecode.instructions()[0].push_data, [0; 20]);
+        assert_eq!(
+            bytecode.function_selectors(),
+            [crate::hash::function_selector("transfer(address,uint256)")]
+        );
+    }
+
+    #[test]
+    fn matches_runtime_code_ignores_metadata_and_immutables() {
+        let placeholder = format!("__{:_<38}", "name");
+        let bytecode = Bytecode::from_hex_str(&format!(
+            "0x73{}7f{}00a16469706673582000000000000000000000000000000000000000000000000000000000000000000028",
+            placeholder,
+            "00".repeat(32),
+        ))
+        .unwrap();
+        let immutables = [ImmutableReference {
+            start: 22,
+            length: 32,
+        }];
+
+        let code = hex::decode(format!(
+            "73{}7f{}00a16469706673582011111111111111111111111111111111111111111111111111111111111111110028",
+            "42".repeat(20),
+            "13".repeat(32),
+        ))
+        .unwrap();
+        assert!(bytecode.matches_runtime_code(&code, &immutables));
+        assert!(!bytecode.matches_runtime_code(&code, &[]));
+
+        let mut mismatched = code.clone();
+        mismatched[0] = 0x74;
+        assert!(!bytecode.matches_runtime_code(&mismatched, &immutables));
+        assert!(!bytecode.matches_runtime_code(&code[1..], &immutables));
+    }
+
     #[test]
     fn bytecode_link_fail() {
         let address = Address::zero();
@@ -0,0 +1,320 @@
+//! This module implements a simple EVM bytecode disassembler along with a
+//! heuristic for extracting the function selectors of a contract from its
+//! dispatcher code. This is useful for inspecting contracts for which no
+//! artifacts are available.
+
+use crate::bytecode::metadata::strip_metadata;
+use crate::hash::H32;
+use std::fmt::{self, Display, Formatter};
+
+/// A disassembled EVM instruction.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Instruction {
+    /// The byte offset of the instruction in the code.
+    pub offset: usize,
+    /// The instruction opcode.
+    pub opcode: u8,
+    /// The immediate data of `PUSH` instructions. This can be shorter than the
+    /// size of the `PUSH` for truncated instructions at the end of the code.
+    pub push_data: Vec<u8>,
+}
+
+impl Instruction {
+    /// Returns the mnemonic of the instruction opcode, or `None` for undefined
+    /// opcodes.
+    pub fn mnemonic(&self) -> Option<&'static str> {
+        mnemonic(self.opcode)
+    }
+
+    /// Returns true if the instruction is a `PUSH` instruction with immediate
+    /// data.
+    pub fn is_push(&self) -> bool {
+        push_size(self.opcode) > 0
+    }
+}
+
+impl Display for Instruction {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(f, "{:#06x}: ", self.offset)?;
+        match self.mnemonic() {
+            Some(mnemonic) => f.write_str(mnemonic)?,
+            None => write!(f, "UNKNOWN({:#04x})", self.opcode)?,
+        }
+        if self.is_push() {
+            write!(f, " 0x{}", hex::encode(&self.push_data))?;
+        }
+        Ok(())
+    }
+}
+
+/// An iterator over the instructions of EVM code.
+pub struct Instructions<'a> {
+    code: &'a [u8],
+    offset: usize,
+}
+
+impl Iterator for Instructions<'_> {
+    type Item = Instruction;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let opcode = *self.code.get(self.offset)?;
+        let offset = self.offset;
+        let data_start = (offset + 1).min(self.code.len());
+        let data_end = (data_start + push_size(opcode)).min(self.code.len());
+        self.offset = offset + 1 + push_size(opcode);
+
+        Some(Instruction {
+            offset,
+            opcode,
+            push_data: self.code[data_start..data_end].to_vec(),
+        })
+    }
+}
+
+/// Disassembles EVM code into its instructions.
+pub fn disassemble(code: &[u8]) -> Instructions<'_> {
+    Instructions { code, offset: 0 }
+}
+
+/// Extracts the function selectors from the dispatcher of a contract's
+/// runtime code.
+///
+/// This is a heuristic that looks for the `PUSH4 selector` followed by an
+/// `EQ` comparison, optionally with a `DUP` in between, that `solc` generates
+/// for matching the selector from the calldata. Since `solc` pushes constants
+/// with the shortest `PUSH`, selectors with leading zero bytes are pushed with
+/// `PUSH1` to `PUSH3` and are zero-padded accordingly. Selectors are returned
+/// in the order they appear in the code without duplicates. Note that
+/// contracts that were not compiled with `solc` or that use a custom
+/// dispatcher may produce incomplete results.
+pub fn function_selectors(code: &[u8]) -> Vec<H32> {
+    const EQ: u8 = 0x14;
+    const PUSH1: u8 = 0x60;
+    const PUSH4: u8 = 0x63;
+    const DUP1: u8 = 0x80;
+    const DUP16: u8 = 0x8f;
+
+    let instructions = disassemble(strip_metadata(code)).collect::<Vec<_>>();
+    let mut selectors = Vec::<H32>::new();
+    for (i, instruction) in instructions.iter().enumerate() {
+        let size = push_size(instruction.opcode);
+        if !(PUSH1..=PUSH4).contains(&instruction.opcode) || instruction.push_data.len() != size {
+            continue;
+        }
+
+        let mut next = instructions[i + 1..].iter().map(|i| i.opcode);
+        let is_comparison = match next.next() {
+            Some(EQ) => true,
+            Some(DUP1..=DUP16) => next.next() == Some(EQ),
+            _ => false,
+        };
+        if !is_comparison {
+            continue;
+        }
+
+        let mut selector = H32::default();
+        selector[4 - size..].copy_from_slice(&instruction.push_data);
+        if !selectors.contains(&selector) {
+            selectors.push(selector);
+        }
+    }
+
+    selectors
+}
+
+/// Returns the size of the immediate data of an instruction.
+fn push_size(opcode: u8) -> usize {
+    match opcode {
+        0x60..=0x7f => (opcode - 0x5f) as usize,
+        _ => 0,
+    }
+}
+
+/// Returns the mnemonic for an opcode.
+fn mnemonic(opcode: u8) -> Option<&'static str> {
+    const PUSH: [&str; 32] = [
+        "PUSH1", "PUSH2", "PUSH3", "PUSH4", "PUSH5", "PUSH6", "PUSH7", "PUSH8", "PUSH9", "PUSH10",
+        "PUSH11", "PUSH12", "PUSH13", "PUSH14", "PUSH15", "PUSH16", "PUSH17", "PUSH18", "PUSH19",
+        "PUSH20", "PUSH21", "PUSH22", "PUSH23", "PUSH24", "PUSH25", "PUSH26", "PUSH27", "PUSH28",
+        "PUSH29", "PUSH30", "PUSH31", "PUSH32",
+    ];
+    const DUP: [&str; 16] = [
+        "DUP1", "DUP2", "DUP3", "DUP4", "DUP5", "DUP6", "DUP7", "DUP8", "DUP9", "DUP10", "DUP11",
+        "DUP12", "DUP13", "DUP14", "DUP15", "DUP16",
+    ];
+    const SWAP: [&str; 16] = [
+        "SWAP1", "SWAP2", "SWAP3", "SWAP4", "SWAP5", "SWAP6", "SWAP7", "SWAP8", "SWAP9", "SWAP10",
+        "SWAP11", "SWAP12", "SWAP13", "SWAP14", "SWAP15", "SWAP16",
+    ];
+    const LOG: [&str; 5] = ["LOG0", "LOG1", "LOG2", "LOG3", "LOG4"];
+
+    let mnemonic = match opcode {
+        0x00 => "STOP",
+        0x01 => "ADD",
+        0x02 => "MUL",
+        0x03 => "SUB",
+        0x04 => "DIV",
+        0x05 => "SDIV",
+        0x06 => "MOD",
+        0x07 => "SMOD",
+        0x08 => "ADDMOD",
+        0x09 => "MULMOD",
+        0x0a => "EXP",
+        0x0b => "SIGNEXTEND",
+        0x10 => "LT",
+        0x11 => "GT",
+        0x12 => "SLT",
+        0x13 => "SGT",
+        0x14 => "EQ",
+        0x15 => "ISZERO",
+        0x16 => "AND",
+        0x17 => "OR",
+        0x18 => "XOR",
+        0x19 => "NOT",
+        0x1a => "BYTE",
+        0x1b => "SHL",
+        0x1c => "SHR",
+        0x1d => "SAR",
+        0x20 => "SHA3",
+        0x30 => "ADDRESS",
+        0x31 => "BALANCE",
+        0x32 => "ORIGIN",
+        0x33 => "CALLER",
+        0x34 => "CALLVALUE",
+        0x35 => "CALLDATALOAD",
+        0x36 => "CALLDATASIZE",
+        0x37 => "CALLDATACOPY",
+        0x38 => "CODESIZE",
+        0x39 => "CODECOPY",
+        0x3a => "GASPRICE",
+        0x3b => "EXTCODESIZE",
+        0x3c => "EXTCODECOPY",
+        0x3d => "RETURNDATASIZE",
+        0x3e => "RETURNDATACOPY",
+        0x3f => "EXTCODEHASH",
+        0x40 => "BLOCKHASH",
+        0x41 => "COINBASE",
+        0x42 => "TIMESTAMP",
+        0x43 => "NUMBER",
+        0x44 => "DIFFICULTY",
+        0x45 => "GASLIMIT",
+        0x46 => "CHAINID",
+        0x47 => "SELFBALANCE",
+        0x48 => "BASEFEE",
+        0x50 => "POP",
+        0x51 => "MLOAD",
+        0x52 => "MSTORE",
+        0x53 => "MSTORE8",
+        0x54 => "SLOAD",
+        0x55 => "SSTORE",
+        0x56 => "JUMP",
+        0x57 => "JUMPI",
+        0x58 => "PC",
+        0x59 => "MSIZE",
+        0x5a => "GAS",
+        0x5b => "JUMPDEST",
+        0x5f => "PUSH0",
+        0x60..=0x7f => PUSH[(opcode - 0x60) as usize],
+        0x80..=0x8f => DUP[(opcode - 0x80) as usize],
+        0x90..=0x9f => SWAP[(opcode - 0x90) as usize],
+        0xa0..=0xa4 => LOG[(opcode - 0xa0) as usize],
+        0xf0 => "CREATE",
+        0xf1 => "CALL",
+        0xf2 => "CALLCODE",
+        0xf3 => "RETURN",
+        0xf4 => "DELEGATECALL",
+        0xf5 => "CREATE2",
+        0xfa => "STATICCALL",
+        0xfd => "REVERT",
+        0xfe => "INVALID",
+        0xff => "SELFDESTRUCT",
+        _ => return None,
+    };
+
+    Some(mnemonic)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hash::function_selector;
+
+    #[test]
+    fn disassemble_code() {
+        let code = hex::decode("6080604052348015600f57600080fd5b0c61").unwrap();
+        let instructions = disassemble(&code)
+            .map(|instruction| instruction.to_string())
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            instructions,
+            [
+                "0x0000: PUSH1 0x80",
+                "0x0002: PUSH1 0x40",
+                "0x0004: MSTORE",
+                "0x0005: CALLVALUE",
+                "0x0006: DUP1",
+                "0x0007: ISZERO",
+                "0x0008: PUSH1 0x0f",
+                "0x000a: JUMPI",
+                "0x000b: PUSH1 0x00",
+                "0x000d: DUP1",
+                "0x000e: REVERT",
+                "0x000f: JUMPDEST",
+                "0x0010: UNKNOWN(0x0c)",
+                "0x0011: PUSH2 0x",
+            ]
+        );
+    }
+
+    #[test]
+    fn extract_function_selectors_with_leading_zeros() {
+        // NOTE: `balanceOf(address,uint256)` has the selector `0x00fdd58e`,
+        //   which `solc` pushes with a `PUSH3`.
+        let code = hex::decode(concat!(
+            "60003560e01c",
+            "8062fdd58e14601f57",
+            "8063a9059cbb14602157",
+            "00",
+        ))
+        .unwrap();
+
+        assert_eq!(
+            function_selectors(&code),
+            [
+                function_selector("balanceOf(address,uint256)"),
+                function_selector("transfer(address,uint256)"),
+            ]
+        );
+        assert_eq!(
+            function_selector("balanceOf(address,uint256)"),
+            [0x00, 0xfd, 0xd5, 0x8e]
+        );
+    }
+
+    #[test]
+    fn extract_function_selectors() {
+        // NOTE: Dispatcher for `transfer(address,uint256)` and
+        //   `balanceOf(address)` as generated by `solc`, with the selector
+        //   mask `PUSH4 0xffffffff` and a `PUSH4` not used in a comparison.
+        let code = hex::decode(concat!(
+            "60003560e01c",
+            "8063a9059cbb14601f57",
+            "6370a08231811460215780",
+            "63ffffffff16",
+            "63deadbeef50",
+            "63a9059cbb14",
+            "00fe",
+            "a164736f6c6343000706000a",
+        ))
+        .unwrap();
+
+        assert_eq!(
+            function_selectors(&code),
+            [
+                function_selector("transfer(address,uint256)"),
+                function_selector("balanceOf(address)"),
+            ]
+        );
+    }
+}
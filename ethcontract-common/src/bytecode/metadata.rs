@@ -0,0 +1,288 @@
+//! This module implements decoding the CBOR encoded metadata trailer that
+//! `solc` appends to the runtime bytecode of contracts.
+//!
+//! The trailer is a CBOR map followed by its length encoded as a big-endian
+//! 16-bit integer. It contains a hash of the contract metadata JSON, which
+//! changes with details of the compilation that do not affect the code itself,
+//! such as source file paths and comments, and the compiler version.
+
+use std::convert::TryInto;
+use std::fmt::{self, Display, Formatter};
+use web3::types::H256;
+
+/// The metadata appended by `solc` to contract bytecode.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Metadata {
+    /// The IPFS multihash of the contract metadata JSON.
+    pub ipfs: Option<Vec<u8>>,
+    /// The Swarm hash of the contract metadata JSON, used by `solc` versions
+    /// up to `0.5.x` (`bzzr0`) and `0.6.x` (`bzzr1`).
+    pub swarm: Option<H256>,
+    /// The version of the compiler that was used to compile the contract. Only
+    /// included by `solc` starting with version `0.5.9`.
+    pub solc: Option<CompilerVersion>,
+    /// Whether or not experimental compiler features were used.
+    pub experimental: bool,
+}
+
+impl Metadata {
+    /// Returns the IPFS hash of the contract metadata JSON in its base58
+    /// encoded string representation (i.e. `Qm...`).
+    pub fn ipfs_hash(&self) -> Option<String> {
+        self.ipfs.as_deref().map(base58)
+    }
+}
+
+/// The compiler version encoded in the metadata.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum CompilerVersion {
+    /// A release version.
+    Release(u8, u8, u8),
+    /// A pre-release version, encoded as a string in the metadata.
+    PreRelease(String),
+}
+
+impl Display for CompilerVersion {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            CompilerVersion::Release(major, minor, patch) => {
+                write!(f, "{}.{}.{}", major, minor, patch)
+            }
+            CompilerVersion::PreRelease(version) => f.write_str(version),
+        }
+    }
+}
+
+/// Splits runtime code into the code itself and the decoded metadata trailer.
+/// Returns the code unchanged and no metadata if it does not end with a valid
+/// metadata trailer.
+pub fn split_metadata(code: &[u8]) -> (&[u8], Option<Metadata>) {
+    trailer(code)
+        .and_then(|start| {
+            let metadata = decode_map(&code[start..code.len() - 2])?;
+            Some((&code[..start], Some(metadata)))
+        })
+        .unwrap_or((code, None))
+}
+
+/// Decodes the metadata trailer of runtime code. Returns `None` if the code
+/// does not end with a valid metadata trailer.
+pub fn decode_metadata(code: &[u8]) -> Option<Metadata> {
+    split_metadata(code).1
+}
+
+/// Strips the metadata trailer from runtime code. Code without a valid
+/// metadata trailer is returned unchanged.
+pub fn strip_metadata(code: &[u8]) -> &[u8] {
+    split_metadata(code).0
+}
+
+/// Returns the start of the metadata trailer based on the length encoded in the
+/// last two bytes of the code.
+fn trailer(code: &[u8]) -> Option<usize> {
+    let len_start = code.len().checked_sub(2)?;
+    let len = u16::from_be_bytes([code[len_start], code[len_start + 1]]) as usize;
+    len_start.checked_sub(len)
+}
+
+/// Decodes a CBOR map containing the metadata. The map must span exactly the
+/// specified bytes.
+fn decode_map(bytes: &[u8]) -> Option<Metadata> {
+    let mut decoder = Decoder(bytes);
+    let mut metadata = Metadata::default();
+
+    let entries = match decoder.item()? {
+        Item::Map(entries) if entries > 0 => entries,
+        _ => return None,
+    };
+    for _ in 0..entries {
+        let key = match decoder.item()? {
+            Item::Text(key) => key,
+            _ => return None,
+        };
+        match (key, decoder.item()?) {
+            (b"ipfs", Item::Bytes(hash)) => metadata.ipfs = Some(hash.to_vec()),
+            (b"bzzr0", Item::Bytes(hash)) | (b"bzzr1", Item::Bytes(hash)) => {
+                metadata.swarm = Some(H256(hash.try_into().ok()?))
+            }
+            (b"solc", Item::Bytes(&[major, minor, patch])) => {
+                metadata.solc = Some(CompilerVersion::Release(major, minor, patch))
+            }
+            (b"solc", Item::Text(version)) => {
+                let version = std::str::from_utf8(version).ok()?;
+                metadata.solc = Some(CompilerVersion::PreRelease(version.to_owned()))
+            }
+            (b"experimental", Item::Bool(experimental)) => metadata.experimental = experimental,
+            // NOTE: Nested maps are not supported by the decoder, so their
+            //   entries can not be skipped.
+            (_, Item::Map(_)) => return None,
+            // NOTE: Ignore unknown entries for forwards compatibility.
+            _ => {}
+        }
+    }
+
+    if !decoder.0.is_empty() {
+        return None;
+    }
+    Some(metadata)
+}
+
+/// A decoded CBOR data item. Only the subset of CBOR used by the metadata is
+/// supported.
+enum Item<'a> {
+    Bytes(&'a [u8]),
+    Text(&'a [u8]),
+    Map(u64),
+    Bool(bool),
+    Other,
+}
+
+/// A minimal CBOR decoder.
+struct Decoder<'a>(&'a [u8]);
+
+impl<'a> Decoder<'a> {
+    fn item(&mut self) -> Option<Item<'a>> {
+        let (&initial, rest) = self.0.split_first()?;
+        self.0 = rest;
+
+        let major = initial >> 5;
+        let info = initial & 0x1f;
+        if major == 7 {
+            return match info {
+                20 => Some(Item::Bool(false)),
+                21 => Some(Item::Bool(true)),
+                22 | 23 => Some(Item::Other),
+                _ => None,
+            };
+        }
+
+        let argument = match info {
+            0..=23 => info as u64,
+            24 => u64::from(self.take(1)?[0]),
+            25 => u64::from(u16::from_be_bytes(self.take(2)?.try_into().ok()?)),
+            26 => u64::from(u32::from_be_bytes(self.take(4)?.try_into().ok()?)),
+            27 => u64::from_be_bytes(self.take(8)?.try_into().ok()?),
+            _ => return None,
+        };
+        match major {
+            0 | 1 => Some(Item::Other),
+            2 => Some(Item::Bytes(self.take(argument.try_into().ok()?)?)),
+            3 => Some(Item::Text(self.take(argument.try_into().ok()?)?)),
+            5 => Some(Item::Map(argument)),
+            _ => None,
+        }
+    }
+
+    fn take(&mut self, len: usize) -> Option<&'a [u8]> {
+        if len > self.0.len() {
+            return None;
+        }
+        let (bytes, rest) = self.0.split_at(len);
+        self.0 = rest;
+        Some(bytes)
+    }
+}
+
+/// Encodes bytes with the base58 alphabet used by IPFS.
+fn base58(bytes: &[u8]) -> String {
+    const ALPHABET: &[u8] = b"123456789ABCDEFGHJKLMNPQRSTUVWXYZabcdefghijkmnopqrstuvwxyz";
+
+    let mut digits = Vec::<u8>::with_capacity(bytes.len() * 138 / 100 + 1);
+    for &byte in bytes {
+        let mut carry = byte as u32;
+        for digit in digits.iter_mut() {
+            carry += (*digit as u32) << 8;
+            *digit = (carry % 58) as u8;
+            carry /= 58;
+        }
+        while carry > 0 {
+            digits.push((carry % 58) as u8);
+            carry /= 58;
+        }
+    }
+
+    let zeros = bytes.iter().take_while(|&&byte| byte == 0).count();
+    let mut encoded = "1".repeat(zeros);
+    encoded.extend(
+        digits
+            .iter()
+            .rev()
+            .map(|&digit| char::from(ALPHABET[digit as usize])),
+    );
+    encoded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decode_ipfs_metadata() {
+        let code = hex::decode(
+            "6080fea2646970667358221220993b4e7128d49168b275476d44461ca250c375b19974365fa3372ff084874faf64736f6c63430008000033",
+        )
+        .unwrap();
+
+        let (code, metadata) = split_metadata(&code);
+        let metadata = metadata.expect("missing metadata");
+
+        assert_eq!(code, &[0x60, 0x80, 0xfe]);
+        assert_eq!(metadata.solc, Some(CompilerVersion::Release(0, 8, 0)));
+        assert_eq!(metadata.solc.unwrap().to_string(), "0.8.0");
+        assert_eq!(metadata.swarm, None);
+        assert!(!metadata.experimental);
+        assert_eq!(
+            metadata.ipfs,
+            Some(
+                hex::decode("1220993b4e7128d49168b275476d44461ca250c375b19974365fa3372ff084874faf")
+                    .unwrap()
+            )
+        );
+    }
+
+    #[test]
+    fn decode_swarm_metadata() {
+        let code = hex::decode(format!(
+            "6080a265627a7a72315820{}64736f6c634300050b0032",
+            "42".repeat(32)
+        ))
+        .unwrap();
+
+        let metadata = decode_metadata(&code).expect("missing metadata");
+
+        assert_eq!(metadata.swarm, Some(H256::repeat_byte(0x42)));
+        assert_eq!(metadata.solc, Some(CompilerVersion::Release(0, 5, 11)));
+        assert_eq!(metadata.ipfs, None);
+    }
+
+    #[test]
+    fn strip_invalid_metadata() {
+        for code in &[
+            &[][..],
+            &[0x00],
+            &[0x60, 0x80, 0x00, 0x02],
+            &[0x60, 0x80, 0x00, 0x20],
+            &[0x60, 0xa1, 0x60, 0x00, 0x02],
+        ] {
+            assert_eq!(strip_metadata(code), *code);
+            assert_eq!(decode_metadata(code), None);
+        }
+    }
+
+    #[test]
+    fn ipfs_hash_base58() {
+        let metadata = Metadata {
+            ipfs: Some(
+                hex::decode("12200000000000000000000000000000000000000000000000000000000000000000")
+                    .unwrap(),
+            ),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            metadata.ipfs_hash().unwrap(),
+            "QmNLei78zWmzUdbeRB3CiUfAizWUrbeeZh5K1rhAQKCh51"
+        );
+        assert_eq!(base58(&[0, 0, 1]), "112");
+    }
+}
@@ -17,6 +17,20 @@ pub struct Contract {
     pub abi: Abi,
     /// The contract deployment bytecode.
     pub bytecode: Bytecode,
+    /// The contract runtime bytecode, that is the code stored on chain once
+    /// the contract is deployed.
+    #[serde(rename = "deployedBytecode")]
+    pub deployed_bytecode: Bytecode,
+    /// The locations of immutable variables in the runtime bytecode by AST
+    /// node ID. These are only known after the contract is deployed.
+    #[serde(rename = "immutableReferences")]
+    pub immutable_references: HashMap<String, Vec<ImmutableReference>>,
+    /// The locations of library addresses in the deployment bytecode.
+    #[serde(rename = "linkReferences")]
+    pub link_references: LinkReferences,
+    /// The locations of library addresses in the runtime bytecode.
+    #[serde(rename = "deployedLinkReferences")]
+    pub deployed_link_references: LinkReferences,
     /// The configured networks by network ID for the contract.
     pub networks: HashMap<String, Network>,
     /// The developer documentation.
@@ -43,6 +57,10 @@ impl Contract {
                 receive: false,
             },
             bytecode: Default::default(),
+            deployed_bytecode: Default::default(),
+            immutable_references: HashMap::new(),
+            link_references: HashMap::new(),
+            deployed_link_references: HashMap::new(),
             networks: HashMap::new(),
             devdoc: Default::default(),
             userdoc: Default::default(),
@@ -56,10 +74,32 @@ pub struct Network {
     /// The address at which the contract is deployed on this network.
     pub address: Address,
     /// The hash of the transaction that deployed the contract on this network.
-    #[serde(rename = "transactionHash")]
+    #[serde(rename = "transactionHash", skip_serializing_if = "Option::is_none")]
     pub deployment_information: Option<DeploymentInformation>,
 }
 
+/// The location of an immutable variable in a contract's runtime bytecode.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct ImmutableReference {
+    /// The byte offset of the immutable value in the runtime bytecode.
+    pub start: usize,
+    /// The length of the immutable value in bytes.
+    pub length: usize,
+}
+
+/// The locations of library addresses in a contract's bytecode, by source
+/// file and library name.
+pub type LinkReferences = HashMap<String, HashMap<String, Vec<LinkReference>>>;
+
+/// The location of a library address in a contract's bytecode.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct LinkReference {
+    /// The byte offset of the library address in the bytecode.
+    pub start: usize,
+    /// The length of the library address in bytes, always 20.
+    pub length: usize,
+}
+
 /// A contract's documentation.
 #[derive(Clone, Debug, Default, Serialize, Deserialize)]
 pub struct Documentation {
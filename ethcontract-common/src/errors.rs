@@ -22,6 +22,24 @@ pub enum ArtifactError {
     /// Contract have multiple deployment addresses on the same chain.
     #[error("chain with id {0} appears several times in the artifact")]
     DuplicateChain(String),
+
+    /// Contract is not deployed on the chain it is being saved for.
+    #[error("contract {0} is not deployed on chain with id {1}")]
+    NotDeployed(String, String),
+
+    /// Attempted to save a single network export for a number of networks
+    /// other than one.
+    #[error("hardhat export requires exactly one network, got {0}")]
+    SingleExportNetworks(usize),
+
+    /// Contracts with the same name but different ABIs appear several times
+    /// in the artifact.
+    #[error("contract {0} appears several times in the artifact with different ABIs")]
+    DuplicateContract(String),
+
+    /// Compiler output contains compilation errors.
+    #[error("compiler output contains errors: {0}")]
+    CompilerErrors(String),
 }
 
 /// An error reading bytecode string representation.
@@ -53,9 +71,24 @@ pub enum LinkError {
     /// traditional linkers.
     #[error("undefined library {0}")]
     UndefinedLibrary(String),
+
+    /// Error when a link reference does not point to a library address
+    /// placeholder or to a linked address in the bytecode.
+    #[error("invalid link reference at byte offset {0} with length {1}")]
+    InvalidReference(usize, usize),
 }
 
 /// An error representing an error parsing a parameter type.
 #[derive(Clone, Debug, Error)]
 #[error("'{0}' is not a valid Solidity type")]
 pub struct ParseParamTypeError(pub String);
+
+/// An error parsing a human-readable ABI fragment.
+#[derive(Clone, Debug, Error)]
+#[error("invalid ABI fragment '{fragment}': {reason}")]
+pub struct ParseAbiError {
+    /// The fragment that failed to parse.
+    pub fragment: String,
+    /// The reason the fragment is invalid.
+    pub reason: String,
+}
@@ -0,0 +1,564 @@
+//! This module implements parsing contract ABIs from human-readable fragments,
+//! for example:
+//!
+//! ```text
+//! function transfer(address to, uint256 amount) returns (bool)
+//! function balanceOf(address owner) view returns (uint256)
+//! event Transfer(address indexed from, address indexed to, uint256 value)
+//! error InsufficientBalance(uint256 available, uint256 required)
+//! ```
+//!
+//! This is the format used by Solidity interface declarations, without the
+//! trailing semicolons.
+
+use crate::abi::{Constructor, Event, EventParam, Function, Param, ParamType, StateMutability};
+use crate::errors::ParseAbiError;
+use crate::hash::{self, H32};
+use crate::Abi;
+
+/// A parsed human-readable ABI fragment.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Fragment {
+    /// A contract constructor.
+    Constructor(Constructor),
+    /// A contract function.
+    Function(Function),
+    /// A contract event.
+    Event(Event),
+    /// A custom error.
+    Error(CustomError),
+    /// The contract fallback function.
+    Fallback,
+    /// The contract receive function.
+    Receive,
+}
+
+/// A custom Solidity error.
+#[derive(Clone, Debug, PartialEq)]
+pub struct CustomError {
+    /// The error name.
+    pub name: String,
+    /// The error parameters.
+    pub inputs: Vec<Param>,
+}
+
+impl CustomError {
+    /// Computes the error signature in the standard ABI format.
+    pub fn abi_signature(&self) -> String {
+        format!(
+            "{}({})",
+            self.name,
+            self.inputs
+                .iter()
+                .map(|input| input.kind.to_string())
+                .collect::<Vec<_>>()
+                .join(","),
+        )
+    }
+
+    /// Computes the 4-byte selector that prefixes the revert data of the
+    /// error.
+    pub fn selector(&self) -> H32 {
+        hash::function_selector(self.abi_signature())
+    }
+}
+
+/// Parses a contract ABI from human-readable fragments.
+///
+/// Note that custom errors are parsed for validation but are not included in
+/// the returned `Abi`, since it has no representation for them. Use
+/// [`parse_fragment`] to retrieve them.
+pub fn parse_abi<I, S>(fragments: I) -> Result<Abi, ParseAbiError>
+where
+    I: IntoIterator<Item = S>,
+    S: AsRef<str>,
+{
+    let mut abi = Abi::default();
+    for fragment in fragments {
+        match parse_fragment(fragment.as_ref())? {
+            Fragment::Constructor(constructor) => abi.constructor = Some(constructor),
+            Fragment::Function(function) => abi
+                .functions
+                .entry(function.name.clone())
+                .or_default()
+                .push(function),
+            Fragment::Event(event) => abi
+                .events
+                .entry(event.name.clone())
+                .or_default()
+                .push(event),
+            Fragment::Error(_) => {}
+            Fragment::Fallback => abi.fallback = true,
+            Fragment::Receive => abi.receive = true,
+        }
+    }
+
+    Ok(abi)
+}
+
+/// Parses a single human-readable ABI fragment. The `function` keyword may be
+/// omitted for functions.
+pub fn parse_fragment(fragment: &str) -> Result<Fragment, ParseAbiError> {
+    let error = |reason: &str| ParseAbiError {
+        fragment: fragment.to_owned(),
+        reason: reason.to_owned(),
+    };
+
+    let mut parser = Parser::new(fragment).map_err(error)?;
+    let parsed = parser.fragment().map_err(error)?;
+    if parser.tokens.len() > parser.pos {
+        return Err(error("unexpected trailing input"));
+    }
+
+    Ok(parsed)
+}
+
+/// Parses a single Solidity type, such as `uint256`, `address[2]` or
+/// `(uint256,bytes)[]`.
+pub fn parse_param_type(kind: &str) -> Result<ParamType, ParseAbiError> {
+    let error = |reason: &str| ParseAbiError {
+        fragment: kind.to_owned(),
+        reason: reason.to_owned(),
+    };
+
+    let mut parser = Parser::new(kind).map_err(error)?;
+    let parsed = parser.param_type().map_err(error)?;
+    if parser.tokens.len() > parser.pos {
+        return Err(error("unexpected trailing input"));
+    }
+
+    Ok(parsed)
+}
+
+/// A lexical token of a human-readable ABI fragment.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum Token<'a> {
+    Word(&'a str),
+    OpenParen,
+    CloseParen,
+    OpenBracket,
+    CloseBracket,
+    Comma,
+}
+
+/// A recursive descent parser for human-readable ABI fragments.
+struct Parser<'a> {
+    tokens: Vec<Token<'a>>,
+    pos: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn new(input: &'a str) -> Result<Self, &'static str> {
+        let mut tokens = Vec::new();
+        let mut chars = input.char_indices().peekable();
+        while let Some((start, c)) = chars.next() {
+            let token = match c {
+                '(' => Token::OpenParen,
+                ')' => Token::CloseParen,
+                '[' => Token::OpenBracket,
+                ']' => Token::CloseBracket,
+                ',' => Token::Comma,
+                c if c.is_whitespace() => continue,
+                c if is_word_char(c) => {
+                    let mut end = start + c.len_utf8();
+                    while let Some(&(i, c)) = chars.peek() {
+                        if !is_word_char(c) {
+                            break;
+                        }
+                        end = i + c.len_utf8();
+                        chars.next();
+                    }
+                    Token::Word(&input[start..end])
+                }
+                _ => return Err("unexpected character"),
+            };
+            tokens.push(token);
+        }
+
+        Ok(Parser { tokens, pos: 0 })
+    }
+
+    fn peek(&self) -> Option<Token<'a>> {
+        self.tokens.get(self.pos).copied()
+    }
+
+    fn next(&mut self) -> Option<Token<'a>> {
+        let token = self.peek();
+        self.pos += 1;
+        token
+    }
+
+    fn expect(&mut self, token: Token, reason: &'static str) -> Result<(), &'static str> {
+        if self.next() != Some(token) {
+            return Err(reason);
+        }
+        Ok(())
+    }
+
+    fn word(&mut self) -> Result<&'a str, &'static str> {
+        match self.next() {
+            Some(Token::Word(word)) => Ok(word),
+            _ => Err("expected identifier"),
+        }
+    }
+
+    fn fragment(&mut self) -> Result<Fragment, &'static str> {
+        let fragment = match self.word()? {
+            "function" => {
+                let name = self.word()?;
+                Fragment::Function(self.function(name)?)
+            }
+            "constructor" => {
+                let inputs = self.params()?;
+                self.state_mutability()?;
+                Fragment::Constructor(Constructor { inputs })
+            }
+            "event" => {
+                let name = self.word()?.to_owned();
+                let inputs = self.event_params()?;
+                let anonymous = self.peek() == Some(Token::Word("anonymous"));
+                if anonymous {
+                    self.next();
+                }
+                Fragment::Event(Event {
+                    name,
+                    inputs,
+                    anonymous,
+                })
+            }
+            "error" => {
+                let name = self.word()?.to_owned();
+                let inputs = self.params()?;
+                Fragment::Error(CustomError { name, inputs })
+            }
+            "fallback" => {
+                self.params()?;
+                self.state_mutability()?;
+                Fragment::Fallback
+            }
+            "receive" => {
+                self.params()?;
+                self.state_mutability()?;
+                Fragment::Receive
+            }
+            name => Fragment::Function(self.function(name)?),
+        };
+
+        Ok(fragment)
+    }
+
+    fn function(&mut self, name: &str) -> Result<Function, &'static str> {
+        let inputs = self.params()?;
+        let state_mutability = self.state_mutability()?;
+        let outputs = if self.peek() == Some(Token::Word("returns")) {
+            self.next();
+            self.params()?
+        } else {
+            Vec::new()
+        };
+
+        #[allow(deprecated)]
+        Ok(Function {
+            name: name.to_owned(),
+            inputs,
+            outputs,
+            constant: matches!(
+                state_mutability,
+                StateMutability::Pure | StateMutability::View
+            ),
+            state_mutability,
+        })
+    }
+
+    /// Parses function modifiers, returning the state mutability.
+    fn state_mutability(&mut self) -> Result<StateMutability, &'static str> {
+        let mut state_mutability = StateMutability::NonPayable;
+        while let Some(Token::Word(word)) = self.peek() {
+            state_mutability = match word {
+                "pure" => StateMutability::Pure,
+                "view" | "constant" => StateMutability::View,
+                "payable" => StateMutability::Payable,
+                "nonpayable" => StateMutability::NonPayable,
+                "external" | "public" | "virtual" | "override" => state_mutability,
+                "returns" => break,
+                _ => return Err("unexpected function modifier"),
+            };
+            self.next();
+        }
+
+        Ok(state_mutability)
+    }
+
+    fn params(&mut self) -> Result<Vec<Param>, &'static str> {
+        self.param_list(|parser| {
+            let (name, kind, indexed) = parser.param()?;
+            if indexed {
+                return Err("only event parameters can be indexed");
+            }
+            Ok(Param { name, kind })
+        })
+    }
+
+    fn event_params(&mut self) -> Result<Vec<EventParam>, &'static str> {
+        self.param_list(|parser| {
+            let (name, kind, indexed) = parser.param()?;
+            Ok(EventParam {
+                name,
+                kind,
+                indexed,
+            })
+        })
+    }
+
+    fn param_list<T>(
+        &mut self,
+        mut param: impl FnMut(&mut Self) -> Result<T, &'static str>,
+    ) -> Result<Vec<T>, &'static str> {
+        self.expect(Token::OpenParen, "expected '('")?;
+        let mut params = Vec::new();
+        if self.peek() == Some(Token::CloseParen) {
+            self.next();
+            return Ok(params);
+        }
+        loop {
+            params.push(param(self)?);
+            match self.next() {
+                Some(Token::Comma) => continue,
+                Some(Token::CloseParen) => break,
+                _ => return Err("expected ',' or ')'"),
+            }
+        }
+
+        Ok(params)
+    }
+
+    /// Parses a parameter with its type, an optional name and modifiers.
+    fn param(&mut self) -> Result<(String, ParamType, bool), &'static str> {
+        let kind = self.param_type()?;
+        let mut indexed = false;
+        let mut name = String::new();
+        while let Some(Token::Word(word)) = self.peek() {
+            match word {
+                "indexed" => indexed = true,
+                "memory" | "calldata" | "storage" | "payable" => {}
+                _ if name.is_empty() => name = word.to_owned(),
+                _ => return Err("unexpected parameter modifier"),
+            }
+            self.next();
+        }
+
+        Ok((name, kind, indexed))
+    }
+
+    fn param_type(&mut self) -> Result<ParamType, &'static str> {
+        let mut kind = match self.peek() {
+            Some(Token::OpenParen) => self.tuple_type()?,
+            Some(Token::Word("tuple")) => {
+                self.next();
+                self.tuple_type()?
+            }
+            _ => elementary_type(self.word()?)?,
+        };
+        while self.peek() == Some(Token::OpenBracket) {
+            self.next();
+            kind = match self.next() {
+                Some(Token::CloseBracket) => ParamType::Array(Box::new(kind)),
+                Some(Token::Word(len)) => {
+                    let len = len.parse().map_err(|_| "invalid array length")?;
+                    self.expect(Token::CloseBracket, "expected ']'")?;
+                    ParamType::FixedArray(Box::new(kind), len)
+                }
+                _ => return Err("expected array length or ']'"),
+            };
+        }
+
+        Ok(kind)
+    }
+
+    fn tuple_type(&mut self) -> Result<ParamType, &'static str> {
+        let params = self.params()?;
+        Ok(ParamType::Tuple(
+            params.into_iter().map(|param| param.kind).collect(),
+        ))
+    }
+}
+
+fn is_word_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '_' || c == '$'
+}
+
+fn elementary_type(word: &str) -> Result<ParamType, &'static str> {
+    let sized = |prefix: &str, default: usize, valid: fn(usize) -> bool| {
+        let digits = &word[prefix.len()..];
+        let size = match digits {
+            "" => default,
+            _ if digits.starts_with('0') => return Err("invalid type size"),
+            _ => digits.parse().map_err(|_| "unknown type")?,
+        };
+        if !valid(size) {
+            return Err("invalid type size");
+        }
+        Ok(size)
+    };
+
+    let kind = match word {
+        "address" => ParamType::Address,
+        "bool" => ParamType::Bool,
+        "string" => ParamType::String,
+        "bytes" => ParamType::Bytes,
+        _ if word.starts_with("bytes") => {
+            ParamType::FixedBytes(sized("bytes", 0, |size| (1..=32).contains(&size))?)
+        }
+        _ if word.starts_with("uint") => {
+            ParamType::Uint(sized("uint", 256, |size| size % 8 == 0 && size <= 256)?)
+        }
+        _ if word.starts_with("int") => {
+            ParamType::Int(sized("int", 256, |size| size % 8 == 0 && size <= 256)?)
+        }
+        _ => return Err("unknown type"),
+    };
+
+    Ok(kind)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::abiext::{EventExt, FunctionExt};
+
+    #[test]
+    fn parse_erc20_abi() {
+        let abi = parse_abi(&[
+            "constructor(uint256 supply)",
+            "function transfer(address to, uint256 amount) returns (bool)",
+            "function balanceOf(address owner) external view returns (uint256 balance)",
+            "event Transfer(address indexed from, address indexed to, uint256 value)",
+            "error InsufficientBalance(uint256 available, uint256 required)",
+            "receive() external payable",
+        ])
+        .unwrap();
+
+        assert_eq!(
+            abi.constructor.as_ref().unwrap().inputs[0].kind,
+            ParamType::Uint(256)
+        );
+        assert!(abi.receive);
+        assert!(!abi.fallback);
+
+        let transfer = abi.function("transfer").unwrap();
+        assert_eq!(transfer.abi_signature(), "transfer(address,uint256)");
+        assert_eq!(transfer.inputs[0].name, "to");
+        assert_eq!(transfer.outputs[0].kind, ParamType::Bool);
+        assert_eq!(transfer.state_mutability, StateMutability::NonPayable);
+
+        let balance_of = abi.function("balanceOf").unwrap();
+        assert_eq!(balance_of.state_mutability, StateMutability::View);
+        assert_eq!(balance_of.outputs[0].name, "balance");
+
+        let transfer = abi.event("Transfer").unwrap();
+        assert_eq!(
+            transfer.abi_signature(),
+            "Transfer(address,address,uint256)"
+        );
+        assert!(transfer.inputs[0].indexed);
+        assert!(!transfer.inputs[2].indexed);
+        assert!(!transfer.anonymous);
+    }
+
+    #[test]
+    fn parse_complex_types() {
+        for (kind, expected) in &[
+            ("uint", ParamType::Uint(256)),
+            ("int8", ParamType::Int(8)),
+            ("bytes32", ParamType::FixedBytes(32)),
+            ("address[]", ParamType::Array(Box::new(ParamType::Address))),
+            (
+                "bool[2][]",
+                ParamType::Array(Box::new(ParamType::FixedArray(
+                    Box::new(ParamType::Bool),
+                    2,
+                ))),
+            ),
+            (
+                "(uint256 a, (bytes, string)[] b)",
+                ParamType::Tuple(vec![
+                    ParamType::Uint(256),
+                    ParamType::Array(Box::new(ParamType::Tuple(vec![
+                        ParamType::Bytes,
+                        ParamType::String,
+                    ]))),
+                ]),
+            ),
+            (
+                "tuple(address,uint8)[3]",
+                ParamType::FixedArray(
+                    Box::new(ParamType::Tuple(vec![
+                        ParamType::Address,
+                        ParamType::Uint(8),
+                    ])),
+                    3,
+                ),
+            ),
+        ] {
+            assert_eq!(parse_param_type(kind).unwrap(), *expected);
+        }
+    }
+
+    #[test]
+    fn parse_fragments() {
+        let function =
+            match parse_fragment("swap((address, uint256)[] calldata orders) payable").unwrap() {
+                Fragment::Function(function) => function,
+                fragment => panic!("unexpected fragment {:?}", fragment),
+            };
+        assert_eq!(function.abi_signature(), "swap((address,uint256)[])");
+        assert_eq!(function.state_mutability, StateMutability::Payable);
+
+        let error = match parse_fragment("error Unauthorized(address caller)").unwrap() {
+            Fragment::Error(error) => error,
+            fragment => panic!("unexpected fragment {:?}", fragment),
+        };
+        assert_eq!(error.abi_signature(), "Unauthorized(address)");
+        assert_eq!(
+            error.selector(),
+            hash::function_selector("Unauthorized(address)")
+        );
+
+        let event = match parse_fragment("event Log(string message) anonymous").unwrap() {
+            Fragment::Event(event) => event,
+            fragment => panic!("unexpected fragment {:?}", fragment),
+        };
+        assert!(event.anonymous);
+
+        assert_eq!(
+            parse_fragment("fallback() external").unwrap(),
+            Fragment::Fallback
+        );
+    }
+
+    #[test]
+    fn parse_invalid_fragments() {
+        for fragment in &[
+            "",
+            "function",
+            "function foo(",
+            "function foo(uint7)",
+            "function foo(bytes33)",
+            "function foo(uint256) returns",
+            "function foo(address indexed a)",
+            "function foo() mutable",
+            "event Foo(uint256 a b)",
+            "function foo() extra()",
+            "function foo(uint256[x])",
+            "function foo(uint256 #)",
+            "function foo(uint08)",
+            "function foo(uint0)",
+        ] {
+            assert!(
+                parse_fragment(fragment).is_err(),
+                "unexpectedly parsed {:?}",
+                fragment
+            );
+        }
+    }
+}
@@ -9,6 +9,7 @@ pub mod bytecode;
 pub mod contract;
 pub mod errors;
 pub mod hash;
+pub mod human_readable;
 
 pub use crate::abiext::FunctionExt;
 pub use crate::bytecode::Bytecode;
@@ -13,18 +13,22 @@ use ethcontract_common::abi::{Function, Param, ParamType};
 use ethcontract_common::abiext::{FunctionExt, ParamTypeExt};
 use ethcontract_common::artifact::truffle::TruffleLoader;
 use ethcontract_common::contract::Network;
-use ethcontract_common::Address;
-use ethcontract_generate::loaders::{HardHatFormat, HardHatLoader};
-use ethcontract_generate::{parse_address, ContractBuilder, Source};
+use ethcontract_common::human_readable::{parse_abi, parse_fragment, Fragment};
+use ethcontract_common::{Address, Contract};
+use ethcontract_generate::loaders::{
+    ForgeLoader, HardHatFormat, HardHatLoader, SolcFormat, SolcLoader,
+};
+use ethcontract_generate::{parse_address, solc, ContractBuilder, Source};
 use proc_macro::TokenStream;
 use proc_macro2::{Span, TokenStream as TokenStream2};
 use quote::{quote, ToTokens as _};
 use std::collections::HashSet;
+use std::env;
 use syn::ext::IdentExt;
 use syn::parse::{Error as ParseError, Parse, ParseStream, Result as ParseResult};
 use syn::{
-    braced, parenthesized, parse_macro_input, Error as SynError, Ident, LitInt, LitStr, Path,
-    Token, Visibility,
+    braced, bracketed, parenthesized, parse_macro_input, token, Error as SynError, Ident, LitInt,
+    LitStr, Path, Token, Visibility,
 };
 
 /// Proc macro to generate type-safe bindings to a contract.
@@ -48,12 +52,39 @@ use syn::{
 ///
 /// // NPM package source
 /// contract!("npm:@openzeppelin/contracts@4.2.0/build/contracts/IERC20.json")
+///
+/// // Solidity source compiled with a local `solc`
+/// contract!("solc:contracts/Token.sol", contract = Token)
 /// ```
 ///
 /// Note that Etherscan rate-limits requests to their API, to avoid this an
 /// `ETHERSCAN_API_KEY` environment variable can be set. If it is, it will use
 /// that API key when retrieving the contract ABI.
 ///
+/// Remote sources can be cached for offline builds and pinned to hashes of
+/// their content in a lockfile with the `ETHCONTRACT_CACHE_DIR`,
+/// `ETHCONTRACT_LOCKFILE` and `ETHCONTRACT_OFFLINE` environment variables,
+/// see [`ethcontract_generate::cache`] for more details. The macro is
+/// expanded again whenever these variables change.
+///
+/// For small interfaces, the ABI can also be specified inline as a list of
+/// human-readable fragments instead of an artifact source. In this case, the
+/// contract name must be specified with the `contract` parameter:
+///
+/// ```ignore
+/// contract!(
+///     [
+///         "function balanceOf(address owner) view returns (uint256)",
+///         "function transfer(address to, uint256 amount) returns (bool)",
+///         "event Transfer(address indexed from, address indexed to, uint256 value)",
+///     ],
+///     contract = ERC20,
+/// );
+/// ```
+///
+/// Custom `error` fragments and the `format` parameter are not supported for
+/// human-readable ABIs.
+///
 /// Currently, the proc macro accepts additional parameters to configure some
 /// aspects of the code generation. Specifically it accepts the following.
 ///
@@ -61,17 +92,27 @@ use syn::{
 ///
 ///   Available values are:
 ///
-///   - `truffle` (default) to use [truffle loader];
+///   - `truffle` (default, unless the source is a Solidity file) to use
+///     [truffle loader];
 ///   - `hardhat` to use [hardhat loader] in [single export mode];
-///   - `hardhat_multi` to use hardhat loader in [multi export mode].
-///
-///   Note that hardhat artifacts export multiple contracts. You'll have to use
-///   `contract` parameter to specify which contract to generate bindings to.
+///   - `hardhat_multi` to use hardhat loader in [multi export mode];
+///   - `forge` to use [forge loader] for a single forge artifact, usually
+///     found in `out/<File>.sol/<Contract>.json`;
+///   - `solc_standard_json` (default for Solidity sources) to use
+///     [solc loader] with output of `solc --standard-json`;
+///   - `solc_combined_json` to use solc loader with output of
+///     `solc --combined-json abi,bin,bin-runtime`.
+///
+///   Note that hardhat artifacts and solc output contain multiple contracts.
+///   You'll have to use `contract` parameter to specify which contract to
+///   generate bindings to.
 ///
 ///   [truffle loader]: ethcontract_common::artifact::truffle::TruffleLoader
 ///   [hardhat loader]: ethcontract_common::artifact::hardhat::HardHatLoader
 ///   [single export mode]: ethcontract_common::artifact::hardhat::Format::SingleExport
 ///   [multi export mode]: ethcontract_common::artifact::hardhat::Format::MultiExport
+///   [forge loader]: ethcontract_common::artifact::forge::ForgeLoader
+///   [solc loader]: ethcontract_common::artifact::solc::SolcLoader
 ///
 /// - `contract`: name of the contract we're generating bindings to.
 ///
@@ -211,7 +252,7 @@ pub fn contract(input: TokenStream) -> TokenStream {
 }
 
 fn generate(args: ContractArgs) -> Result<TokenStream2> {
-    let mut artifact_format = Format::Truffle;
+    let mut artifact_format = None;
     let mut contract_name = None;
 
     let mut builder = ContractBuilder::new();
@@ -246,16 +287,47 @@ fn generate(args: ContractArgs) -> Result<TokenStream2> {
             Parameter::EventDerives(derives) => {
                 builder.event_derives.extend(derives);
             }
-            Parameter::Format(format) => artifact_format = format,
+            Parameter::Format(format) => artifact_format = Some(format),
         };
     }
 
-    let source = Source::parse(&args.artifact_path)?;
-    let json = source.artifact_json()?;
+    let artifact_path = match args.source {
+        ContractSource::Artifact(artifact_path) => artifact_path,
+        ContractSource::HumanReadable(fragments) => {
+            let contract_name = contract_name.ok_or_else(|| {
+                anyhow!(
+                    "when using human-readable ABIs, you should specify \
+                     contract name using 'contract' parameter"
+                )
+            })?;
+
+            let mut contract = Contract::with_name(contract_name);
+            contract.abi = parse_abi(&fragments)?;
+
+            return Ok(builder.generate(&contract)?.into_tokens());
+        }
+    };
 
-    match artifact_format {
-        Format::Truffle => {
-            let mut contract = TruffleLoader::new().load_contract_from_str(&json)?;
+    let source = Source::parse(&artifact_path)?;
+    let json = source.artifact_json()?;
+    let dependencies = expand_dependencies(&source, &json)?;
+
+    // NOTE: Solidity sources are compiled into `solc` standard JSON output, so
+    //   use the matching format unless one is specified explicitly.
+    let artifact_format = artifact_format.unwrap_or(match source {
+        Source::Solidity(_) => Format::Solc(SolcFormat::StandardJson),
+        _ => Format::Truffle,
+    });
+
+    let artifact = match artifact_format {
+        Format::Truffle | Format::Forge => {
+            // NOTE: Both formats contain a single contract which may be
+            //   unnamed, so they are handled the same way once loaded.
+            let mut contract = if let Format::Forge = artifact_format {
+                ForgeLoader::new().load_contract_from_str(&json)?
+            } else {
+                TruffleLoader::new().load_contract_from_str(&json)?
+            };
 
             if let Some(contract_name) = contract_name {
                 if contract.name.is_empty() {
@@ -264,45 +336,93 @@ fn generate(args: ContractArgs) -> Result<TokenStream2> {
                     return Err(anyhow!(
                         "there is no contract '{}' in artifact '{}'",
                         contract_name,
-                        args.artifact_path
+                        artifact_path
                     ));
                 }
             }
 
-            Ok(builder.generate(&contract)?.into_tokens())
+            let bindings = builder.generate(&contract)?.into_tokens();
+            return Ok(quote! { #dependencies #bindings });
         }
 
-        Format::HardHat(format) => {
-            let artifact = HardHatLoader::new().load_from_str(format, &json)?;
-
-            if let Some(contract_name) = contract_name {
-                if let Some(contract) = artifact.get(&contract_name) {
-                    Ok(builder.generate(contract)?.into_tokens())
-                } else {
-                    Err(anyhow!(
-                        "there is no contract '{}' in artifact '{}'",
-                        contract_name,
-                        args.artifact_path
-                    ))
-                }
-            } else {
-                Err(anyhow!(
-                    "when using hardhat artifacts, you should specify \
-                     contract name using 'contract' parameter"
-                ))
-            }
+        Format::HardHat(format) => HardHatLoader::new().load_from_str(format, &json)?,
+        Format::Solc(format) => SolcLoader::new().load_from_str(format, &json)?,
+    };
+
+    if let Some(contract_name) = contract_name {
+        if let Some(contract) = artifact.get(&contract_name) {
+            let bindings = builder.generate(contract)?.into_tokens();
+            Ok(quote! { #dependencies #bindings })
+        } else {
+            Err(anyhow!(
+                "there is no contract '{}' in artifact '{}'",
+                contract_name,
+                artifact_path
+            ))
         }
+    } else {
+        Err(anyhow!(
+            "when using hardhat or solc artifacts, you should specify \
+             contract name using 'contract' parameter"
+        ))
     }
 }
 
+/// Expands an anchor including the local files the artifact was loaded from,
+/// so that the macro is expanded again whenever they change. For remote
+/// sources, the anchor reads the environment variables configuring the source
+/// cache instead.
+///
+/// Cargo tracks files included with `include_bytes!` and environment
+/// variables read with `option_env!` as dependencies of the crate, while files
+/// and environment variables read by procedural macros are not tracked.
+fn expand_dependencies(source: &Source, json: &str) -> Result<TokenStream2> {
+    let paths = match source {
+        Source::Local(path) => vec![path.clone()],
+        // NOTE: Non-relative imports are resolved by `solc` against its
+        //   working directory, which is also ours.
+        Source::Solidity(_) => {
+            let root = env::current_dir()?;
+            solc::output_sources(json)?
+                .into_iter()
+                .map(|path| root.join(path))
+                .filter(|path| path.is_file())
+                .collect()
+        }
+        Source::Http(_) | Source::Etherscan(_) | Source::Npm(_) => {
+            return Ok(quote! {
+                const _: &[Option<&str>] = &[
+                    option_env!("ETHCONTRACT_CACHE_DIR"),
+                    option_env!("ETHCONTRACT_LOCKFILE"),
+                    option_env!("ETHCONTRACT_OFFLINE"),
+                ];
+            });
+        }
+    };
+
+    let paths = paths.iter().map(|path| path.to_string_lossy());
+    Ok(quote! {
+        const _: &[&[u8]] = &[#( include_bytes!(#paths) ),*];
+    })
+}
+
 /// Contract procedural macro arguments.
 #[cfg_attr(test, derive(Debug, Eq, PartialEq))]
 struct ContractArgs {
     visibility: Option<String>,
-    artifact_path: String,
+    source: ContractSource,
     parameters: Vec<Parameter>,
 }
 
+/// The source of the contract ABI.
+#[cfg_attr(test, derive(Debug, Eq, PartialEq))]
+enum ContractSource {
+    /// An artifact source, see [`Source::parse`] for supported sources.
+    Artifact(String),
+    /// A list of human-readable ABI fragments.
+    HumanReadable(Vec<String>),
+}
+
 impl ParseInner for ContractArgs {
     fn spanned_parse(input: ParseStream) -> ParseResult<(Span, Self)> {
         let visibility = match input.parse::<Visibility>()? {
@@ -315,24 +435,55 @@ impl ParseInner for ContractArgs {
         //   therefore, the path will always be rooted on the cargo manifest
         //   directory. Eventually we can use the `Span::source_file` API to
         //   have a better experience.
-        let (span, artifact_path) = {
+        let (span, source) = if input.peek(token::Bracket) {
+            let span = input.span();
+            let content;
+            bracketed!(content in input);
+            let fragments = content
+                .parse_terminated::<_, Token![,]>(|input| {
+                    let literal = input.parse::<LitStr>()?;
+                    let fragment = literal.value();
+                    let parsed = parse_fragment(&fragment)
+                        .map_err(|err| ParseError::new(literal.span(), err))?;
+                    if let Fragment::Error(_) = parsed {
+                        return Err(ParseError::new(
+                            literal.span(),
+                            "custom errors are not supported in human-readable ABIs",
+                        ));
+                    }
+                    Ok(fragment)
+                })?
+                .into_iter()
+                .collect();
+            (span, ContractSource::HumanReadable(fragments))
+        } else {
             let literal = input.parse::<LitStr>()?;
-            (literal.span(), literal.value())
+            (literal.span(), ContractSource::Artifact(literal.value()))
         };
 
         if !input.is_empty() {
             input.parse::<Token![,]>()?;
         }
-        let parameters = input
+        let parameters: Vec<_> = input
             .parse_terminated::<_, Token![,]>(Parameter::parse)?
             .into_iter()
             .collect();
 
+        let has_format = parameters
+            .iter()
+            .any(|parameter| matches!(parameter, Parameter::Format(_)));
+        if let (ContractSource::HumanReadable(_), true) = (&source, has_format) {
+            return Err(ParseError::new(
+                span,
+                "artifact format can not be specified for human-readable ABIs",
+            ));
+        }
+
         Ok((
             span,
             ContractArgs {
                 visibility,
-                artifact_path,
+                source,
                 parameters,
             },
         ))
@@ -344,6 +495,8 @@ impl ParseInner for ContractArgs {
 enum Format {
     Truffle,
     HardHat(HardHatFormat),
+    Forge,
+    Solc(SolcFormat),
 }
 
 /// A single procedural macro parameter.
@@ -379,6 +532,9 @@ impl Parse for Parameter {
                     "truffle" => Format::Truffle,
                     "hardhat" => Format::HardHat(HardHatFormat::SingleExport),
                     "hardhat_multi" => Format::HardHat(HardHatFormat::MultiExport),
+                    "forge" => Format::Forge,
+                    "solc_standard_json" => Format::Solc(SolcFormat::StandardJson),
+                    "solc_combined_json" => Format::Solc(SolcFormat::CombinedJson),
                     format => {
                         return Err(ParseError::new(
                             token.span(),
@@ -591,7 +747,10 @@ mod tests {
     #[test]
     fn parse_contract_args() {
         let args = contract_args!("path/to/artifact.json");
-        assert_eq!(args.artifact_path, "path/to/artifact.json");
+        assert_eq!(
+            args.source,
+            ContractSource::Artifact("path/to/artifact.json".into())
+        );
     }
 
     #[test]
@@ -607,7 +766,7 @@ mod tests {
             args,
             ContractArgs {
                 visibility: None,
-                artifact_path: "artifact.json".into(),
+                source: ContractSource::Artifact("artifact.json".into()),
                 parameters: vec![],
             },
         );
@@ -634,7 +793,7 @@ mod tests {
             args,
             ContractArgs {
                 visibility: Some(quote!(pub(crate)).to_string()),
-                artifact_path: "artifact.json".into(),
+                source: ContractSource::Artifact("artifact.json".into()),
                 parameters: vec![
                     Parameter::Crate("foobar".into()),
                     Parameter::Mod("contract".into()),
@@ -664,7 +823,7 @@ mod tests {
             args,
             ContractArgs {
                 visibility: None,
-                artifact_path: "artifact.json".into(),
+                source: ContractSource::Artifact("artifact.json".into()),
                 parameters: vec![Parameter::Format(Format::HardHat(
                     HardHatFormat::MultiExport
                 ))],
@@ -679,7 +838,7 @@ mod tests {
             args,
             ContractArgs {
                 visibility: None,
-                artifact_path: "artifact.json".into(),
+                source: ContractSource::Artifact("artifact.json".into()),
                 parameters: vec![Parameter::Contract(
                     "Contract".into(),
                     Some("Renamed".into())
@@ -688,6 +847,103 @@ mod tests {
         );
     }
 
+    #[test]
+    fn parse_contract_args_human_readable() {
+        let args = contract_args!(
+            [
+                "function balanceOf(address owner) view returns (uint256)",
+                "event Transfer(address indexed from, address indexed to, uint256 value)",
+            ],
+            contract = ERC20,
+        );
+        assert_eq!(
+            args,
+            ContractArgs {
+                visibility: None,
+                source: ContractSource::HumanReadable(vec![
+                    "function balanceOf(address owner) view returns (uint256)".into(),
+                    "event Transfer(address indexed from, address indexed to, uint256 value)"
+                        .into(),
+                ]),
+                parameters: vec![Parameter::Contract("ERC20".into(), None)],
+            },
+        );
+    }
+
+    #[test]
+    fn invalid_human_readable_fragment_error() {
+        contract_args_err!(["function balanceOf(address owner"], contract = ERC20);
+    }
+
+    #[test]
+    fn human_readable_custom_error_error() {
+        contract_args_err!(
+            ["error InsufficientBalance(uint256 available)"],
+            contract = ERC20,
+        );
+    }
+
+    #[test]
+    fn human_readable_format_error() {
+        contract_args_err!(
+            ["function totalSupply() view returns (uint256)"],
+            contract = ERC20,
+            format = hardhat_multi,
+        );
+    }
+
+    #[test]
+    fn parse_contract_args_forge_format() {
+        let args = contract_args!("out/Token.sol/Token.json", format = forge);
+        assert_eq!(args.parameters, vec![Parameter::Format(Format::Forge)]);
+    }
+
+    #[test]
+    fn parse_contract_args_solc_format() {
+        let args = contract_args!("solc-output.json", format = solc_combined_json);
+        assert_eq!(
+            args.parameters,
+            vec![Parameter::Format(Format::Solc(SolcFormat::CombinedJson))]
+        );
+    }
+
+    #[test]
+    fn expand_dependencies_for_local_sources() {
+        let source = Source::local("/build/contracts/Token.json");
+        assert_eq!(
+            expand_dependencies(&source, "{}").unwrap().to_string(),
+            quote! {
+                const _: &[&[u8]] = &[include_bytes!("/build/contracts/Token.json")];
+            }
+            .to_string(),
+        );
+
+        let root = env::current_dir().unwrap();
+        let source = Source::solidity(root.join("src/lib.rs"));
+        let json = r#"{"sources":{"src/lib.rs":{},"/missing/Token.sol":{}}}"#;
+        let path = root.join("src/lib.rs").to_string_lossy().into_owned();
+        assert_eq!(
+            expand_dependencies(&source, json).unwrap().to_string(),
+            quote! {
+                const _: &[&[u8]] = &[include_bytes!(#path)];
+            }
+            .to_string(),
+        );
+
+        let source = Source::npm("@openzeppelin/contracts/build/contracts/IERC20.json");
+        assert_eq!(
+            expand_dependencies(&source, "{}").unwrap().to_string(),
+            quote! {
+                const _: &[Option<&str>] = &[
+                    option_env!("ETHCONTRACT_CACHE_DIR"),
+                    option_env!("ETHCONTRACT_LOCKFILE"),
+                    option_env!("ETHCONTRACT_OFFLINE"),
+                ];
+            }
+            .to_string(),
+        );
+    }
+
     #[test]
     fn unsupported_format_error() {
         contract_args_err!("artifact.json", format = yaml);
@@ -0,0 +1,466 @@
+//! Implements an offline cache and a lockfile for remote artifact sources.
+//!
+//! Remote sources, that is HTTP(S), Etherscan and NPM sources, are fetched
+//! over the network every time they are loaded. To support offline builds
+//! and to protect against remote content changing, their content can be
+//! stored in a content-addressed cache directory and pinned to a hash in
+//! a lockfile.
+//!
+//! The cache is configured through the following environment variables:
+//!
+//! - `ETHCONTRACT_CACHE_DIR`: directory for cached sources. Each source is
+//!   stored in a file named by the keccak256 hash of its content. The cache
+//!   also keeps an index with the hash of the last content fetched for each
+//!   source;
+//!
+//! - `ETHCONTRACT_LOCKFILE`: path to a lockfile that pins sources to hashes
+//!   of their content. Sources that are not yet pinned are added to the
+//!   lockfile when they are fetched. Fetching a pinned source that has
+//!   different content results in an error;
+//!
+//! - `ETHCONTRACT_OFFLINE`: when set to `1` or `true`, sources are never
+//!   fetched over the network. Instead, they are read from the cache, using
+//!   hashes from the lockfile, or from the cache index for unpinned sources.
+//!
+//! Relative paths are rooted in the crate's root `CARGO_MANIFEST_DIR`, the
+//! same way as relative artifact paths.
+//!
+//! Lockfiles and cache indices are JSON objects that map source URLs, such
+//! as `npm:@openzeppelin/contracts@2.5.0/build/contracts/IERC20.json`, to
+//! hashes of their content.
+
+use crate::util;
+use anyhow::{anyhow, Context, Result};
+use ethcontract_common::abi::Hash;
+use ethcontract_common::hash;
+use std::collections::BTreeMap;
+use std::env;
+use std::fs::{self, OpenOptions};
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+use std::process;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Name of the cache index file in the cache directory.
+const INDEX_FILE: &str = "index.json";
+
+/// Maximum time to wait for a lock held by another build. Locks older than
+/// this are considered stale, left behind by a build that was killed, and
+/// are broken.
+const LOCK_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Cache for the content of remote artifact sources.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct SourceCache {
+    /// Directory for cached sources. If `None`, sources are not cached.
+    pub dir: Option<PathBuf>,
+
+    /// Path to the lockfile. If `None`, sources are not pinned.
+    pub lockfile: Option<PathBuf>,
+
+    /// Whether sources should be read from the cache instead of being
+    /// fetched over the network.
+    pub offline: bool,
+}
+
+impl SourceCache {
+    /// Creates a new cache that does not store anything, and always fetches
+    /// sources over the network.
+    pub fn new() -> Self {
+        SourceCache::default()
+    }
+
+    /// Creates a new cache configured from the `ETHCONTRACT_CACHE_DIR`,
+    /// `ETHCONTRACT_LOCKFILE` and `ETHCONTRACT_OFFLINE` environment
+    /// variables. See [module documentation](self) for more details.
+    pub fn from_env() -> Self {
+        let offline = env::var("ETHCONTRACT_OFFLINE")
+            .map(|value| value == "1" || value.eq_ignore_ascii_case("true"))
+            .unwrap_or(false);
+
+        // NOTE: Joining an absolute path replaces the root, so only relative
+        //   paths are rooted in the project directory.
+        let root = util::project_root().unwrap_or_default();
+        let path = |name: &str| env::var_os(name).map(|path| root.join(path));
+
+        SourceCache {
+            dir: path("ETHCONTRACT_CACHE_DIR"),
+            lockfile: path("ETHCONTRACT_LOCKFILE"),
+            offline,
+        }
+    }
+
+    /// Sets the cache directory. See [`dir`] for more info.
+    ///
+    /// [`dir`]: #structfield.dir
+    pub fn dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.dir = Some(dir.into());
+        self
+    }
+
+    /// Sets path to the lockfile. See [`lockfile`] for more info.
+    ///
+    /// [`lockfile`]: #structfield.lockfile
+    pub fn lockfile(mut self, lockfile: impl Into<PathBuf>) -> Self {
+        self.lockfile = Some(lockfile.into());
+        self
+    }
+
+    /// Enables or disables offline mode. See [`offline`] for more info.
+    ///
+    /// [`offline`]: #structfield.offline
+    pub fn offline(mut self, offline: bool) -> Self {
+        self.offline = offline;
+        self
+    }
+
+    /// Returns the content of a source identified by the specified URL.
+    ///
+    /// Pinned sources present in the cache are read from the cache, other
+    /// sources are retrieved with `fetch` unless in offline mode.
+    pub fn get(&self, url: &str, fetch: impl FnOnce() -> Result<String>) -> Result<String> {
+        let pinned = match &self.lockfile {
+            Some(path) => read_hashes(path)?.get(url).copied(),
+            None => None,
+        };
+
+        if let Some(hash) = pinned {
+            if let Some(content) = self.read_cached(url, hash)? {
+                return Ok(content);
+            }
+        }
+
+        if self.offline {
+            let hash = match (pinned, &self.dir) {
+                (Some(hash), _) => hash,
+                (None, Some(dir)) => read_hashes(&dir.join(INDEX_FILE))?
+                    .get(url)
+                    .copied()
+                    .ok_or_else(|| anyhow!("source '{}' is not cached", url))?,
+                (None, None) => {
+                    return Err(anyhow!(
+                        "cannot load source '{}' in offline mode without a cache directory",
+                        url
+                    ))
+                }
+            };
+            return self
+                .read_cached(url, hash)?
+                .ok_or_else(|| anyhow!("source '{}' with hash {:?} is not cached", url, hash));
+        }
+
+        let content = fetch()?;
+        let hash = content_hash(&content);
+        if let Some(pinned) = pinned {
+            if pinned != hash {
+                return Err(anyhow!(
+                    "content of source '{}' does not match the lockfile: \
+                     expected hash {:?}, got {:?}",
+                    url,
+                    pinned,
+                    hash,
+                ));
+            }
+        }
+
+        if let Some(dir) = &self.dir {
+            fs::create_dir_all(dir)
+                .with_context(|| format!("failed to create cache directory {}", dir.display()))?;
+            write_atomic(&dir.join(blob_name(hash)), &content)?;
+
+            update_hashes(&dir.join(INDEX_FILE), |hashes| {
+                Ok(hashes.insert(url.to_string(), hash) != Some(hash))
+            })?;
+        }
+
+        if let (Some(path), None) = (&self.lockfile, pinned) {
+            // NOTE: The source may have been pinned by a concurrent build
+            //   since the lockfile was read, so check its hash again.
+            update_hashes(path, |hashes| match hashes.get(url) {
+                Some(pinned) if *pinned != hash => Err(anyhow!(
+                    "content of source '{}' does not match the lockfile: \
+                     expected hash {:?}, got {:?}",
+                    url,
+                    pinned,
+                    hash,
+                )),
+                Some(_) => Ok(false),
+                None => {
+                    hashes.insert(url.to_string(), hash);
+                    Ok(true)
+                }
+            })?;
+        }
+
+        Ok(content)
+    }
+
+    /// Reads the content of a source from the cache. Returns `None` if the
+    /// source is not cached, and an error if cached content does not match
+    /// its hash.
+    fn read_cached(&self, url: &str, hash: Hash) -> Result<Option<String>> {
+        let dir = match &self.dir {
+            Some(dir) => dir,
+            None => return Ok(None),
+        };
+
+        let path = dir.join(blob_name(hash));
+        let content = match fs::read_to_string(&path) {
+            Ok(content) => content,
+            Err(err) if err.kind() == ErrorKind::NotFound => return Ok(None),
+            Err(err) => {
+                return Err(err)
+                    .with_context(|| format!("failed to read cached source {}", path.display()))
+            }
+        };
+
+        let actual = content_hash(&content);
+        if actual != hash {
+            return Err(anyhow!(
+                "cached content of source '{}' in {} is corrupted: \
+                 expected hash {:?}, got {:?}",
+                url,
+                path.display(),
+                hash,
+                actual,
+            ));
+        }
+
+        Ok(Some(content))
+    }
+}
+
+/// Computes the hash of source content.
+fn content_hash(content: &str) -> Hash {
+    Hash::from(hash::keccak256(content.as_bytes()))
+}
+
+/// Returns the name of the cache file for content with the specified hash.
+fn blob_name(hash: Hash) -> String {
+    format!("{:x}.json", hash)
+}
+
+/// Reads a lockfile or a cache index. Missing files are treated as empty.
+fn read_hashes(path: &Path) -> Result<BTreeMap<String, Hash>> {
+    match fs::read_to_string(path) {
+        Ok(json) => serde_json::from_str(&json)
+            .with_context(|| format!("failed to parse lockfile {}", path.display())),
+        Err(err) if err.kind() == ErrorKind::NotFound => Ok(BTreeMap::new()),
+        Err(err) => Err(err).with_context(|| format!("failed to read lockfile {}", path.display())),
+    }
+}
+
+/// Updates a lockfile or a cache index with the specified function, which
+/// returns whether the hashes were modified and need to be written.
+///
+/// The file is locked during the update, so that concurrent builds expanding
+/// different sources do not lose each other's updates.
+fn update_hashes(
+    path: &Path,
+    update: impl FnOnce(&mut BTreeMap<String, Hash>) -> Result<bool>,
+) -> Result<()> {
+    let _lock = FileLock::acquire(path)?;
+    let mut hashes = read_hashes(path)?;
+    if update(&mut hashes)? {
+        write_hashes(path, &hashes)?;
+    }
+    Ok(())
+}
+
+/// Writes a lockfile or a cache index.
+fn write_hashes(path: &Path, hashes: &BTreeMap<String, Hash>) -> Result<()> {
+    let mut json = serde_json::to_string_pretty(hashes)?;
+    json.push('\n');
+    write_atomic(path, &json)
+}
+
+/// Writes a file by renaming a temporary file, so that concurrent builds never
+/// observe partially written content.
+fn write_atomic(path: &Path, content: &str) -> Result<()> {
+    // NOTE: Procedural macros can be expanded on multiple threads of the same
+    //   process, so the process ID alone does not make the name unique.
+    static COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+    let mut temp = path.as_os_str().to_owned();
+    temp.push(format!(
+        ".{}.{}.tmp",
+        process::id(),
+        COUNTER.fetch_add(1, Ordering::Relaxed),
+    ));
+
+    fs::write(&temp, content)
+        .and_then(|_| fs::rename(&temp, path))
+        .with_context(|| format!("failed to write {}", path.display()))
+}
+
+/// An exclusive lock on a file, held by creating a `.lock` file next to it
+/// and released by removing it once dropped.
+struct FileLock(PathBuf);
+
+impl FileLock {
+    /// Acquires the lock for the specified file, waiting for it to be
+    /// released if it is held by another build.
+    fn acquire(path: &Path) -> Result<Self> {
+        FileLock::acquire_with_timeout(path, LOCK_TIMEOUT)
+    }
+
+    /// Acquires the lock for the specified file, waiting for it to be released
+    /// if it is held by another build and breaking it if it is older than the
+    /// specified timeout.
+    fn acquire_with_timeout(path: &Path, timeout: Duration) -> Result<Self> {
+        let mut lock = path.as_os_str().to_owned();
+        lock.push(".lock");
+        let lock = PathBuf::from(lock);
+
+        let start = Instant::now();
+        loop {
+            match OpenOptions::new().write(true).create_new(true).open(&lock) {
+                Ok(_) => return Ok(FileLock(lock)),
+                Err(err) if err.kind() == ErrorKind::AlreadyExists => {
+                    if is_stale(&lock, timeout) && fs::remove_file(&lock).is_ok() {
+                        continue;
+                    }
+                    if start.elapsed() > timeout {
+                        return Err(anyhow!(
+                            "timed out waiting for lock {}, remove it if no other \
+                             build is running",
+                            lock.display(),
+                        ));
+                    }
+                    thread::sleep(Duration::from_millis(10));
+                }
+                Err(err) => {
+                    return Err(err)
+                        .with_context(|| format!("failed to create lock {}", lock.display()))
+                }
+            }
+        }
+    }
+}
+
+/// Returns whether a lock file was created longer than the timeout ago.
+fn is_stale(lock: &Path, timeout: Duration) -> bool {
+    let age = fs::metadata(lock)
+        .and_then(|metadata| metadata.modified())
+        .ok()
+        .and_then(|modified| modified.elapsed().ok());
+    matches!(age, Some(age) if age >= timeout)
+}
+
+impl Drop for FileLock {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.0);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const URL: &str = "npm:@openzeppelin/contracts@2.5.0/build/contracts/IERC20.json";
+
+    #[test]
+    fn offline_reads_cached_sources() {
+        let temp = tempfile::tempdir().unwrap();
+        let cache = SourceCache::new().dir(temp.path().join("cache"));
+
+        let content = cache.get(URL, || Ok("{}".to_string())).unwrap();
+        assert_eq!(content, "{}");
+
+        let content = cache
+            .clone()
+            .offline(true)
+            .get(URL, || panic!("fetched source in offline mode"))
+            .unwrap();
+        assert_eq!(content, "{}");
+
+        assert!(cache
+            .offline(true)
+            .get("npm:missing.json", || panic!(
+                "fetched source in offline mode"
+            ))
+            .is_err());
+    }
+
+    #[test]
+    fn lockfile_pins_sources() {
+        let temp = tempfile::tempdir().unwrap();
+        let lockfile = temp.path().join("ethcontract.lock");
+        let cache = SourceCache::new().lockfile(&lockfile);
+
+        cache.get(URL, || Ok("{}".to_string())).unwrap();
+        assert_eq!(read_hashes(&lockfile).unwrap()[URL], content_hash("{}"));
+
+        assert_eq!(cache.get(URL, || Ok("{}".to_string())).unwrap(), "{}");
+        assert!(cache.get(URL, || Ok("[]".to_string())).is_err());
+    }
+
+    #[test]
+    fn pinned_sources_are_read_from_cache() {
+        let temp = tempfile::tempdir().unwrap();
+        let cache = SourceCache::new()
+            .dir(temp.path().join("cache"))
+            .lockfile(temp.path().join("ethcontract.lock"));
+
+        cache.get(URL, || Ok("{}".to_string())).unwrap();
+        let content = cache.get(URL, || panic!("fetched pinned source")).unwrap();
+        assert_eq!(content, "{}");
+
+        fs::write(
+            temp.path()
+                .join("cache")
+                .join(blob_name(content_hash("{}"))),
+            "[]",
+        )
+        .unwrap();
+        assert!(cache.get(URL, || Ok("{}".to_string())).is_err());
+    }
+
+    #[test]
+    fn concurrent_updates_are_not_lost() {
+        let temp = tempfile::tempdir().unwrap();
+        let cache = SourceCache::new()
+            .dir(temp.path().join("cache"))
+            .lockfile(temp.path().join("ethcontract.lock"));
+        let urls = (0..8)
+            .map(|i| format!("npm:contracts@{}/IERC20.json", i))
+            .collect::<Vec<_>>();
+
+        let threads = urls
+            .iter()
+            .cloned()
+            .map(|url| {
+                let cache = cache.clone();
+                thread::spawn(move || {
+                    let content = format!("{{\"url\":\"{}\"}}", url);
+                    cache.get(&url, || Ok(content)).unwrap();
+                })
+            })
+            .collect::<Vec<_>>();
+        for thread in threads {
+            thread.join().unwrap();
+        }
+
+        let index = read_hashes(&temp.path().join("cache").join(INDEX_FILE)).unwrap();
+        let lockfile = read_hashes(&temp.path().join("ethcontract.lock")).unwrap();
+        for url in &urls {
+            assert!(index.contains_key(url));
+            assert!(lockfile.contains_key(url));
+        }
+    }
+
+    #[test]
+    fn stale_locks_are_broken() {
+        let temp = tempfile::tempdir().unwrap();
+        let path = temp.path().join(INDEX_FILE);
+        let lock = temp.path().join("index.json.lock");
+        fs::write(&lock, "").unwrap();
+
+        let acquired = FileLock::acquire_with_timeout(&path, Duration::from_secs(0)).unwrap();
+        assert!(lock.exists());
+        drop(acquired);
+        assert!(!lock.exists());
+    }
+}
@@ -18,21 +18,32 @@ pub(crate) fn expand(cx: &Context) -> TokenStream {
 
     let contract_json = TruffleLoader::save_to_string(cx.contract).unwrap();
 
-    let deployments = cx.networks.iter().map(|(chain_id, network)| {
-        let chain_id = Literal::string(chain_id);
-        let address = expand_address(network.address);
-        let deployment_information = expand_deployment_information(network.deployment_information);
-
-        quote! {
-            contract.networks.insert(
-                #chain_id.to_owned(),
-                self::ethcontract::common::contract::Network {
-                    address: #address,
-                    deployment_information: #deployment_information,
-                },
-            );
-        }
+    // NOTE: Truffle artifacts can not represent deployment block numbers, so
+    //   deployments with block numbers are added to the contract separately.
+    let block_deployments = cx.contract.networks.iter().filter(|(_, network)| {
+        matches!(
+            network.deployment_information,
+            Some(DeploymentInformation::BlockNumber(_))
+        )
     });
+    let deployments = block_deployments
+        .chain(&cx.networks)
+        .map(|(chain_id, network)| {
+            let chain_id = Literal::string(chain_id);
+            let address = expand_address(network.address);
+            let deployment_information =
+                expand_deployment_information(network.deployment_information);
+
+            quote! {
+                contract.networks.insert(
+                    #chain_id.to_owned(),
+                    self::ethcontract::common::contract::Network {
+                        address: #address,
+                        deployment_information: #deployment_information,
+                    },
+                );
+            }
+        });
 
     quote! {
         #doc
@@ -146,12 +157,70 @@ pub(crate) fn expand(cx: &Context) -> TokenStream {
 
                 let transport = DynTransport::new(web3.transport().clone());
                 let web3 = Web3::new(transport);
-                let abi = Self::raw_contract().abi.clone();
-                let instance = Instance::with_deployment_info(web3, abi, address, deployment_information);
+                let contract = Self::raw_contract();
+                let instance = Instance::with_deployment_info(
+                    web3,
+                    contract.abi.clone(),
+                    address,
+                    deployment_information,
+                )
+                .with_deployed_bytecode(
+                    contract.deployed_bytecode.clone(),
+                    contract.immutable_references.values().flatten().copied(),
+                );
 
                 Contract::from_raw(instance)
             }
 
+            /// Creates a new contract instance for an upgradeable proxy at the
+            /// given `Address` using this contract as its implementation.
+            ///
+            /// The implementation is resolved from the standard EIP-1967 and
+            /// EIP-1822 proxy storage slots. The code of the resolved
+            /// implementation is verified to match the runtime bytecode of this
+            /// contract, so this fails for contracts without runtime bytecode.
+            pub async fn at_proxy<F, B, T>(
+                web3: &self::ethcontract::web3::api::Web3<T>,
+                proxy: self::ethcontract::Address,
+            ) -> Result<Self, self::ethcontract::errors::DeployError>
+            where
+                F: std::future::Future<
+                        Output = Result<
+                            self::ethcontract::json::Value,
+                            self::ethcontract::web3::Error,
+                        >,
+                    > + Send
+                    + 'static,
+                B: std::future::Future<
+                        Output = Result<
+                            Vec<
+                                Result<
+                                    self::ethcontract::json::Value,
+                                    self::ethcontract::web3::Error,
+                                >,
+                            >,
+                            self::ethcontract::web3::Error,
+                        >,
+                    > + Send
+                    + 'static,
+                T: self::ethcontract::web3::Transport<Out = F>
+                    + self::ethcontract::web3::BatchTransport<Batch = B>
+                    + Send
+                    + Sync
+                    + 'static,
+            {
+                use self::ethcontract::Instance;
+                use self::ethcontract::transport::DynTransport;
+                use self::ethcontract::web3::api::Web3;
+
+                let transport = DynTransport::new(web3.transport().clone());
+                let web3 = Web3::new(transport);
+                let contract = Self::raw_contract().clone();
+                let instance = Instance::at_proxy(web3, contract, proxy).await?;
+
+                Ok(Contract::from_raw(instance))
+            }
+
             /// Creates a contract from a raw instance.
             fn from_raw(instance: self::ethcontract::dyns::DynInstance) -> Self {
                 let methods = Methods { instance };
@@ -169,6 +238,24 @@ pub(crate) fn expand(cx: &Context) -> TokenStream {
                 self.raw_instance().deployment_information()
             }
 
+            /// Verifies that the code deployed at the contract address matches
+            /// the contract runtime bytecode.
+            pub async fn verify_code(&self) -> Result<(), self::ethcontract::errors::DeployError> {
+                self.raw_instance().verify_code().await
+            }
+
+            /// Records the deployment of this contract on the current network
+            /// into an artifact, so that it can be located with `deployed`
+            /// once the artifact is saved and bindings are generated again.
+            pub async fn record_deployment(
+                &self,
+                artifact: &mut self::ethcontract::common::artifact::Artifact,
+            ) -> Result<(), self::ethcontract::errors::DeployError> {
+                self.raw_instance()
+                    .record_deployment(artifact, Self::raw_contract())
+                    .await
+            }
+
             /// Returns a reference to the default method options used by this
             /// contract.
             pub fn defaults(&self) -> &self::ethcontract::contract::MethodDefaults {
@@ -2,7 +2,7 @@ use crate::generate::{methods, Context};
 use crate::util;
 use anyhow::{Context as _, Result};
 use inflector::Inflector;
-use proc_macro2::{Literal, TokenStream};
+use proc_macro2::{Ident, Literal, TokenStream};
 use quote::quote;
 
 pub(crate) fn expand(cx: &Context) -> Result<TokenStream> {
@@ -70,6 +70,63 @@ fn expand_deployed(cx: &Context) -> TokenStream {
     }
 }
 
+/// Returns the libraries that need to be linked for deploying the contract
+/// along with the field names for the generated `Libraries` struct.
+///
+/// Libraries are named by their fully qualified names when the contract has
+/// link references, and by their placeholder names otherwise. Since hashed
+/// `__$<hash>$__` placeholders do not contain the library name, their fields
+/// are named after the hash instead.
+fn expand_libraries(cx: &Context) -> Vec<(String, Ident)> {
+    let mut names = if cx.contract.link_references.is_empty() {
+        cx.contract
+            .bytecode
+            .undefined_libraries()
+            .map(String::from)
+            .collect::<Vec<_>>()
+    } else {
+        cx.contract
+            .link_references
+            .iter()
+            .flat_map(|(path, libraries)| {
+                libraries
+                    .keys()
+                    .map(move |library| format!("{}:{}", path, library))
+            })
+            .collect()
+    };
+    names.sort();
+
+    let short_name = |name: &str| name.rsplit(':').next().unwrap_or(name).to_string();
+    names
+        .iter()
+        .map(|name| {
+            if let Some(hash) = name
+                .strip_prefix('$')
+                .and_then(|name| name.strip_suffix('$'))
+            {
+                return (name.clone(), util::ident(&format!("library_{}", hash)));
+            }
+
+            // NOTE: Libraries with the same name from different source files
+            //   need their path in the field name to be unique.
+            let short = short_name(name);
+            let is_unique = names
+                .iter()
+                .filter(|name| short_name(name) == short)
+                .count()
+                == 1;
+            let field = if is_unique {
+                short
+            } else {
+                name.replace(|c: char| !c.is_ascii_alphanumeric(), "_")
+            };
+
+            (name.clone(), util::safe_ident(&field.to_snake_case()))
+        })
+        .collect()
+}
+
 fn expand_deploy(cx: &Context) -> Result<TokenStream> {
     if cx.contract.bytecode.is_empty() {
         // do not generate deploy method for contracts that have empty bytecode
@@ -88,12 +145,7 @@ fn expand_deploy(cx: &Context) -> Result<TokenStream> {
         None => (quote! {}, quote! {()}),
     };
 
-    let libs: Vec<_> = cx
-        .contract
-        .bytecode
-        .undefined_libraries()
-        .map(|name| (name, util::safe_ident(&name.to_snake_case())))
-        .collect();
+    let libs = expand_libraries(cx);
     let (lib_struct, lib_input, link) = if !libs.is_empty() {
         let lib_struct = {
             let lib_struct_fields = libs.iter().map(|(name, field)| {
@@ -118,7 +170,13 @@ fn expand_deploy(cx: &Context) -> Result<TokenStream> {
                 let name_lit = Literal::string(name);
 
                 quote! {
-                    bytecode.link(#name_lit, libs.#field).expect("valid library");
+                    bytecode
+                        .link_with_references(
+                            #name_lit,
+                            libs.#field,
+                            &Self::raw_contract().link_references,
+                        )
+                        .expect("valid library");
                 }
             });
 
@@ -204,3 +262,92 @@ fn expand_deploy(cx: &Context) -> Result<TokenStream> {
         }
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ContractBuilder;
+    use ethcontract_common::bytecode::library_placeholder;
+    use ethcontract_common::contract::LinkReference;
+    use ethcontract_common::{Address, Bytecode, Contract};
+
+    #[test]
+    fn expand_libraries_by_placeholder() {
+        let mut contract = Contract::with_name("Contract");
+        contract.bytecode = Bytecode::from_hex_str(&format!("0x73{:_<40}", "__SafeMath")).unwrap();
+
+        let context = Context::from_builder(&contract, ContractBuilder::new()).unwrap();
+        let libs = expand_libraries(&context);
+        assert_eq!(libs.len(), 1);
+        assert_eq!(libs[0].0, "SafeMath");
+        assert_eq!(libs[0].1, "safe_math");
+    }
+
+    #[test]
+    fn expand_libraries_by_hashed_placeholder() {
+        let placeholder = library_placeholder("contracts/Math.sol:Math");
+        let mut contract = Contract::with_name("Contract");
+        contract.bytecode = Bytecode::from_hex_str(&format!("0x73{}", placeholder)).unwrap();
+
+        let context = Context::from_builder(&contract, ContractBuilder::new()).unwrap();
+        let libs = expand_libraries(&context);
+        assert_eq!(libs.len(), 1);
+        assert_eq!(libs[0].0, placeholder.trim_matches('_'));
+        assert_eq!(
+            libs[0].1.to_string(),
+            format!("library_{}", &placeholder[3..37])
+        );
+
+        let mut bytecode = contract.bytecode.clone();
+        bytecode
+            .link_with_references(
+                &libs[0].0,
+                Address::repeat_byte(0x42),
+                &contract.link_references,
+            )
+            .unwrap();
+        assert!(!bytecode.requires_linking());
+
+        expand(&context).unwrap();
+    }
+
+    #[test]
+    fn expand_libraries_by_fully_qualified_name() {
+        let reference = vec![LinkReference {
+            start: 1,
+            length: 20,
+        }];
+        let mut contract = Contract::with_name("Contract");
+        for (path, library) in &[
+            ("contracts/Math.sol", "Math"),
+            ("contracts/Strings.sol", "Strings"),
+            ("lib/Strings.sol", "Strings"),
+        ] {
+            contract
+                .link_references
+                .entry(path.to_string())
+                .or_default()
+                .insert(library.to_string(), reference.clone());
+        }
+
+        let context = Context::from_builder(&contract, ContractBuilder::new()).unwrap();
+        let libs = expand_libraries(&context)
+            .into_iter()
+            .map(|(name, field)| (name, field.to_string()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            libs,
+            [
+                ("contracts/Math.sol:Math".to_string(), "math".to_string()),
+                (
+                    "contracts/Strings.sol:Strings".to_string(),
+                    "contracts_strings_sol_strings".to_string()
+                ),
+                (
+                    "lib/Strings.sol:Strings".to_string(),
+                    "lib_strings_sol_strings".to_string()
+                ),
+            ]
+        );
+    }
+}
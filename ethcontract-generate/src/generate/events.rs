@@ -386,6 +386,15 @@ fn expand_all_events(cx: &Context) -> TokenStream {
                     self.deployment_information(),
                 )
             }
+
+            /// Decodes all events emitted by this contract from the logs of a
+            /// transaction receipt.
+            pub fn decode_receipt_events(
+                &self,
+                receipt: &self::ethcontract::web3::types::TransactionReceipt,
+            ) -> Vec<self::ethcontract::Event<Event>> {
+                self.raw_instance().decode_receipt_events(receipt)
+            }
         }
 
         #event_enum
@@ -824,6 +833,56 @@ mod tests {
         });
     }
 
+    #[test]
+    fn expand_all_events_and_receipt_decoding() {
+        let mut contract = Contract::with_name("Contract");
+        let context = Context::from_builder(&contract, ContractBuilder::new()).unwrap();
+        assert_quote!(expand_all_events(&context), {});
+
+        contract.abi.events.insert(
+            "Foo".into(),
+            vec![Event {
+                name: "Foo".into(),
+                inputs: vec![EventParam {
+                    name: String::new(),
+                    kind: ParamType::Bool,
+                    indexed: false,
+                }],
+                anonymous: false,
+            }],
+        );
+
+        let context = Context::from_builder(&contract, ContractBuilder::new()).unwrap();
+
+        let event_enum = expand_event_enum(&context);
+        let event_parse_log = expand_event_parse_log(&context);
+
+        assert_quote!(expand_all_events(&context), {
+            impl Contract {
+                /// Returns a log stream with all events.
+                pub fn all_events(&self) -> self::ethcontract::dyns::DynAllEventsBuilder<Event> {
+                    self::ethcontract::dyns::DynAllEventsBuilder::new(
+                        self.raw_instance().web3(),
+                        self.address(),
+                        self.deployment_information(),
+                    )
+                }
+
+                /// Decodes all events emitted by this contract from the logs of a
+                /// transaction receipt.
+                pub fn decode_receipt_events(
+                    &self,
+                    receipt: &self::ethcontract::web3::types::TransactionReceipt,
+                ) -> Vec<self::ethcontract::Event<Event>> {
+                    self.raw_instance().decode_receipt_events(receipt)
+                }
+            }
+
+            #event_enum
+            #event_parse_log
+        });
+    }
+
     #[test]
     #[rustfmt::skip]
     fn expand_hash_value() {
@@ -10,12 +10,16 @@
 #[path = "test/macros.rs"]
 mod test_macros;
 
+pub mod cache;
+pub mod solc;
 pub mod source;
 
 mod generate;
 mod rustfmt;
 mod util;
 
+pub use crate::cache::SourceCache;
+pub use crate::solc::Solc;
 pub use crate::source::Source;
 pub use crate::util::parse_address;
 
@@ -24,9 +28,11 @@ pub use ethcontract_common::artifact::{Artifact, ContractMut, InsertResult};
 /// Convenience re-imports so that you don't have to add `ethcontract-common`
 /// as a dependency.
 pub mod loaders {
+    pub use ethcontract_common::artifact::forge::ForgeLoader;
     pub use ethcontract_common::artifact::hardhat::{
         Format as HardHatFormat, HardHatLoader, NetworkEntry,
     };
+    pub use ethcontract_common::artifact::solc::{Format as SolcFormat, SolcLoader};
     pub use ethcontract_common::artifact::truffle::TruffleLoader;
 }
 
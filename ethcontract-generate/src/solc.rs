@@ -0,0 +1,310 @@
+//! Compiles Solidity sources with a locally installed `solc` binary.
+//!
+//! Compiled sources produce `solc` standard JSON output which can be loaded
+//! using the [solc loader] in [standard JSON mode].
+//!
+//! # Examples
+//!
+//! Generate bindings for a Solidity contract from a build script, rebuilding
+//! them whenever the contract or any of its imports change:
+//!
+//! ```no_run
+//! use ethcontract_generate::loaders::{SolcFormat, SolcLoader};
+//! use ethcontract_generate::{solc, ContractBuilder, Solc};
+//!
+//! let json = Solc::new()
+//!     .remapping("@openzeppelin/=node_modules/@openzeppelin/")
+//!     .allow_path("node_modules")
+//!     .compile("contracts/Token.sol")
+//!     .unwrap();
+//! solc::rerun_if_changed(&json).unwrap();
+//!
+//! let artifact = SolcLoader::new()
+//!     .load_from_str(SolcFormat::StandardJson, &json)
+//!     .unwrap();
+//!
+//! ContractBuilder::new()
+//!     .generate(artifact.get("Token").unwrap())
+//!     .unwrap()
+//!     .write_to_file("src/token.rs")
+//!     .unwrap();
+//! ```
+//!
+//! [solc loader]: ethcontract_common::artifact::solc::SolcLoader
+//! [standard JSON mode]: ethcontract_common::artifact::solc::Format::StandardJson
+
+use anyhow::{anyhow, Context, Result};
+use serde_json::{json, Value};
+use std::env;
+use std::fs;
+use std::io::Write;
+use std::iter;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+/// Outputs requested from the compiler for every contract.
+const OUTPUT_SELECTION: &[&str] = &[
+    "abi",
+    "devdoc",
+    "userdoc",
+    "evm.bytecode.object",
+    "evm.bytecode.linkReferences",
+    "evm.deployedBytecode.object",
+    "evm.deployedBytecode.linkReferences",
+    "evm.deployedBytecode.immutableReferences",
+];
+
+/// A locally installed `solc` compiler.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Solc {
+    /// Path to the `solc` binary.
+    ///
+    /// Defaults to the value of the `SOLC` environment variable if it is set,
+    /// and to `solc` from the `PATH` otherwise.
+    pub binary: PathBuf,
+
+    /// Additional directories from which the compiler is allowed to load
+    /// imported sources.
+    ///
+    /// The directory containing the compiled source is always allowed.
+    pub allow_paths: Vec<PathBuf>,
+
+    /// Import remappings of the form `prefix=target`, for example
+    /// `@openzeppelin/=node_modules/@openzeppelin/`.
+    pub remappings: Vec<String>,
+}
+
+impl Solc {
+    /// Creates a new compiler that uses the `solc` binary specified by the
+    /// `SOLC` environment variable, or `solc` from the `PATH`.
+    pub fn new() -> Self {
+        Solc::with_binary(env::var_os("SOLC").unwrap_or_else(|| "solc".into()))
+    }
+
+    /// Creates a new compiler that uses the specified `solc` binary.
+    pub fn with_binary(binary: impl Into<PathBuf>) -> Self {
+        Solc {
+            binary: binary.into(),
+            allow_paths: Vec::new(),
+            remappings: Vec::new(),
+        }
+    }
+
+    /// Adds a directory to the list of [`allowed paths`].
+    ///
+    /// [`allowed paths`]: #structfield.allow_paths
+    pub fn allow_path(mut self, path: impl Into<PathBuf>) -> Self {
+        self.allow_paths.push(path.into());
+        self
+    }
+
+    /// Adds an import remapping, see [`remappings`] for more details.
+    ///
+    /// [`remappings`]: #structfield.remappings
+    pub fn remapping(mut self, remapping: impl Into<String>) -> Self {
+        self.remappings.push(remapping.into());
+        self
+    }
+
+    /// Configures the compiler for sources within a project directory.
+    ///
+    /// The project directory is allowed for imports and the remappings
+    /// listed in its `remappings.txt` file, if any, are added with relative
+    /// targets resolved against it.
+    pub fn project_root(mut self, root: impl AsRef<Path>) -> Result<Self> {
+        let root = root.as_ref();
+        let remappings = root.join("remappings.txt");
+        if remappings.is_file() {
+            let content = fs::read_to_string(&remappings)
+                .with_context(|| format!("failed to read {}", remappings.display()))?;
+            for line in content
+                .lines()
+                .map(str::trim)
+                .filter(|line| !line.is_empty())
+            {
+                let (prefix, target) = line
+                    .split_once('=')
+                    .ok_or_else(|| anyhow!("invalid remapping '{}'", line))?;
+                let target = root.join(target).display().to_string();
+                self = self.remapping(format!("{}={}", prefix, target));
+            }
+        }
+
+        Ok(self.allow_path(root))
+    }
+
+    /// Compiles a Solidity source file and returns the compiler's standard
+    /// JSON output.
+    ///
+    /// Note that compilation errors are reported in the output rather than
+    /// as an error of this method. They are detected when the output is
+    /// loaded with the [solc loader].
+    ///
+    /// [solc loader]: ethcontract_common::artifact::solc::SolcLoader
+    pub fn compile(&self, path: impl AsRef<Path>) -> Result<String> {
+        let path = path.as_ref();
+        let path = path
+            .canonicalize()
+            .with_context(|| format!("unable to canonicalize path {}", path.display()))?;
+        let content = fs::read_to_string(&path)
+            .with_context(|| format!("failed to read Solidity source {}", path.display()))?;
+
+        let parent = path.parent().unwrap_or(&path);
+        let allow_paths = iter::once(parent)
+            .chain(self.allow_paths.iter().map(PathBuf::as_path))
+            .map(|path| path.display().to_string())
+            .collect::<Vec<_>>()
+            .join(",");
+
+        let mut child = Command::new(&self.binary)
+            .arg("--standard-json")
+            .arg("--allow-paths")
+            .arg(allow_paths)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .with_context(|| format!("failed to run {}", self.binary.display()))?;
+
+        let input = standard_json_input(&path.to_string_lossy(), &content, &self.remappings);
+        child
+            .stdin
+            .take()
+            .expect("solc stdin is piped")
+            .write_all(input.to_string().as_bytes())?;
+
+        let output = child.wait_with_output()?;
+        if !output.status.success() {
+            return Err(anyhow!(
+                "{} exited with {}: {}",
+                self.binary.display(),
+                output.status,
+                String::from_utf8_lossy(&output.stderr).trim(),
+            ));
+        }
+
+        Ok(String::from_utf8(output.stdout)?)
+    }
+}
+
+impl Default for Solc {
+    fn default() -> Self {
+        Solc::new()
+    }
+}
+
+/// Creates the standard JSON input for compiling a single source file.
+///
+/// Source file is named by its absolute path, so that the compiler resolves
+/// relative imports against its directory.
+fn standard_json_input(name: &str, content: &str, remappings: &[String]) -> Value {
+    json!({
+        "language": "Solidity",
+        "sources": {
+            name: { "content": content },
+        },
+        "settings": {
+            "remappings": remappings,
+            "outputSelection": {
+                "*": { "*": OUTPUT_SELECTION },
+            },
+        },
+    })
+}
+
+/// Returns paths to all sources present in `solc` standard JSON output, that is
+/// the compiled sources and all of the sources they import.
+pub fn output_sources(json: &str) -> Result<Vec<PathBuf>> {
+    let output: Value = serde_json::from_str(json).context("failed to parse solc output")?;
+    let sources = match output.get("sources") {
+        Some(Value::Object(sources)) => sources,
+        _ => {
+            return Err(anyhow!(
+                "solc output does not contain any sources: {}",
+                output["errors"]
+            ))
+        }
+    };
+
+    Ok(sources.keys().map(PathBuf::from).collect())
+}
+
+/// Instructs Cargo to re-run the build script when any of the sources in
+/// `solc` standard JSON output change, that is the compiled sources and all of
+/// the sources they import.
+///
+/// This function is intended to be used from build scripts with the output
+/// of [`Solc::compile`], it prints a `cargo:rerun-if-changed` line for every
+/// source to the standard output.
+pub fn rerun_if_changed(json: &str) -> Result<()> {
+    for source in output_sources(json)? {
+        println!("cargo:rerun-if-changed={}", source.display());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn standard_json_input_selects_outputs() {
+        let remappings = ["@openzeppelin/=/node_modules/@openzeppelin/".to_string()];
+        let input = standard_json_input("/contracts/Token.sol", "contract Token {}", &remappings);
+
+        assert_eq!(input["language"], "Solidity");
+        assert_eq!(
+            input["sources"]["/contracts/Token.sol"]["content"],
+            "contract Token {}"
+        );
+        assert_eq!(input["settings"]["remappings"], json!(remappings));
+        assert_eq!(
+            input["settings"]["outputSelection"]["*"]["*"],
+            json!(OUTPUT_SELECTION)
+        );
+    }
+
+    #[test]
+    fn project_root_reads_remappings() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        fs::write(
+            root.join("remappings.txt"),
+            "@openzeppelin/=lib/openzeppelin/contracts/\n\nds-test/=/abs/ds-test/\n",
+        )
+        .unwrap();
+
+        let solc = Solc::with_binary("solc").project_root(root).unwrap();
+        assert_eq!(solc.allow_paths, [root]);
+        assert_eq!(
+            solc.remappings,
+            [
+                format!(
+                    "@openzeppelin/={}",
+                    root.join("lib/openzeppelin/contracts/").display()
+                ),
+                "ds-test/=/abs/ds-test/".to_string(),
+            ]
+        );
+    }
+
+    #[test]
+    fn output_sources_lists_imports() {
+        let json = r#"{
+            "sources": {
+                "/contracts/Math.sol": { "id": 1 },
+                "/contracts/Token.sol": { "id": 0 }
+            },
+            "contracts": {}
+        }"#;
+
+        assert_eq!(
+            output_sources(json).unwrap(),
+            [
+                PathBuf::from("/contracts/Math.sol"),
+                PathBuf::from("/contracts/Token.sol"),
+            ]
+        );
+        assert!(output_sources(r#"{"errors":[]}"#).is_err());
+    }
+}
@@ -14,6 +14,15 @@
 //!     .expect("failed to load an artifact");
 //! ```
 //!
+//! Compile a Solidity source with a local `solc`:
+//!
+//! ```no_run
+//! # use ethcontract_generate::Source;
+//! let json = Source::parse("solc:contracts/IERC20.sol")
+//!     .and_then(|source| source.artifact_json())
+//!     .expect("failed to compile a contract");
+//! ```
+//!
 //! Load artifact from an NPM package:
 //!
 //! ```no_run
@@ -23,6 +32,8 @@
 //!     .expect("failed to load an artifact");
 //! ```
 
+use crate::cache::SourceCache;
+use crate::solc::Solc;
 use crate::util;
 use anyhow::{anyhow, Context, Error, Result};
 use ethcontract_common::Address;
@@ -60,6 +71,19 @@ pub enum Source {
     ///
     /// [unpkg]: unpkg.io
     Npm(String),
+
+    /// Solidity source file on the local file system, compiled with a locally
+    /// installed [`Solc`] compiler.
+    ///
+    /// The `solc` binary can be configured with the `SOLC` environment
+    /// variable. Sources are compiled for the crate root directory, see
+    /// [`Solc::project_root`], so they can import any file within it and use
+    /// the remappings from its `remappings.txt`. Compiled sources can be
+    /// parsed using the [solc loader] in [standard JSON mode].
+    ///
+    /// [solc loader]: ethcontract_common::artifact::solc::SolcLoader
+    /// [standard JSON mode]: ethcontract_common::artifact::solc::Format::StandardJson
+    Solidity(PathBuf),
 }
 
 impl Source {
@@ -86,7 +110,12 @@ impl Source {
     /// - a URL with `npm` scheme, NPM package name, an optional version
     ///   and a path (defaulting to the latest version and `index.js`).
     ///   For example `npm:@openzeppelin/contracts/build/contracts/IERC20.json`.
-    ///   The contract artifact or ABI will be retrieved through [`unpkg`].
+    ///   The contract artifact or ABI will be retrieved through [`unpkg`];
+    ///
+    /// - a URL with `solc` scheme and a path to a Solidity source file, for
+    ///   example `solc:contracts/IERC20.sol`. Local paths to files with `.sol`
+    ///   extension are treated the same way. The source will be compiled
+    ///   with a local `solc`, see [`Source::Solidity`] for more details.
     ///
     /// [Etherscan]: etherscan.io
     /// [unpkg]: unpkg.io
@@ -105,6 +134,7 @@ impl Source {
         let url = base.join(source.as_ref())?;
 
         match url.scheme() {
+            "file" if url.path().ends_with(".sol") => Ok(Source::solidity(url.path())),
             "file" => Ok(Source::local(url.path())),
             "http" | "https" => match url.host_str() {
                 Some("etherscan.io") => Source::etherscan(
@@ -117,6 +147,7 @@ impl Source {
             },
             "etherscan" => Source::etherscan(url.path()),
             "npm" => Ok(Source::npm(url.path())),
+            "solc" => Ok(Source::solidity(root.join(url.path()))),
             _ => Err(anyhow!("unsupported URL '{}'", url)),
         }
     }
@@ -145,21 +176,43 @@ impl Source {
         Source::Npm(package_path.into())
     }
 
+    /// Creates a Solidity source from a path to a source file.
+    pub fn solidity(path: impl AsRef<Path>) -> Self {
+        Source::Solidity(path.as_ref().into())
+    }
+
     /// Retrieves the source JSON of the artifact.
     ///
     /// This will either read the JSON from the file system or retrieve
     /// a contract ABI from the network, depending on the source type.
     ///
     /// Contract ABIs will be wrapped into a JSON object, so that you can load
-    /// them using the [truffle loader].
+    /// them using the [truffle loader]. Solidity sources are compiled and
+    /// the `solc` standard JSON output is returned.
     ///
     /// [truffle loader]: ethcontract_common::artifact::truffle::TruffleLoader
+    ///
+    /// Remote sources are cached according to the environment, see
+    /// [`SourceCache::from_env`] for more details.
     pub fn artifact_json(&self) -> Result<String> {
+        self.artifact_json_with_cache(&SourceCache::from_env())
+    }
+
+    /// Retrieves the source JSON of the artifact, using the specified cache
+    /// for remote sources.
+    pub fn artifact_json_with_cache(&self, cache: &SourceCache) -> Result<String> {
         match self {
             Source::Local(path) => get_local_contract(path),
-            Source::Http(url) => get_http_contract(url),
-            Source::Etherscan(address) => get_etherscan_contract(*address),
-            Source::Npm(package) => get_npm_contract(package),
+            Source::Http(url) => cache.get(url.as_str(), || get_http_contract(url)),
+            Source::Etherscan(address) => cache.get(&format!("etherscan:{:?}", address), || {
+                get_etherscan_contract(*address)
+            }),
+            Source::Npm(package) => {
+                cache.get(&format!("npm:{}", package), || get_npm_contract(package))
+            }
+            Source::Solidity(path) => Solc::new()
+                .project_root(util::project_root()?)?
+                .compile(path),
         }
     }
 }
@@ -284,6 +337,18 @@ mod tests {
                 "npm:@openzeppelin/contracts@2.5.0/build/contracts/IERC20.json",
                 Source::npm("@openzeppelin/contracts@2.5.0/build/contracts/IERC20.json"),
             ),
+            (
+                "solc:contracts/IERC20.sol",
+                Source::solidity("/rooted/contracts/IERC20.sol"),
+            ),
+            (
+                "solc:/absolute/IERC20.sol",
+                Source::solidity("/absolute/IERC20.sol"),
+            ),
+            (
+                "relative/IERC20.sol",
+                Source::solidity("/rooted/relative/IERC20.sol"),
+            ),
         ] {
             let source = Source::with_root(root, url).unwrap();
             assert_eq!(source, *expected);
@@ -4,6 +4,8 @@ use ethcontract_common::Address;
 use inflector::Inflector;
 use proc_macro2::{Ident, Literal, Span, TokenStream};
 use quote::quote;
+use std::env;
+use std::path::PathBuf;
 use syn::Ident as SynIdent;
 
 /// Expands a identifier string into an token.
@@ -53,6 +55,15 @@ where
     Ok(address_str[2..].parse()?)
 }
 
+/// Returns the root directory of the crate being built, that is the
+/// `CARGO_MANIFEST_DIR` if it is set and the current directory otherwise.
+pub fn project_root() -> Result<PathBuf> {
+    match env::var_os("CARGO_MANIFEST_DIR") {
+        Some(root) => Ok(PathBuf::from(root)),
+        None => Ok(env::current_dir()?),
+    }
+}
+
 /// Performs an HTTP GET request and return the contents of the response.
 pub fn http_get(url: &str) -> Result<String> {
     let mut buffer = Vec::new();
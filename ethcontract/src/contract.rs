@@ -5,6 +5,7 @@
 mod deploy;
 mod event;
 mod method;
+pub mod proxy;
 
 use crate::{
     errors::{DeployError, LinkError},
@@ -12,18 +13,22 @@ use crate::{
 };
 use ethcontract_common::abi::{Error as AbiError, Result as AbiResult};
 use ethcontract_common::abiext::FunctionExt;
+use ethcontract_common::artifact::Artifact;
+use ethcontract_common::contract::{ImmutableReference, LinkReferences, Network};
 use ethcontract_common::hash::H32;
 use ethcontract_common::{Abi, Bytecode, Contract, DeploymentInformation};
 use std::collections::HashMap;
 use std::hash::Hash;
 use web3::api::Web3;
-use web3::types::{Address, Bytes, H256};
+use web3::types::{Address, Bytes, TransactionReceipt, H256};
 use web3::Transport;
 
-pub use self::deploy::{Deploy, DeployBuilder};
+pub use self::deploy::{
+    create2_address, create_address, Create2Factory, Deploy, DeployBuilder, LibraryDeployer,
+};
 pub use self::event::{
-    AllEventsBuilder, Event, EventBuilder, EventMetadata, EventStatus, ParseLog, RawLog,
-    StreamEvent, Topic,
+    decode_receipt_events, decode_receipt_logs, AllEventsBuilder, DecodedLog, Event, EventBuilder,
+    EventMetadata, EventStatus, ParseLog, RawLog, StreamEvent, Topic,
 };
 pub use self::method::{MethodBuilder, MethodDefaults, ViewMethodBuilder};
 use std::marker::PhantomData;
@@ -64,6 +69,11 @@ pub struct Instance<T: Transport> {
     abi: Abi,
     address: Address,
     deployment_information: Option<DeploymentInformation>,
+    /// The contract runtime bytecode used for verifying the code deployed at
+    /// the instance address.
+    deployed_bytecode: Bytecode,
+    /// The locations of immutable variables in the runtime bytecode.
+    immutable_references: Vec<ImmutableReference>,
     /// Default method parameters to use when sending method transactions or
     /// querying method calls.
     pub defaults: MethodDefaults,
@@ -108,6 +118,8 @@ impl<T: Transport> Instance<T> {
             abi,
             address,
             deployment_information,
+            deployed_bytecode: Bytecode::default(),
+            immutable_references: Vec::new(),
             defaults: MethodDefaults::default(),
             methods,
             events,
@@ -118,7 +130,8 @@ impl<T: Transport> Instance<T> {
     /// the `web3` provider from the given `Contract`'s ABI and networks.
     ///
     /// Note that this does not verify that a contract with a matching `Abi` is
-    /// actually deployed at the given address.
+    /// actually deployed at the given address, use [`Instance::verify_code`]
+    /// for that.
     pub async fn deployed(web3: Web3<T>, contract: Contract) -> Result<Self, DeployError> {
         let network_id = web3.net().version().await?;
         let network = contract
@@ -131,9 +144,118 @@ impl<T: Transport> Instance<T> {
             contract.abi,
             network.address,
             network.deployment_information,
+        )
+        .with_deployed_bytecode(
+            contract.deployed_bytecode,
+            contract.immutable_references.into_values().flatten(),
         ))
     }
 
+    /// Records the deployment of this instance on the network of its `web3`
+    /// provider into an artifact, so that it can be located with
+    /// [`Instance::deployed`] once the artifact is saved and loaded again.
+    ///
+    /// The contract is inserted into the artifact if it is not there yet, and
+    /// replaces any previous deployment on the same network.
+    pub async fn record_deployment(
+        &self,
+        artifact: &mut Artifact,
+        contract: &Contract,
+    ) -> Result<(), DeployError> {
+        let network_id = self.web3.net().version().await?;
+        artifact.record_deployment(
+            contract,
+            network_id,
+            Network {
+                address: self.address,
+                deployment_information: self.deployment_information,
+            },
+        )?;
+
+        Ok(())
+    }
+
+    /// Sets the contract runtime bytecode and the locations of its immutable
+    /// variables used for verifying the code deployed at the instance address.
+    pub fn with_deployed_bytecode<I>(mut self, bytecode: Bytecode, immutable_references: I) -> Self
+    where
+        I: IntoIterator<Item = ImmutableReference>,
+    {
+        self.deployed_bytecode = bytecode;
+        self.immutable_references = immutable_references.into_iter().collect();
+        self
+    }
+
+    /// Verifies that the code deployed at the instance address matches the
+    /// contract runtime bytecode, ignoring the `solc` metadata trailer, linked
+    /// libraries and immutable variables. This allows misconfigured deployment
+    /// addresses to be detected early.
+    pub async fn verify_code(&self) -> Result<(), DeployError> {
+        if self.deployed_bytecode.is_empty() {
+            return Err(DeployError::NoDeployedBytecode);
+        }
+
+        let code = self.web3.eth().code(self.address, None).await?;
+        if !self
+            .deployed_bytecode
+            .matches_runtime_code(&code.0, &self.immutable_references)
+        {
+            return Err(DeployError::CodeMismatch(self.address));
+        }
+
+        Ok(())
+    }
+
+    /// Creates a new contract instance for an upgradeable proxy at the given
+    /// `Address` with the `Abi` of its implementation `Contract`.
+    ///
+    /// The implementation is resolved from the standard EIP-1967 and EIP-1822
+    /// proxy storage slots and its code is verified to match the runtime
+    /// bytecode of the contract, so the contract must have runtime bytecode.
+    /// Use [`Instance::at_proxy_unchecked`] for contracts without it. Note that
+    /// the instance is bound to the proxy address, so it keeps working across
+    /// upgrades even though the implementation is only verified once.
+    pub async fn at_proxy(
+        web3: Web3<T>,
+        contract: Contract,
+        proxy: Address,
+    ) -> Result<Self, DeployError> {
+        if contract.deployed_bytecode.is_empty() {
+            return Err(DeployError::NoDeployedBytecode);
+        }
+
+        let implementation = proxy::resolve_implementation(&web3, proxy)
+            .await?
+            .ok_or(DeployError::NotAProxy(proxy))?;
+        Instance::at(web3.clone(), Abi::default(), implementation)
+            .with_deployed_bytecode(
+                contract.deployed_bytecode,
+                contract.immutable_references.into_values().flatten(),
+            )
+            .verify_code()
+            .await?;
+
+        Ok(Instance::at(web3, contract.abi, proxy))
+    }
+
+    /// Creates a new contract instance for an upgradeable proxy at the given
+    /// `Address` with the specified `Abi`, without verifying the code of its
+    /// implementation.
+    ///
+    /// This only checks that the address is a proxy with an implementation set
+    /// in one of the standard EIP-1967 and EIP-1822 proxy storage slots.
+    pub async fn at_proxy_unchecked(
+        web3: Web3<T>,
+        abi: Abi,
+        proxy: Address,
+    ) -> Result<Self, DeployError> {
+        proxy::resolve_implementation(&web3, proxy)
+            .await?
+            .ok_or(DeployError::NotAProxy(proxy))?;
+
+        Ok(Instance::at(web3, abi, proxy))
+    }
+
     /// Creates a contract builder with the specified `web3` provider and the
     /// given `Contract` byte code. This allows the contract deployment
     /// transaction to be configured before deploying the contract.
@@ -285,6 +407,16 @@ impl<T: Transport> Instance<T> {
     pub fn all_events(&self) -> AllEventsBuilder<T, RawLog> {
         AllEventsBuilder::new(self.web3(), self.address(), self.deployment_information())
     }
+
+    /// Decodes the events emitted by this contract instance from the logs of a
+    /// transaction receipt. Logs emitted by other contracts or that cannot be
+    /// parsed into `E` are skipped.
+    pub fn decode_receipt_events<E>(&self, receipt: &TransactionReceipt) -> Vec<Event<E>>
+    where
+        E: ParseLog,
+    {
+        decode_receipt_events(receipt, self.address())
+    }
 }
 
 /// Builder for specifying linking options for a contract.
@@ -294,6 +426,8 @@ pub struct Linker {
     abi: Abi,
     /// The deployment code for the contract.
     bytecode: Bytecode,
+    /// The locations of library addresses in the deployment code.
+    link_references: LinkReferences,
 }
 
 impl Linker {
@@ -302,6 +436,7 @@ impl Linker {
         Linker {
             abi: contract.abi,
             bytecode: contract.bytecode,
+            link_references: contract.link_references,
         }
     }
 
@@ -309,15 +444,16 @@ impl Linker {
     /// incrementally link so that we can verify each time a library is linked
     /// whether it was successful or not.
     ///
-    /// # Panics
-    ///
-    /// Panics if an invalid library name is used (for example if it is more
-    /// than 38 characters long).
+    /// Libraries can be specified by their fully qualified name (for example
+    /// `contracts/Math.sol:Math`) or by their name. The contract's link
+    /// references are used for linking when available, otherwise the library
+    /// placeholders in the bytecode are replaced.
     pub fn library<S>(mut self, name: S, address: Address) -> Result<Linker, LinkError>
     where
         S: AsRef<str>,
     {
-        self.bytecode.link(name, address)?;
+        self.bytecode
+            .link_with_references(name, address, &self.link_references)?;
         Ok(self)
     }
 
@@ -388,7 +524,6 @@ where
 mod tests {
     use super::*;
     use crate::test::prelude::*;
-    use ethcontract_common::contract::Network;
     use ethcontract_common::Contract;
     use web3::types::H256;
 
@@ -428,6 +563,43 @@ mod tests {
         );
     }
 
+    #[test]
+    fn record_deployment() {
+        let mut transport = TestTransport::new();
+        let web3 = Web3::new(transport.clone());
+
+        let address = addr!("0x0102030405060708091011121314151617181920");
+        let contract = Contract::with_name("Contract");
+        let instance = Instance::with_deployment_info(
+            web3.clone(),
+            contract.abi.clone(),
+            address,
+            Some(DeploymentInformation::BlockNumber(1337)),
+        );
+
+        let mut artifact = Artifact::new();
+        transport.add_response(json!("42"));
+        instance
+            .record_deployment(&mut artifact, &contract)
+            .immediate()
+            .expect("failed to record deployment");
+
+        transport.assert_request("net_version", &[]);
+        transport.assert_no_more_requests();
+
+        let contract = artifact.get("Contract").unwrap().clone();
+        transport.add_response(json!("42"));
+        let deployed = Instance::deployed(web3, contract)
+            .immediate()
+            .expect("failed to find recorded deployment");
+
+        assert_eq!(deployed.address(), address);
+        assert_eq!(
+            deployed.deployment_information(),
+            Some(DeploymentInformation::BlockNumber(1337))
+        );
+    }
+
     #[test]
     fn deployed_not_found() {
         let mut transport = TestTransport::new();
@@ -453,4 +625,147 @@ mod tests {
             err
         );
     }
+
+    #[test]
+    fn at_proxy_verifies_implementation() {
+        let mut transport = TestTransport::new();
+        let web3 = Web3::new(transport.clone());
+
+        let proxy = addr!("0x0101010101010101010101010101010101010101");
+        let implementation = addr!("0x0202020202020202020202020202020202020202");
+        let contract = Contract {
+            deployed_bytecode: Bytecode::from_hex_str(
+                "0x7f0000000000000000000000000000000000000000000000000000000000000000",
+            )
+            .unwrap(),
+            immutable_references: vec![(
+                "1".to_owned(),
+                vec![ImmutableReference {
+                    start: 1,
+                    length: 32,
+                }],
+            )]
+            .into_iter()
+            .collect(),
+            ..Contract::empty()
+        };
+
+        transport.add_response(json!(H256::from(implementation)));
+        transport.add_response(json!(format!("0x7f{}", "42".repeat(32))));
+        let instance = Instance::at_proxy(web3.clone(), contract.clone(), proxy)
+            .immediate()
+            .expect("failed to create proxy instance");
+
+        transport.assert_request(
+            "eth_getStorageAt",
+            &[
+                json!(proxy),
+                json!(proxy::IMPLEMENTATION_SLOT),
+                json!("latest"),
+            ],
+        );
+        transport.assert_request("eth_getCode", &[json!(implementation), json!("latest")]);
+        transport.assert_no_more_requests();
+        assert_eq!(instance.address(), proxy);
+
+        transport.add_response(json!(H256::from(implementation)));
+        transport.add_response(json!("0x00"));
+        let err = Instance::at_proxy(web3, contract, proxy)
+            .immediate()
+            .expect_err("unexpected success with mismatched code");
+
+        assert!(
+            matches!(&err, DeployError::CodeMismatch(address) if *address == implementation),
+            "expected code mismatch error but got '{:?}'",
+            err
+        );
+    }
+
+    #[test]
+    fn at_proxy_requires_deployed_bytecode() {
+        let transport = TestTransport::new();
+        let web3 = Web3::new(transport.clone());
+
+        let proxy = addr!("0x0101010101010101010101010101010101010101");
+        let err = Instance::at_proxy(web3, Contract::empty(), proxy)
+            .immediate()
+            .expect_err("unexpected success without deployed bytecode");
+
+        assert!(
+            matches!(err, DeployError::NoDeployedBytecode),
+            "expected no deployed bytecode error but got '{:?}'",
+            err
+        );
+        transport.assert_no_more_requests();
+    }
+
+    #[test]
+    fn at_proxy_unchecked() {
+        let mut transport = TestTransport::new();
+        let web3 = Web3::new(transport.clone());
+
+        let proxy = addr!("0x0101010101010101010101010101010101010101");
+        let implementation = addr!("0x0202020202020202020202020202020202020202");
+
+        transport.add_response(json!(H256::from(implementation)));
+        let instance = Instance::at_proxy_unchecked(web3, Abi::default(), proxy)
+            .immediate()
+            .expect("failed to create proxy instance");
+
+        transport.assert_request(
+            "eth_getStorageAt",
+            &[
+                json!(proxy),
+                json!(proxy::IMPLEMENTATION_SLOT),
+                json!("latest"),
+            ],
+        );
+        transport.assert_no_more_requests();
+        assert_eq!(instance.address(), proxy);
+    }
+
+    #[test]
+    fn verify_code() {
+        let mut transport = TestTransport::new();
+        let web3 = Web3::new(transport.clone());
+
+        let address = addr!("0x0102030405060708091011121314151617181920");
+        let instance = Instance::at(web3, Abi::default(), address);
+
+        let err = instance
+            .verify_code()
+            .immediate()
+            .expect_err("unexpected success verifying without bytecode");
+        assert!(matches!(err, DeployError::NoDeployedBytecode));
+
+        let instance = instance.with_deployed_bytecode(
+            Bytecode::from_hex_str(
+                "0x7f0000000000000000000000000000000000000000000000000000000000000000",
+            )
+            .unwrap(),
+            vec![ImmutableReference {
+                start: 1,
+                length: 32,
+            }],
+        );
+
+        transport.add_response(json!(format!("0x7f{}", "42".repeat(32))));
+        instance
+            .verify_code()
+            .immediate()
+            .expect("failed to verify code");
+        transport.assert_request("eth_getCode", &[json!(address), json!("latest")]);
+        transport.assert_no_more_requests();
+
+        transport.add_response(json!("0x"));
+        let err = instance
+            .verify_code()
+            .immediate()
+            .expect_err("unexpected success verifying empty code");
+        assert!(
+            matches!(err, DeployError::CodeMismatch(a) if a == address),
+            "expected code mismatch error but got '{:?}'",
+            err
+        );
+    }
 }
@@ -1,14 +1,23 @@
 //! Implementation for creating instances for deployed contracts and deploying
 //! new contracts.
 
+mod create;
+mod create2;
+mod libraries;
+
+pub use self::create::create_address;
+pub use self::create2::{create2_address, Create2Factory};
+pub use self::libraries::LibraryDeployer;
 use crate::errors::{DeployError, ExecutionError};
 use crate::tokens::Tokenize;
-use crate::transaction::{Account, GasPrice, TransactionBuilder, TransactionResult};
+use crate::transaction::{
+    resolve_sender, Account, GasPolicy, GasPrice, TransactionBuilder, TransactionResult,
+};
 use ethcontract_common::abi::Error as AbiError;
 use ethcontract_common::{Abi, Bytecode};
 use std::marker::PhantomData;
 use web3::api::Web3;
-use web3::types::{Address, Bytes, H256, U256};
+use web3::types::{Address, BlockNumber, Bytes, H256, U256};
 use web3::Transport;
 
 /// a factory trait for deployable contract instances. this traits provides
@@ -48,8 +57,14 @@ where
     web3: Web3<T>,
     /// The factory context.
     context: I::Context,
+    /// The contract init code, that is the contract bytecode followed by the
+    /// ABI encoded constructor parameters.
+    init_code: Bytes,
     /// The underlying transaction used t
     tx: TransactionBuilder<T>,
+    /// The address the contract gets deployed to when it is deployed through
+    /// a `CREATE2` factory.
+    create2_address: Option<Address>,
     _instance: PhantomData<I>,
 }
 
@@ -87,7 +102,9 @@ where
         Ok(DeployBuilder {
             web3: web3.clone(),
             context,
+            init_code: data.clone(),
             tx: TransactionBuilder::new(web3).data(data).confirmations(0),
+            create2_address: None,
             _instance: PhantomData,
         })
     }
@@ -113,6 +130,13 @@ where
         self
     }
 
+    /// Specify the gas policy to use for turning gas estimates into a gas
+    /// limit, if not specified then the gas estimate will be used as is.
+    pub fn gas_policy(mut self, value: GasPolicy) -> Self {
+        self.tx = self.tx.gas_policy(value);
+        self
+    }
+
     /// Specify what how much ETH to transfer with the transaction, if not
     /// specified then no ETH will be sent.
     pub fn value(mut self, value: U256) -> Self {
@@ -135,6 +159,63 @@ where
         self
     }
 
+    /// Deploy the contract with `CREATE2` through the specified factory with a
+    /// salt instead of creating it directly with the deployment transaction.
+    /// The address of the deployed contract only depends on the factory, the
+    /// salt and the contract init code, and can be computed beforehand with
+    /// [`create2_address`].
+    pub fn create2(mut self, factory: Create2Factory, salt: H256) -> Self {
+        self.tx = self
+            .tx
+            .to(factory.address)
+            .data(factory.calldata(salt, &self.init_code.0));
+        self.create2_address = Some(factory.deployment_address(salt, &self.init_code.0));
+        self
+    }
+
+    /// Returns the contract init code, that is the contract bytecode followed
+    /// by the ABI encoded constructor parameters.
+    pub fn init_code(&self) -> &Bytes {
+        &self.init_code
+    }
+
+    /// Returns the address the contract will be deployed to.
+    ///
+    /// For `CREATE2` deployments the address is computed from the factory, the
+    /// salt and the init code. Otherwise, the sender and nonce are resolved
+    /// from the node if they were not specified and are fixed for the
+    /// deployment transaction, so that the contract gets deployed to the
+    /// returned address. Note that the deployment fails if another transaction
+    /// from the sender uses the nonce first.
+    pub async fn predict_address(&mut self) -> Result<Address, DeployError> {
+        if let Some(address) = self.create2_address {
+            return Ok(address);
+        }
+
+        let from = match &self.tx.from {
+            Some(account) => account.address(),
+            None => {
+                let from = resolve_sender(&self.web3, None).await?;
+                self.tx.from = Some(Account::Local(from, None));
+                from
+            }
+        };
+        let nonce = match self.tx.nonce {
+            Some(nonce) => nonce,
+            None => {
+                let nonce = self
+                    .web3
+                    .eth()
+                    .transaction_count(from, Some(BlockNumber::Pending))
+                    .await?;
+                self.tx.nonce = Some(nonce);
+                nonce
+            }
+        };
+
+        Ok(create_address(from, nonce))
+    }
+
     /// Extract inner `TransactionBuilder` from this `DeployBuilder`. This
     /// exposes `TransactionBuilder` only APIs.
     pub fn into_inner(self) -> TransactionBuilder<T> {
@@ -143,16 +224,39 @@ where
 
     /// Sign (if required) and execute the transaction. Returns the transaction
     /// hash that can be used to retrieve transaction information.
-    pub async fn deploy(self) -> Result<I, DeployError> {
+    ///
+    /// The address of the deployed contract is verified against the address
+    /// predicted from the sender and nonce, which get resolved before sending
+    /// the transaction if they were not specified. For `CREATE2` deployments,
+    /// the predicted address is instead verified to contain code, since the
+    /// factory may not revert when the contract creation fails.
+    pub async fn deploy(mut self) -> Result<I, DeployError> {
+        let predicted_address = self.predict_address().await?;
+
         let tx = match self.tx.send().await? {
             TransactionResult::Receipt(tx) => tx,
             TransactionResult::Hash(tx) => return Err(DeployError::Pending(tx)),
         };
 
         let transaction_hash = tx.transaction_hash;
-        let address = tx
-            .contract_address
-            .ok_or_else(|| ExecutionError::Failure(Box::new(tx)))?;
+        let address = match self.create2_address {
+            Some(address) => {
+                let code = self.web3.eth().code(address, None).await?;
+                if code.0.is_empty() {
+                    return Err(DeployError::NoCodeDeployed(address));
+                }
+                address
+            }
+            None => {
+                let address = tx
+                    .contract_address
+                    .ok_or_else(|| ExecutionError::Failure(Box::new(tx)))?;
+                if address != predicted_address {
+                    return Err(DeployError::AddressMismatch(predicted_address, address));
+                }
+                address
+            }
+        };
 
         Ok(I::from_deployment(
             self.web3,
@@ -169,6 +273,7 @@ mod tests {
     use crate::contract::{Instance, Linker};
     use crate::test::prelude::*;
     use ethcontract_common::{Bytecode, Contract};
+    use web3::types::H2048;
 
     type InstanceDeployBuilder<T> = DeployBuilder<T, Instance<T>>;
 
@@ -203,6 +308,280 @@ mod tests {
         transport.assert_no_more_requests();
     }
 
+    #[test]
+    fn deploy_create2() {
+        let mut transport = TestTransport::new();
+        let web3 = Web3::new(transport.clone());
+
+        let from = addr!("0x9876543210987654321098765432109876543210");
+        let factory = Create2Factory::default();
+        let salt = H256::repeat_byte(0x42);
+        let tx_hash = H256::repeat_byte(0xff);
+        let contract = Contract {
+            bytecode: Bytecode::from_hex_str("0x1337").unwrap(),
+            ..Contract::empty()
+        };
+        let address = create2_address(factory.address, salt, &[0x13, 0x37]);
+
+        transport.add_response(json!(tx_hash));
+        transport.add_response(json!("0x1"));
+        transport.add_response(json!({
+            "transactionHash": tx_hash,
+            "transactionIndex": "0x0",
+            "blockNumber": "0x1",
+            "blockHash": H256::repeat_byte(1),
+            "cumulativeGasUsed": "0x1337",
+            "gasUsed": "0x1337",
+            "logsBloom": H2048::zero(),
+            "logs": [],
+            "status": "0x1",
+        }));
+        transport.add_response(json!("0x1337"));
+
+        let instance = InstanceDeployBuilder::new(web3, Linker::new(contract), ())
+            .expect("error creating deploy builder")
+            .from(Account::Local(from, None))
+            .gas(1.into())
+            .gas_price(2.into())
+            .create2(factory, salt)
+            .deploy()
+            .immediate()
+            .expect("deployment failed");
+
+        transport.assert_request(
+            "eth_sendTransaction",
+            &[json!({
+                "from": from,
+                "to": factory.address,
+                "gas": "0x1",
+                "gasPrice": "0x2",
+                "data": format!("0x{}1337", hex::encode(salt)),
+            })],
+        );
+        transport.assert_request("eth_blockNumber", &[]);
+        transport.assert_request("eth_getTransactionReceipt", &[json!(tx_hash)]);
+        transport.assert_request("eth_getCode", &[json!(address), json!("latest")]);
+        transport.assert_no_more_requests();
+
+        assert_eq!(instance.address(), address);
+    }
+
+    #[test]
+    fn deploy_create2_without_code() {
+        let mut transport = TestTransport::new();
+        let web3 = Web3::new(transport.clone());
+
+        let from = addr!("0x9876543210987654321098765432109876543210");
+        let factory = Create2Factory::default();
+        let salt = H256::repeat_byte(0x42);
+        let tx_hash = H256::repeat_byte(0xff);
+        let contract = Contract {
+            bytecode: Bytecode::from_hex_str("0x1337").unwrap(),
+            ..Contract::empty()
+        };
+        let address = create2_address(factory.address, salt, &[0x13, 0x37]);
+
+        transport.add_response(json!(tx_hash));
+        transport.add_response(json!("0x1"));
+        transport.add_response(json!({
+            "transactionHash": tx_hash,
+            "transactionIndex": "0x0",
+            "blockNumber": "0x1",
+            "blockHash": H256::repeat_byte(1),
+            "cumulativeGasUsed": "0x1337",
+            "gasUsed": "0x1337",
+            "logsBloom": H2048::zero(),
+            "logs": [],
+            "status": "0x1",
+        }));
+        transport.add_response(json!("0x"));
+
+        let err = InstanceDeployBuilder::new(web3, Linker::new(contract), ())
+            .expect("error creating deploy builder")
+            .from(Account::Local(from, None))
+            .gas(1.into())
+            .gas_price(2.into())
+            .create2(factory, salt)
+            .deploy()
+            .immediate()
+            .expect_err("unexpected success deploying without code");
+
+        transport.assert_request(
+            "eth_sendTransaction",
+            &[json!({
+                "from": from,
+                "to": factory.address,
+                "gas": "0x1",
+                "gasPrice": "0x2",
+                "data": format!("0x{}1337", hex::encode(salt)),
+            })],
+        );
+        transport.assert_request("eth_blockNumber", &[]);
+        transport.assert_request("eth_getTransactionReceipt", &[json!(tx_hash)]);
+        transport.assert_request("eth_getCode", &[json!(address), json!("latest")]);
+        transport.assert_no_more_requests();
+
+        assert!(
+            matches!(err, DeployError::NoCodeDeployed(a) if a == address),
+            "expected no code deployed error but got '{:?}'",
+            err
+        );
+    }
+
+    fn receipt(tx_hash: H256, contract_address: Address) -> serde_json::Value {
+        json!({
+            "transactionHash": tx_hash,
+            "transactionIndex": "0x0",
+            "blockNumber": "0x1",
+            "blockHash": H256::repeat_byte(1),
+            "cumulativeGasUsed": "0x1337",
+            "gasUsed": "0x1337",
+            "contractAddress": contract_address,
+            "logsBloom": H2048::zero(),
+            "logs": [],
+            "status": "0x1",
+        })
+    }
+
+    #[test]
+    fn deploy_predict_address() {
+        let mut transport = TestTransport::new();
+        let web3 = Web3::new(transport.clone());
+
+        let from = addr!("0x9876543210987654321098765432109876543210");
+        let tx_hash = H256::repeat_byte(0xff);
+        let contract = Contract {
+            bytecode: Bytecode::from_hex_str("0x42").unwrap(),
+            ..Contract::empty()
+        };
+        let address = create_address(from, 42.into());
+
+        transport.add_response(json!([from]));
+        transport.add_response(json!("0x2a"));
+        let mut builder = InstanceDeployBuilder::new(web3, Linker::new(contract), ())
+            .expect("error creating deploy builder")
+            .gas(1.into())
+            .gas_price(2.into());
+        let predicted = builder
+            .predict_address()
+            .immediate()
+            .expect("failed to predict address");
+
+        transport.assert_request("eth_accounts", &[]);
+        transport.assert_request("eth_getTransactionCount", &[json!(from), json!("pending")]);
+        transport.assert_no_more_requests();
+        assert_eq!(predicted, address);
+
+        transport.add_response(json!(tx_hash));
+        transport.add_response(json!("0x1"));
+        transport.add_response(receipt(tx_hash, address));
+        let instance = builder.deploy().immediate().expect("deployment failed");
+
+        transport.assert_request(
+            "eth_sendTransaction",
+            &[json!({
+                "from": from,
+                "gas": "0x1",
+                "gasPrice": "0x2",
+                "data": "0x42",
+                "nonce": "0x2a",
+            })],
+        );
+        transport.assert_request("eth_blockNumber", &[]);
+        transport.assert_request("eth_getTransactionReceipt", &[json!(tx_hash)]);
+        transport.assert_no_more_requests();
+
+        assert_eq!(instance.address(), address);
+    }
+
+    #[test]
+    fn deploy_address_mismatch() {
+        let mut transport = TestTransport::new();
+        let web3 = Web3::new(transport.clone());
+
+        let from = addr!("0x9876543210987654321098765432109876543210");
+        let tx_hash = H256::repeat_byte(0xff);
+        let contract = Contract {
+            bytecode: Bytecode::from_hex_str("0x42").unwrap(),
+            ..Contract::empty()
+        };
+
+        transport.add_response(json!(tx_hash));
+        transport.add_response(json!("0x1"));
+        transport.add_response(receipt(tx_hash, Address::repeat_byte(0x42)));
+        let result = InstanceDeployBuilder::new(web3, Linker::new(contract), ())
+            .expect("error creating deploy builder")
+            .from(Account::Local(from, None))
+            .gas(1.into())
+            .gas_price(2.into())
+            .nonce(0.into())
+            .deploy()
+            .immediate();
+
+        assert!(
+            matches!(
+                &result,
+                Err(DeployError::AddressMismatch(predicted, actual))
+                    if *predicted == create_address(from, 0.into())
+                        && *actual == Address::repeat_byte(0x42)
+            ),
+            "unexpected deployment result {:?}",
+            result.map(|instance| instance.address()),
+        );
+    }
+
+    #[test]
+    fn deploy_verifies_resolved_address() {
+        let mut transport = TestTransport::new();
+        let web3 = Web3::new(transport.clone());
+
+        let from = addr!("0x9876543210987654321098765432109876543210");
+        let tx_hash = H256::repeat_byte(0xff);
+        let contract = Contract {
+            bytecode: Bytecode::from_hex_str("0x42").unwrap(),
+            ..Contract::empty()
+        };
+
+        transport.add_response(json!([from]));
+        transport.add_response(json!("0x2a"));
+        transport.add_response(json!(tx_hash));
+        transport.add_response(json!("0x1"));
+        transport.add_response(receipt(tx_hash, Address::repeat_byte(0x42)));
+        let result = InstanceDeployBuilder::new(web3, Linker::new(contract), ())
+            .expect("error creating deploy builder")
+            .gas(1.into())
+            .gas_price(2.into())
+            .deploy()
+            .immediate();
+
+        transport.assert_request("eth_accounts", &[]);
+        transport.assert_request("eth_getTransactionCount", &[json!(from), json!("pending")]);
+        transport.assert_request(
+            "eth_sendTransaction",
+            &[json!({
+                "from": from,
+                "gas": "0x1",
+                "gasPrice": "0x2",
+                "data": "0x42",
+                "nonce": "0x2a",
+            })],
+        );
+        transport.assert_request("eth_blockNumber", &[]);
+        transport.assert_request("eth_getTransactionReceipt", &[json!(tx_hash)]);
+        transport.assert_no_more_requests();
+
+        assert!(
+            matches!(
+                &result,
+                Err(DeployError::AddressMismatch(predicted, actual))
+                    if *predicted == create_address(from, 42.into())
+                        && *actual == Address::repeat_byte(0x42)
+            ),
+            "unexpected deployment result {:?}",
+            result.map(|instance| instance.address()),
+        );
+    }
+
     #[test]
     fn deploy() {
         // TODO(nlordell): implement this test - there is an open issue for this
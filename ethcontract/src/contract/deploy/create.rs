@@ -0,0 +1,38 @@
+//! Module implements computing the addresses of contracts deployed with
+//! `CREATE`.
+
+use ethcontract_common::hash;
+use rlp::RlpStream;
+use web3::types::{Address, U256};
+
+/// Computes the address of a contract created with `CREATE` by the specified
+/// sender with a nonce. This is the address of a contract deployed with a
+/// transaction from `sender` with the specified `nonce`.
+pub fn create_address(sender: Address, nonce: U256) -> Address {
+    let mut stream = RlpStream::new_list(2);
+    stream.append(&sender);
+    stream.append(&nonce);
+
+    Address::from_slice(&hash::keccak256(stream.out())[12..])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn create_address_for_nonces() {
+        let sender = addr!("0x6ac7ea33f8831ea9dcc53393aaa88b25a785dbf0");
+        for (nonce, address) in &[
+            (0, "0xcd234a471b72ba2f1ccf0a70fcaba648a5eecd8d"),
+            (1, "0x343c43a37d37dff08ae8c4a11544c718abb4fcf8"),
+            (2, "0xf778b86fa74e846c4f0a1fbd1335fe81c00a0c91"),
+            (3, "0xfffd933a0bc612844eaf0c6fe3e5b8e9b6c1d19c"),
+        ] {
+            assert_eq!(
+                create_address(sender, U256::from(*nonce)),
+                address.parse().unwrap(),
+            );
+        }
+    }
+}
@@ -0,0 +1,122 @@
+//! Module implements deterministic contract deployments with `CREATE2`
+//! factories.
+
+use ethcontract_common::hash;
+use web3::types::{Address, Bytes, H160, H256};
+
+/// A factory contract for deploying contracts with `CREATE2`.
+///
+/// The factory is called with the 32-byte salt followed by the contract init
+/// code as calldata, which is the interface of the deterministic deployment
+/// proxy. The address of the deployed contract only depends on the factory
+/// address, the salt and the init code, so the same contract can be deployed
+/// to the same address on every chain the factory is deployed on.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Create2Factory {
+    /// The address of the factory contract.
+    pub address: Address,
+}
+
+impl Create2Factory {
+    /// The address of the deterministic deployment proxy. It is deployed with
+    /// a pre-EIP-155 transaction and so is available at the same address on
+    /// most chains.
+    pub const DETERMINISTIC_DEPLOYMENT_PROXY: Address = H160([
+        0x4e, 0x59, 0xb4, 0x48, 0x47, 0xb3, 0x79, 0x57, 0x85, 0x88, 0x92, 0x0c, 0xa7, 0x8f, 0xbf,
+        0x26, 0xc0, 0xb4, 0x95, 0x6c,
+    ]);
+
+    /// Create a new `CREATE2` factory at the specified address.
+    pub fn new(address: Address) -> Self {
+        Create2Factory { address }
+    }
+
+    /// Returns the calldata for deploying the specified init code with a salt
+    /// through the factory.
+    pub fn calldata(&self, salt: H256, init_code: &[u8]) -> Bytes {
+        let mut data = Vec::with_capacity(32 + init_code.len());
+        data.extend_from_slice(salt.as_bytes());
+        data.extend_from_slice(init_code);
+        Bytes(data)
+    }
+
+    /// Returns the address of a contract deployed through the factory with the
+    /// specified salt and init code.
+    pub fn deployment_address(&self, salt: H256, init_code: &[u8]) -> Address {
+        create2_address(self.address, salt, init_code)
+    }
+}
+
+impl Default for Create2Factory {
+    fn default() -> Self {
+        Create2Factory::new(Create2Factory::DETERMINISTIC_DEPLOYMENT_PROXY)
+    }
+}
+
+/// Computes the address of a contract created with `CREATE2` by the specified
+/// factory with a salt and init code, as defined in EIP-1014.
+pub fn create2_address(factory: Address, salt: H256, init_code: &[u8]) -> Address {
+    let mut buffer = [0u8; 85];
+    buffer[0] = 0xff;
+    buffer[1..21].copy_from_slice(factory.as_bytes());
+    buffer[21..53].copy_from_slice(salt.as_bytes());
+    buffer[53..85].copy_from_slice(&hash::keccak256(init_code));
+
+    Address::from_slice(&hash::keccak256(&buffer[..])[12..])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn create2_address_eip1014_examples() {
+        // Test vectors from EIP-1014.
+        for (factory, salt, init_code, address) in &[
+            (
+                "0x0000000000000000000000000000000000000000",
+                "0x0000000000000000000000000000000000000000000000000000000000000000",
+                &[0x00][..],
+                "0x4D1A2e2bB4F88F0250f26Ffff098B0b30B26BF38",
+            ),
+            (
+                "0xdeadbeef00000000000000000000000000000000",
+                "0x000000000000000000000000feed000000000000000000000000000000000000",
+                &[0x00][..],
+                "0xD04116cDd17beBE565EB2422F2497E06cC1C9833",
+            ),
+            (
+                "0x00000000000000000000000000000000deadbeef",
+                "0x00000000000000000000000000000000000000000000000000000000cafebabe",
+                &[0xde, 0xad, 0xbe, 0xef][..],
+                "0x60f3f640a8508fC6a86d45DF051962668E1e8AC7",
+            ),
+            (
+                "0x0000000000000000000000000000000000000000",
+                "0x0000000000000000000000000000000000000000000000000000000000000000",
+                &[][..],
+                "0xE33C0C7F7df4809055C3ebA6c09CFe4BaF1BD9e0",
+            ),
+        ] {
+            assert_eq!(
+                create2_address(factory.parse().unwrap(), salt.parse().unwrap(), init_code),
+                address.parse().unwrap(),
+            );
+        }
+    }
+
+    #[test]
+    fn factory_calldata() {
+        let factory = Create2Factory::default();
+        let salt = H256::repeat_byte(0x42);
+
+        let calldata = factory.calldata(salt, &[0x13, 0x37]);
+
+        assert_eq!(&calldata.0[..32], salt.as_bytes());
+        assert_eq!(&calldata.0[32..], &[0x13, 0x37]);
+        assert_eq!(
+            factory.deployment_address(salt, &[0x13, 0x37]),
+            create2_address(factory.address, salt, &[0x13, 0x37]),
+        );
+    }
+}
@@ -0,0 +1,500 @@
+//! Module implements automatic deployment of the libraries a contract depends
+//! on.
+
+use crate::contract::{Instance, Linker};
+use crate::errors::DeployError;
+use crate::transaction::{Account, GasPolicy, GasPrice};
+use ethcontract_common::artifact::Artifact;
+use ethcontract_common::bytecode::library_placeholder;
+use ethcontract_common::contract::Network;
+use ethcontract_common::Contract;
+use std::collections::HashMap;
+use web3::api::Web3;
+use web3::types::Address;
+use web3::Transport;
+
+/// Builder for deploying the libraries a contract depends on and linking them
+/// into the contract.
+///
+/// Libraries are looked up by name in an artifact, and can themselves depend
+/// on other libraries. Libraries that are already deployed on the network of
+/// the `web3` provider, that is libraries with a matching entry in their
+/// `networks`, are reused; missing libraries are deployed in dependency order.
+#[must_use = "library deployers do nothing unless you `.link()` them"]
+pub struct LibraryDeployer<'a, T: Transport> {
+    /// The underlying `web3` provider.
+    web3: Web3<T>,
+    /// The artifact containing the contract and its libraries.
+    artifact: &'a Artifact,
+    /// The account used for deploying libraries.
+    from: Option<Account>,
+    /// The gas price used for deploying libraries.
+    gas_price: Option<GasPrice>,
+    /// The gas policy used for deploying libraries.
+    gas_policy: Option<GasPolicy>,
+    /// The number of confirmations to wait for each library deployment.
+    confirmations: Option<usize>,
+}
+
+impl<'a, T: Transport> LibraryDeployer<'a, T> {
+    /// Create a new library deployer for contracts in the specified artifact.
+    pub fn new(web3: Web3<T>, artifact: &'a Artifact) -> Self {
+        LibraryDeployer {
+            web3,
+            artifact,
+            from: None,
+            gas_price: None,
+            gas_policy: None,
+            confirmations: None,
+        }
+    }
+
+    /// Specify the account used for deploying libraries, if not specified the
+    /// the transactions will be locally signed with the default user.
+    pub fn from(mut self, value: Account) -> Self {
+        self.from = Some(value);
+        self
+    }
+
+    /// Specify the gas price to use for deploying libraries, if not specified
+    /// then the estimated gas price will be used.
+    pub fn gas_price(mut self, value: GasPrice) -> Self {
+        self.gas_price = Some(value);
+        self
+    }
+
+    /// Specify the gas policy to use for deploying libraries, if not specified
+    /// then the gas estimate will be used as is.
+    pub fn gas_policy(mut self, value: GasPolicy) -> Self {
+        self.gas_policy = Some(value);
+        self
+    }
+
+    /// Specify the number of confirmations to wait for each library
+    /// deployment, defaults to 0.
+    pub fn confirmations(mut self, value: usize) -> Self {
+        self.confirmations = Some(value);
+        self
+    }
+
+    /// Deploys the missing libraries the specified contract depends on and
+    /// returns the contract linked with all of its libraries, ready to be
+    /// deployed.
+    pub async fn link(self, name: &str) -> Result<Linker, DeployError> {
+        let (linker, _) = self.link_with_deployments(name).await?;
+        Ok(linker)
+    }
+
+    /// Deploys the missing libraries the specified contract depends on and
+    /// returns the contract linked with all of its libraries along with the
+    /// deployments of the newly deployed libraries by name.
+    ///
+    /// The deployments can be recorded into the artifact with
+    /// [`Artifact::record_deployment`], so that the libraries are reused the
+    /// next time a contract depending on them is linked.
+    pub async fn link_with_deployments(
+        self,
+        name: &str,
+    ) -> Result<(Linker, HashMap<String, Network>), DeployError> {
+        let contract = self.contract(name)?;
+        let mut deployments = HashMap::new();
+        if dependencies(self.artifact, contract).is_empty() {
+            return Ok((link(self.artifact, contract, &HashMap::new())?, deployments));
+        }
+
+        let network_id = self.web3.net().version().await?;
+        let order = deployment_order(self.artifact, name, &network_id)?;
+        let (_, libraries) = order
+            .split_last()
+            .expect("deployment order always contains the contract");
+
+        let mut addresses = HashMap::new();
+        for library in libraries {
+            let contract = self.contract(library)?;
+            let address = match contract.networks.get(&network_id) {
+                Some(network) => network.address,
+                None => {
+                    let network = self.deploy(contract, &addresses).await?;
+                    let address = network.address;
+                    deployments.insert(library.clone(), network);
+                    address
+                }
+            };
+            addresses.insert(library.as_str(), address);
+        }
+
+        Ok((link(self.artifact, contract, &addresses)?, deployments))
+    }
+
+    /// Deploys a library that has all of its dependencies deployed.
+    async fn deploy(
+        &self,
+        contract: &Contract,
+        addresses: &HashMap<&str, Address>,
+    ) -> Result<Network, DeployError> {
+        let mut builder =
+            link(self.artifact, contract, addresses)?.deploy(self.web3.clone(), ())?;
+        if let Some(from) = &self.from {
+            builder = builder.from(from.clone());
+        }
+        if let Some(gas_price) = self.gas_price {
+            builder = builder.gas_price(gas_price);
+        }
+        if let Some(gas_policy) = self.gas_policy {
+            builder = builder.gas_policy(gas_policy);
+        }
+        if let Some(confirmations) = self.confirmations {
+            builder = builder.confirmations(confirmations);
+        }
+
+        let instance: Instance<T> = builder.deploy().await?;
+        Ok(Network {
+            address: instance.address(),
+            deployment_information: instance.deployment_information(),
+        })
+    }
+
+    fn contract(&self, name: &str) -> Result<&'a Contract, DeployError> {
+        self.artifact
+            .get(name)
+            .ok_or_else(|| DeployError::NotInArtifact(name.to_string()))
+    }
+}
+
+/// Returns the libraries a contract depends on, as pairs of the name used for
+/// linking and the name of the library contract.
+///
+/// Libraries are taken from the contract's link references when available,
+/// and from the placeholders remaining in its bytecode otherwise. Hashed
+/// `__$<hash>$__` placeholders are resolved to the library in the artifact
+/// whose fully qualified name hashes to the placeholder.
+fn dependencies(artifact: &Artifact, contract: &Contract) -> Vec<(String, String)> {
+    let mut dependencies = if contract.link_references.is_empty() {
+        contract
+            .bytecode
+            .undefined_libraries()
+            .map(|name| resolve_placeholder(artifact, name))
+            .collect::<Vec<_>>()
+    } else {
+        contract
+            .link_references
+            .iter()
+            .flat_map(|(path, libraries)| {
+                libraries
+                    .keys()
+                    .map(move |library| (format!("{}:{}", path, library), library.clone()))
+            })
+            .collect()
+    };
+    dependencies.sort();
+    dependencies
+}
+
+/// Resolves a library placeholder name to the name used for linking and the
+/// name of the library contract.
+///
+/// Since hashed placeholders do not contain the library name, the fully
+/// qualified names known from the link references of the contracts in the
+/// artifact, as well as the plain contract names, are tried as candidates.
+/// Placeholders that can not be resolved are returned as is.
+fn resolve_placeholder(artifact: &Artifact, name: &str) -> (String, String) {
+    if name.starts_with('$') && name.ends_with('$') {
+        let references = artifact.iter().flat_map(|contract| {
+            contract
+                .link_references
+                .iter()
+                .chain(&contract.deployed_link_references)
+        });
+        let qualified_names = references.flat_map(|(path, libraries)| {
+            libraries
+                .keys()
+                .map(move |library| (format!("{}:{}", path, library), library.clone()))
+        });
+        let names = artifact
+            .iter()
+            .map(|contract| (contract.name.clone(), contract.name.clone()));
+
+        let resolved = qualified_names.chain(names).find(|(qualified_name, _)| {
+            library_placeholder(qualified_name).trim_matches('_') == name
+        });
+        if let Some(resolved) = resolved {
+            return resolved;
+        }
+    }
+
+    (name.to_string(), name.to_string())
+}
+
+/// Links a contract with the specified library addresses.
+fn link(
+    artifact: &Artifact,
+    contract: &Contract,
+    addresses: &HashMap<&str, Address>,
+) -> Result<Linker, DeployError> {
+    let mut linker = Linker::new(contract.clone());
+    for (link_name, library) in dependencies(artifact, contract) {
+        linker = linker.library(link_name, addresses[library.as_str()])?;
+    }
+    Ok(linker)
+}
+
+/// Computes the order in which a contract and the libraries it transitively
+/// depends on need to be deployed. The contract itself is always last.
+///
+/// The dependencies of libraries that are already deployed on the specified
+/// network are not visited, since they do not need to be deployed again.
+fn deployment_order(
+    artifact: &Artifact,
+    name: &str,
+    network_id: &str,
+) -> Result<Vec<String>, DeployError> {
+    fn visit(
+        artifact: &Artifact,
+        name: &str,
+        network_id: &str,
+        visiting: &mut Vec<String>,
+        order: &mut Vec<String>,
+    ) -> Result<(), DeployError> {
+        if order.iter().any(|visited| visited == name) {
+            return Ok(());
+        }
+        if visiting.iter().any(|visiting| visiting == name) {
+            return Err(DeployError::LibraryCycle(name.to_string()));
+        }
+
+        let contract = artifact
+            .get(name)
+            .ok_or_else(|| DeployError::NotInArtifact(name.to_string()))?;
+
+        let is_deployed_library =
+            !visiting.is_empty() && contract.networks.contains_key(network_id);
+        if !is_deployed_library {
+            visiting.push(name.to_string());
+            for (_, library) in dependencies(artifact, contract) {
+                visit(artifact, &library, network_id, visiting, order)?;
+            }
+            visiting.pop();
+        }
+
+        order.push(name.to_string());
+        Ok(())
+    }
+
+    let mut order = Vec::new();
+    visit(artifact, name, network_id, &mut Vec::new(), &mut order)?;
+    Ok(order)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::contract::deploy::create_address;
+    use crate::test::prelude::*;
+    use ethcontract_common::Bytecode;
+    use web3::types::{H2048, H256};
+
+    fn library(name: &str, code: &str) -> Contract {
+        let mut contract = Contract::with_name(name);
+        contract.bytecode = Bytecode::from_hex_str(code).unwrap();
+        contract
+    }
+
+    fn placeholder(name: &str) -> String {
+        format!("__{:_<38}", name)
+    }
+
+    fn artifact() -> Artifact {
+        let mut math = library("Math", "0x6001");
+        math.networks.insert(
+            "42".to_string(),
+            Network {
+                address: Address::repeat_byte(0x01),
+                deployment_information: None,
+            },
+        );
+
+        let mut artifact = Artifact::new();
+        artifact.insert(math);
+        artifact.insert(library("Strings", &format!("0x73{}", placeholder("Math"))));
+        artifact.insert(library(
+            "Token",
+            &format!("0x73{}73{}", placeholder("Strings"), placeholder("Math")),
+        ));
+        artifact
+    }
+
+    #[test]
+    fn deployment_order_is_topological() {
+        assert_eq!(
+            deployment_order(&artifact(), "Token", "42").unwrap(),
+            ["Math", "Strings", "Token"]
+        );
+        assert_eq!(
+            deployment_order(&artifact(), "Math", "42").unwrap(),
+            ["Math"]
+        );
+    }
+
+    #[test]
+    fn deployment_order_errors() {
+        let mut artifact = artifact();
+        artifact.insert(library("Cyclic", &format!("0x73{}", placeholder("Cyclic"))));
+        artifact.insert(library("Missing", &format!("0x73{}", placeholder("Other"))));
+
+        assert!(matches!(
+            deployment_order(&artifact, "Cyclic", "42"),
+            Err(DeployError::LibraryCycle(name)) if name == "Cyclic"
+        ));
+        assert!(matches!(
+            deployment_order(&artifact, "Missing", "42"),
+            Err(DeployError::NotInArtifact(name)) if name == "Other"
+        ));
+    }
+
+    #[test]
+    fn deployment_order_skips_deployed_libraries() {
+        let mut artifact = artifact();
+        let mut strings = library("Strings", &format!("0x73{}", placeholder("Missing")));
+        strings.networks.insert(
+            "42".to_string(),
+            Network {
+                address: Address::repeat_byte(0x02),
+                deployment_information: None,
+            },
+        );
+        artifact.insert(strings);
+
+        assert_eq!(
+            deployment_order(&artifact, "Token", "42").unwrap(),
+            ["Math", "Strings", "Token"]
+        );
+        assert!(matches!(
+            deployment_order(&artifact, "Token", "1"),
+            Err(DeployError::NotInArtifact(name)) if name == "Missing"
+        ));
+    }
+
+    #[test]
+    fn dependencies_resolve_hashed_placeholders() {
+        let mut artifact = artifact();
+        let mut linked = library("Linked", "0x6000");
+        linked
+            .link_references
+            .entry("contracts/Strings.sol".to_string())
+            .or_default()
+            .insert("Strings".to_string(), Vec::new());
+        artifact.insert(linked);
+
+        let token = library(
+            "Token",
+            &format!(
+                "0x73{}73{}73{}",
+                library_placeholder("contracts/Strings.sol:Strings"),
+                library_placeholder("Math"),
+                library_placeholder("contracts/Unknown.sol:Unknown"),
+            ),
+        );
+        let unknown = library_placeholder("contracts/Unknown.sol:Unknown")
+            .trim_matches('_')
+            .to_string();
+        assert_eq!(
+            dependencies(&artifact, &token),
+            [
+                (unknown.clone(), unknown),
+                ("Math".to_string(), "Math".to_string()),
+                (
+                    "contracts/Strings.sol:Strings".to_string(),
+                    "Strings".to_string()
+                ),
+            ]
+        );
+
+        artifact.insert(library(
+            "Token",
+            &format!(
+                "0x73{}73{}",
+                library_placeholder("contracts/Strings.sol:Strings"),
+                library_placeholder("Math"),
+            ),
+        ));
+        assert_eq!(
+            deployment_order(&artifact, "Token", "42").unwrap(),
+            ["Math", "Strings", "Token"]
+        );
+    }
+
+    #[test]
+    fn link_deploys_missing_libraries() {
+        let mut transport = TestTransport::new();
+        let web3 = Web3::new(transport.clone());
+
+        let from = addr!("0x9876543210987654321098765432109876543210");
+        let tx_hash = H256::repeat_byte(0xff);
+        let strings = create_address(from, 0.into());
+        let artifact = artifact();
+
+        transport.add_response(json!("42"));
+        transport.add_response(json!("0x0"));
+        transport.add_response(json!("0x1"));
+        transport.add_response(json!(tx_hash));
+        transport.add_response(json!("0x1"));
+        transport.add_response(json!({
+            "transactionHash": tx_hash,
+            "transactionIndex": "0x0",
+            "blockNumber": "0x1",
+            "blockHash": H256::repeat_byte(1),
+            "cumulativeGasUsed": "0x1337",
+            "gasUsed": "0x1337",
+            "contractAddress": strings,
+            "logsBloom": H2048::zero(),
+            "logs": [],
+            "status": "0x1",
+        }));
+
+        let (linker, deployments) = LibraryDeployer::new(web3.clone(), &artifact)
+            .from(Account::Local(from, None))
+            .gas_price(2.into())
+            .link_with_deployments("Token")
+            .immediate()
+            .expect("failed to link contract");
+
+        transport.assert_request("net_version", &[]);
+        transport.assert_request("eth_getTransactionCount", &[json!(from), json!("pending")]);
+        transport.assert_request(
+            "eth_estimateGas",
+            &[json!({
+                "from": from,
+                "gasPrice": "0x2",
+                "data": format!("0x73{}", "01".repeat(20)),
+            })],
+        );
+        transport.assert_request(
+            "eth_sendTransaction",
+            &[json!({
+                "from": from,
+                "gas": "0x1",
+                "gasPrice": "0x2",
+                "data": format!("0x73{}", "01".repeat(20)),
+                "nonce": "0x0",
+            })],
+        );
+        transport.assert_request("eth_blockNumber", &[]);
+        transport.assert_request("eth_getTransactionReceipt", &[json!(tx_hash)]);
+        transport.assert_no_more_requests();
+
+        assert_eq!(deployments.len(), 1);
+        assert_eq!(deployments["Strings"].address, strings);
+        assert_eq!(
+            deployments["Strings"].deployment_information,
+            Some(tx_hash.into())
+        );
+
+        let builder = linker
+            .deploy(web3, ())
+            .expect("error creating deploy builder");
+        assert_eq!(
+            builder.init_code().0,
+            hex::decode(format!("73{}73{}", hex::encode(strings), "01".repeat(20))).unwrap(),
+        );
+    }
+}
@@ -2,8 +2,10 @@
 //! detokenization of the data included in the log.
 
 mod data;
+mod receipt;
 
 pub use self::data::{Event, EventMetadata, EventStatus, ParseLog, RawLog, StreamEvent};
+pub use self::receipt::{decode_receipt_events, decode_receipt_logs, DecodedLog};
 use crate::errors::{EventError, ExecutionError};
 use crate::log::LogFilterBuilder;
 use crate::tokens::Tokenize;
@@ -0,0 +1,209 @@
+//! Module implements decoding contract events from the logs of transaction
+//! receipts.
+
+use crate::contract::{Event, ParseLog};
+use ethcontract_common::abi::{
+    Error as AbiError, Event as AbiEvent, LogParam, RawLog as AbiRawLog,
+};
+use ethcontract_common::Abi;
+use web3::types::{Address, Log, TransactionReceipt};
+
+/// Decodes the logs of a transaction receipt that were emitted by the contract
+/// at the specified address into events. Logs that cannot be parsed into `E`
+/// are skipped.
+pub fn decode_receipt_events<E>(receipt: &TransactionReceipt, address: Address) -> Vec<Event<E>>
+where
+    E: ParseLog,
+{
+    receipt
+        .logs
+        .iter()
+        .filter(|log| log.address == address)
+        .filter_map(|log| Event::from_past_log(log.clone(), E::parse_log).ok())
+        .collect()
+}
+
+/// A log decoded with one of several contract ABIs.
+#[derive(Clone, Debug, PartialEq)]
+pub struct DecodedLog {
+    /// The address of the contract that emitted the log.
+    pub address: Address,
+    /// The ABI event that was used to decode the log.
+    pub event: AbiEvent,
+    /// The decoded event parameters.
+    pub params: Vec<LogParam>,
+}
+
+/// Decodes the logs of a transaction receipt with the events from several
+/// contract ABIs, regardless of the contract that emitted them. This is useful
+/// for transactions that interact with multiple contracts. Logs that do not
+/// match any of the ABI events are skipped.
+///
+/// Standard events are matched by their signature first. Anonymous events are
+/// only tried afterwards, in the order the ABIs were specified, with the first
+/// successfully decoded event being used.
+pub fn decode_receipt_logs<'a, I>(receipt: &TransactionReceipt, abis: I) -> Vec<Event<DecodedLog>>
+where
+    I: IntoIterator<Item = &'a Abi>,
+{
+    let events = abis
+        .into_iter()
+        .flat_map(|abi| abi.events())
+        .collect::<Vec<_>>();
+
+    receipt
+        .logs
+        .iter()
+        .filter_map(|log| {
+            Event::from_past_log(log.clone(), |_| {
+                decode_log(&events, log).ok_or(AbiError::InvalidData)
+            })
+            .ok()
+        })
+        .collect()
+}
+
+/// Decodes a log with the first matching event.
+fn decode_log(events: &[&AbiEvent], log: &Log) -> Option<DecodedLog> {
+    let topic = log.topics.first();
+    let standard_events = events
+        .iter()
+        .filter(|event| !event.anonymous && Some(&event.signature()) == topic);
+    let anonymous_events = events.iter().filter(|event| event.anonymous);
+
+    standard_events.chain(anonymous_events).find_map(|event| {
+        let decoded = event
+            .parse_log(AbiRawLog {
+                topics: log.topics.clone(),
+                data: log.data.0.clone(),
+            })
+            .ok()?;
+
+        Some(DecodedLog {
+            address: log.address,
+            event: (*event).clone(),
+            params: decoded.params,
+        })
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::contract::RawLog;
+    use ethcontract_common::abi::{EventParam, ParamType, Token};
+    use web3::types::{Bytes, H256, U256};
+
+    fn transfer_event() -> AbiEvent {
+        AbiEvent {
+            name: "Transfer".into(),
+            inputs: vec![
+                EventParam {
+                    name: "from".into(),
+                    kind: ParamType::Address,
+                    indexed: true,
+                },
+                EventParam {
+                    name: "to".into(),
+                    kind: ParamType::Address,
+                    indexed: true,
+                },
+                EventParam {
+                    name: "value".into(),
+                    kind: ParamType::Uint(256),
+                    indexed: false,
+                },
+            ],
+            anonymous: false,
+        }
+    }
+
+    fn log(address: Address, topics: Vec<H256>, data: Vec<u8>) -> Log {
+        Log {
+            address,
+            topics,
+            data: Bytes(data),
+            block_hash: Some(H256::repeat_byte(1)),
+            block_number: Some(1.into()),
+            transaction_hash: Some(H256::repeat_byte(2)),
+            transaction_index: Some(0.into()),
+            log_index: Some(0.into()),
+            transaction_log_index: None,
+            log_type: None,
+            removed: None,
+        }
+    }
+
+    fn receipt(logs: Vec<Log>) -> TransactionReceipt {
+        TransactionReceipt {
+            transaction_hash: H256::repeat_byte(2),
+            block_hash: Some(H256::repeat_byte(1)),
+            block_number: Some(1.into()),
+            logs,
+            status: Some(1.into()),
+            ..Default::default()
+        }
+    }
+
+    fn transfer_log(address: Address) -> Log {
+        log(
+            address,
+            vec![
+                transfer_event().signature(),
+                H256::from(Address::repeat_byte(0x11)),
+                H256::from(Address::repeat_byte(0x22)),
+            ],
+            ethcontract_common::abi::encode(&[Token::Uint(42.into())]),
+        )
+    }
+
+    #[test]
+    fn decode_receipt_events_for_address() {
+        let token = Address::repeat_byte(0x01);
+        let other = Address::repeat_byte(0x02);
+        let receipt = receipt(vec![
+            transfer_log(token),
+            transfer_log(other),
+            log(token, vec![H256::repeat_byte(0x42)], vec![]),
+        ]);
+
+        let events = decode_receipt_events::<RawLog>(&receipt, token);
+
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[0].data, RawLog::from(transfer_log(token)));
+        assert_eq!(
+            events[0].meta.as_ref().map(|meta| meta.transaction_hash),
+            Some(H256::repeat_byte(2))
+        );
+    }
+
+    #[test]
+    fn decode_receipt_logs_with_abis() {
+        let mut abi = Abi::default();
+        abi.events.insert("Transfer".into(), vec![transfer_event()]);
+
+        let token = Address::repeat_byte(0x01);
+        let receipt = receipt(vec![
+            transfer_log(token),
+            log(token, vec![H256::repeat_byte(0x42)], vec![]),
+        ]);
+
+        let events = decode_receipt_logs(&receipt, vec![&Abi::default(), &abi]);
+
+        assert_eq!(events.len(), 1);
+        let log = &events[0].data;
+        assert_eq!(log.address, token);
+        assert_eq!(log.event.name, "Transfer");
+        assert_eq!(
+            log.params
+                .iter()
+                .map(|param| param.value.clone())
+                .collect::<Vec<_>>(),
+            vec![
+                Token::Address(Address::repeat_byte(0x11)),
+                Token::Address(Address::repeat_byte(0x22)),
+                Token::Uint(U256::from(42)),
+            ]
+        );
+    }
+}
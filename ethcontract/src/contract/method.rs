@@ -2,13 +2,16 @@
 //! intended to be used directly but to be used by a contract `Instance` with
 //! [Instance::method](ethcontract::contract::Instance::method).
 
-use crate::transaction::{Account, GasPrice, TransactionBuilder, TransactionResult};
+use crate::transaction::{
+    Account, GasPolicy, GasPrice, TransactionBuilder, TransactionEvent, TransactionResult,
+};
 use crate::{batch::CallBatch, errors::MethodError, tokens::Tokenize};
 use ethcontract_common::abi::{Function, Token};
+use futures::stream::{Stream, StreamExt as _};
 use std::marker::PhantomData;
 use web3::types::{Address, BlockId, Bytes, CallRequest, U256};
-use web3::Transport;
 use web3::{api::Web3, BatchTransport};
+use web3::{DuplexTransport, Transport};
 
 /// Default options to be applied to `MethodBuilder` or `ViewMethodBuilder`.
 #[derive(Clone, Debug, Default)]
@@ -19,6 +22,8 @@ pub struct MethodDefaults {
     pub gas: Option<U256>,
     /// Default gas price to use for transaction.
     pub gas_price: Option<GasPrice>,
+    /// Default gas policy to use for transactions with estimated gas.
+    pub gas_policy: Option<GasPolicy>,
 }
 
 /// Data used for building a contract method call or transaction. The method
@@ -68,6 +73,7 @@ impl<T: Transport, R: Tokenize> MethodBuilder<T, R> {
         self.tx.from = self.tx.from.or_else(|| defaults.from.clone());
         self.tx.gas = self.tx.gas.or(defaults.gas);
         self.tx.gas_price = self.tx.gas_price.or(defaults.gas_price);
+        self.tx.gas_policy = self.tx.gas_policy.or(defaults.gas_policy);
         self
     }
 
@@ -92,6 +98,13 @@ impl<T: Transport, R: Tokenize> MethodBuilder<T, R> {
         self
     }
 
+    /// Specify the gas policy to use for turning gas estimates into a gas
+    /// limit, if not specified then the gas estimate will be used as is.
+    pub fn gas_policy(mut self, value: GasPolicy) -> Self {
+        self.tx = self.tx.gas_policy(value);
+        self
+    }
+
     /// Specify what how much ETH to transfer with the transaction, if not
     /// specified then no ETH will be sent.
     pub fn value(mut self, value: U256) -> Self {
@@ -106,6 +119,20 @@ impl<T: Transport, R: Tokenize> MethodBuilder<T, R> {
         self
     }
 
+    /// Specify whether to simulate the transaction against the pending block
+    /// before sending it. If the simulation fails, the transaction is not sent.
+    pub fn preflight(mut self, value: bool) -> Self {
+        self.tx = self.tx.preflight(value);
+        self
+    }
+
+    /// Specify whether to replay the transaction when it fails in order to
+    /// recover its revert reason.
+    pub fn recover_revert_reason(mut self, value: bool) -> Self {
+        self.tx = self.tx.recover_revert_reason(value);
+        self
+    }
+
     /// Specify the number of confirmations to wait for when confirming the
     /// transaction, if not specified will wait for the transaction to be mined
     /// without any extra confirmations.
@@ -128,6 +155,26 @@ impl<T: Transport, R: Tokenize> MethodBuilder<T, R> {
             .map_err(|err| MethodError::new(&function, err))
     }
 
+    /// Sign (if required) and send the method call transaction, returning a
+    /// stream of events for the lifecycle of the transaction.
+    pub fn send_progress(self) -> impl Stream<Item = Result<TransactionEvent, MethodError>> {
+        let Self { function, tx, .. } = self;
+        tx.send_progress()
+            .map(move |event| event.map_err(|err| MethodError::new(&function, err)))
+    }
+
+    /// Simulate the method call transaction against the pending block with the
+    /// same parameters that would be used for sending it. Returns the decoded
+    /// return value of the method, or the revert reason if it would fail.
+    pub async fn simulate(self) -> Result<R, MethodError> {
+        let Self { function, tx, .. } = self;
+        let bytes = tx
+            .simulate()
+            .await
+            .map_err(|err| MethodError::new(&function, err))?;
+        decode_response(function, bytes)
+    }
+
     /// Demotes a `MethodBuilder` into a `ViewMethodBuilder` which has a more
     /// restricted API and cannot actually send transactions.
     pub fn view(self) -> ViewMethodBuilder<T, R> {
@@ -154,6 +201,18 @@ pub struct ViewMethodBuilder<T: Transport, R: Tokenize> {
     pub block: Option<BlockId>,
 }
 
+impl<T: DuplexTransport, R: Tokenize> MethodBuilder<T, R> {
+    /// Sign (if required) and send the method call transaction, waiting for
+    /// confirmations with a `newHeads` subscription when the transport
+    /// supports it, and by polling otherwise.
+    pub async fn send_with_subscription(self) -> Result<TransactionResult, MethodError> {
+        let Self { function, tx, .. } = self;
+        tx.send_with_subscription()
+            .await
+            .map_err(|err| MethodError::new(&function, err))
+    }
+}
+
 impl<T: Transport, R: Tokenize> ViewMethodBuilder<T, R> {
     /// Create a new `ViewMethodBuilder` by demoting a `MethodBuilder`.
     pub fn from_method(method: MethodBuilder<T, R>) -> Self {
@@ -254,6 +313,10 @@ async fn convert_response<
     let bytes = future
         .await
         .map_err(|err| MethodError::new(&function, err))?;
+    decode_response(function, bytes)
+}
+
+fn decode_response<R: Tokenize>(function: Function, bytes: Bytes) -> Result<R, MethodError> {
     let tokens = function
         .decode_output(&bytes.0)
         .map_err(|err| MethodError::new(&function, err))?;
@@ -393,6 +456,39 @@ mod tests {
         transport.assert_no_more_requests();
     }
 
+    #[test]
+    fn method_simulate() {
+        let mut transport = TestTransport::new();
+        let web3 = Web3::new(transport.clone());
+
+        let address = addr!("0x0123456789012345678901234567890123456789");
+        let from = addr!("0x9876543210987654321098765432109876543210");
+        let (function, data) = test_abi_function();
+        let tx = MethodBuilder::<_, U256>::new(web3, function, address, data.clone())
+            .from(Account::Local(from, None))
+            .nonce(42.into());
+
+        transport.add_response(json!(
+            "0x000000000000000000000000000000000000000000000000000000000000002a"
+        ));
+        let result = tx.simulate().immediate().expect("simulation error");
+
+        assert_eq!(result, 42.into());
+        transport.assert_request(
+            "eth_call",
+            &[
+                json!({
+                    "from": from,
+                    "to": address,
+                    "data": data,
+                    "nonce": "0x2a",
+                }),
+                json!("pending"),
+            ],
+        );
+        transport.assert_no_more_requests();
+    }
+
     #[test]
     fn method_defaults_are_applied() {
         let transport = TestTransport::new();
@@ -406,12 +502,14 @@ mod tests {
                 from: Some(Account::Local(from, None)),
                 gas: Some(1.into()),
                 gas_price: Some(2.into()),
+                gas_policy: Some(GasPolicy::scaled(1.5)),
             })
             .into_inner();
 
         assert_eq!(tx.from.map(|a| a.address()), Some(from));
         assert_eq!(tx.gas, Some(1.into()));
         assert_eq!(tx.gas_price, Some(2.into()));
+        assert_eq!(tx.gas_policy, Some(GasPolicy::scaled(1.5)));
         transport.assert_no_more_requests();
     }
 }
@@ -0,0 +1,200 @@
+//! Module implements resolving the implementation of upgradeable proxy
+//! contracts from their standardized storage slots.
+
+use crate::errors::ExecutionError;
+use ethcontract_common::hash::H32;
+use web3::api::Web3;
+use web3::types::{Address, Bytes, CallRequest, H256, U256};
+use web3::Transport;
+
+/// The EIP-1967 storage slot holding the address of the implementation of a
+/// proxy, `keccak256("eip1967.proxy.implementation") - 1`.
+pub const IMPLEMENTATION_SLOT: H256 = H256([
+    0x36, 0x08, 0x94, 0xa1, 0x3b, 0xa1, 0xa3, 0x21, 0x06, 0x67, 0xc8, 0x28, 0x49, 0x2d, 0xb9, 0x8d,
+    0xca, 0x3e, 0x20, 0x76, 0xcc, 0x37, 0x35, 0xa9, 0x20, 0xa3, 0xca, 0x50, 0x5d, 0x38, 0x2b, 0xbc,
+]);
+
+/// The EIP-1967 storage slot holding the address of the admin of a proxy,
+/// `keccak256("eip1967.proxy.admin") - 1`.
+pub const ADMIN_SLOT: H256 = H256([
+    0xb5, 0x31, 0x27, 0x68, 0x4a, 0x56, 0x8b, 0x31, 0x73, 0xae, 0x13, 0xb9, 0xf8, 0xa6, 0x01, 0x6e,
+    0x24, 0x3e, 0x63, 0xb6, 0xe8, 0xee, 0x11, 0x78, 0xd6, 0xa7, 0x17, 0x85, 0x0b, 0x5d, 0x61, 0x03,
+]);
+
+/// The EIP-1967 storage slot holding the address of the beacon of a beacon
+/// proxy, `keccak256("eip1967.proxy.beacon") - 1`.
+pub const BEACON_SLOT: H256 = H256([
+    0xa3, 0xf0, 0xad, 0x74, 0xe5, 0x42, 0x3a, 0xeb, 0xfd, 0x80, 0xd3, 0xef, 0x43, 0x46, 0x57, 0x83,
+    0x35, 0xa9, 0xa7, 0x2a, 0xea, 0xee, 0x59, 0xff, 0x6c, 0xb3, 0x58, 0x2b, 0x35, 0x13, 0x3d, 0x50,
+]);
+
+/// The EIP-1822 storage slot holding the address of the implementation of a
+/// universal upgradeable proxy, `keccak256("PROXIABLE")`.
+pub const PROXIABLE_SLOT: H256 = H256([
+    0xc5, 0xf1, 0x6f, 0x0f, 0xcc, 0x63, 0x9f, 0xa4, 0x8a, 0x69, 0x47, 0x83, 0x6d, 0x98, 0x50, 0xf5,
+    0x04, 0x79, 0x85, 0x23, 0xbf, 0x8c, 0x9a, 0x3a, 0x87, 0xd5, 0x87, 0x6c, 0xf6, 0x22, 0xbc, 0xf7,
+]);
+
+/// The selector of the `implementation()` method of beacons.
+const BEACON_IMPLEMENTATION_SELECTOR: H32 = [0x5c, 0x60, 0xda, 0x1b];
+
+/// Reads the address of the implementation of a proxy from the EIP-1967
+/// implementation slot. Returns `None` if the slot is empty.
+pub async fn implementation<T: Transport>(
+    web3: &Web3<T>,
+    proxy: Address,
+) -> Result<Option<Address>, ExecutionError> {
+    read_address(web3, proxy, IMPLEMENTATION_SLOT).await
+}
+
+/// Reads the address of the admin of a proxy from the EIP-1967 admin slot.
+/// Returns `None` if the slot is empty.
+pub async fn admin<T: Transport>(
+    web3: &Web3<T>,
+    proxy: Address,
+) -> Result<Option<Address>, ExecutionError> {
+    read_address(web3, proxy, ADMIN_SLOT).await
+}
+
+/// Reads the address of the beacon of a beacon proxy from the EIP-1967 beacon
+/// slot. Returns `None` if the slot is empty.
+pub async fn beacon<T: Transport>(
+    web3: &Web3<T>,
+    proxy: Address,
+) -> Result<Option<Address>, ExecutionError> {
+    read_address(web3, proxy, BEACON_SLOT).await
+}
+
+/// Reads the address of the implementation of a proxy from the EIP-1822
+/// proxiable slot. Returns `None` if the slot is empty.
+pub async fn proxiable_implementation<T: Transport>(
+    web3: &Web3<T>,
+    proxy: Address,
+) -> Result<Option<Address>, ExecutionError> {
+    read_address(web3, proxy, PROXIABLE_SLOT).await
+}
+
+/// Queries the implementation of a beacon by calling its `implementation()`
+/// method.
+pub async fn beacon_implementation<T: Transport>(
+    web3: &Web3<T>,
+    beacon: Address,
+) -> Result<Address, ExecutionError> {
+    let output = web3
+        .eth()
+        .call(
+            CallRequest {
+                to: Some(beacon),
+                data: Some(Bytes(BEACON_IMPLEMENTATION_SELECTOR.to_vec())),
+                ..Default::default()
+            },
+            None,
+        )
+        .await?;
+    if output.0.len() != 32 {
+        return Err(ethcontract_common::abi::Error::InvalidData.into());
+    }
+
+    Ok(Address::from_slice(&output.0[12..]))
+}
+
+/// Resolves the address of the implementation of a proxy. The EIP-1967
+/// implementation slot is checked first, followed by the EIP-1967 beacon slot
+/// and finally the EIP-1822 proxiable slot. Returns `None` if the contract does
+/// not look like a proxy.
+pub async fn resolve_implementation<T: Transport>(
+    web3: &Web3<T>,
+    proxy: Address,
+) -> Result<Option<Address>, ExecutionError> {
+    if let Some(implementation) = implementation(web3, proxy).await? {
+        return Ok(Some(implementation));
+    }
+    if let Some(beacon) = beacon(web3, proxy).await? {
+        return Ok(Some(beacon_implementation(web3, beacon).await?));
+    }
+    proxiable_implementation(web3, proxy).await
+}
+
+/// Reads an address from a storage slot of a contract. Returns `None` if the
+/// slot is empty.
+async fn read_address<T: Transport>(
+    web3: &Web3<T>,
+    address: Address,
+    slot: H256,
+) -> Result<Option<Address>, ExecutionError> {
+    let value = web3
+        .eth()
+        .storage(address, U256::from_big_endian(slot.as_bytes()), None)
+        .await?;
+    let value = Address::from_slice(&value[12..]);
+
+    Ok(if value.is_zero() { None } else { Some(value) })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test::prelude::*;
+    use ethcontract_common::hash;
+
+    fn eip1967_slot(name: &str) -> H256 {
+        let slot = U256::from_big_endian(&hash::keccak256(name)) - 1;
+        let mut bytes = [0; 32];
+        slot.to_big_endian(&mut bytes);
+        H256(bytes)
+    }
+
+    #[test]
+    fn proxy_slots() {
+        assert_eq!(
+            IMPLEMENTATION_SLOT,
+            eip1967_slot("eip1967.proxy.implementation")
+        );
+        assert_eq!(ADMIN_SLOT, eip1967_slot("eip1967.proxy.admin"));
+        assert_eq!(BEACON_SLOT, eip1967_slot("eip1967.proxy.beacon"));
+        assert_eq!(PROXIABLE_SLOT, H256(hash::keccak256("PROXIABLE")));
+        assert_eq!(
+            BEACON_IMPLEMENTATION_SELECTOR,
+            hash::function_selector("implementation()")
+        );
+    }
+
+    #[test]
+    fn resolve_beacon_implementation() {
+        let mut transport = TestTransport::new();
+        let web3 = Web3::new(transport.clone());
+
+        let proxy = addr!("0x0101010101010101010101010101010101010101");
+        let beacon = addr!("0x0202020202020202020202020202020202020202");
+        let implementation = addr!("0x0303030303030303030303030303030303030303");
+
+        transport.add_response(json!(H256::zero()));
+        transport.add_response(json!(H256::from(beacon)));
+        transport.add_response(json!(H256::from(implementation)));
+        let resolved = resolve_implementation(&web3, proxy)
+            .immediate()
+            .expect("failed to resolve implementation");
+
+        transport.assert_request(
+            "eth_getStorageAt",
+            &[json!(proxy), json!(IMPLEMENTATION_SLOT), json!("latest")],
+        );
+        transport.assert_request(
+            "eth_getStorageAt",
+            &[json!(proxy), json!(BEACON_SLOT), json!("latest")],
+        );
+        transport.assert_request(
+            "eth_call",
+            &[
+                json!({
+                    "to": beacon,
+                    "data": "0x5c60da1b",
+                }),
+                json!("latest"),
+            ],
+        );
+        transport.assert_no_more_requests();
+
+        assert_eq!(resolved, Some(implementation));
+    }
+}
@@ -9,12 +9,14 @@ use crate::transaction::TransactionResult;
 use ethcontract_common::abi::{Error as AbiError, Event, Function};
 use ethcontract_common::abiext::EventExt;
 pub use ethcontract_common::errors::*;
+use rlp::DecoderError;
 use secp256k1::Error as Secp256k1Error;
+use std::fmt::{self, Display, Formatter};
 use std::num::ParseIntError;
 use thiserror::Error;
 use uint::FromDecStrErr;
 use web3::error::Error as Web3Error;
-use web3::types::{Log, TransactionReceipt, H256};
+use web3::types::{Address, Bytes, Log, TransactionReceipt, H256, U256};
 
 /// Error that can occur while locating a deployed contract.
 #[derive(Debug, Error)]
@@ -49,6 +51,45 @@ pub enum DeployError {
     /// address cannot be determined.
     #[error("contract deployment transaction pending: {0}")]
     Pending(H256),
+
+    /// The contract was deployed to a different address than the one predicted
+    /// from the sender and nonce of the deployment transaction.
+    #[error("contract deployed to {1:?} instead of predicted address {0:?}")]
+    AddressMismatch(Address, Address),
+
+    /// No code was deployed to the address predicted for a `CREATE2`
+    /// deployment, for example because the salt was already used or the
+    /// contract creation failed inside the factory.
+    #[error("no contract code deployed to {0:?}")]
+    NoCodeDeployed(Address),
+
+    /// The implementation of a proxy contract could not be resolved from any
+    /// of the standard proxy storage slots.
+    #[error("could not resolve implementation of proxy {0:?}")]
+    NotAProxy(Address),
+
+    /// Attempted to verify the code of a contract without runtime bytecode.
+    #[error("can not verify contract code without deployed bytecode")]
+    NoDeployedBytecode,
+
+    /// The code deployed at the contract address does not match the contract
+    /// runtime bytecode.
+    #[error("code at {0:?} does not match the contract deployed bytecode")]
+    CodeMismatch(Address),
+
+    /// A contract or library could not be found in the artifact used for
+    /// resolving library dependencies.
+    #[error("could not find contract {0} in artifact")]
+    NotInArtifact(String),
+
+    /// A library transitively depends on itself, so the libraries cannot be
+    /// deployed.
+    #[error("library {0} has a cyclic dependency on itself")]
+    LibraryCycle(String),
+
+    /// An error occured recording a deployment into an artifact.
+    #[error("error recording deployment: {0}")]
+    Artifact(#[from] ArtifactError),
 }
 
 /// Error that can occur while executing a contract call or transaction.
@@ -69,13 +110,18 @@ pub enum ExecutionError {
 
     /// An error indicating that an attempt was made to build or send a locally
     /// signed transaction to a node without any local accounts.
-    #[error("no local accountszxcvzcv")]
+    #[error("no local accounts")]
     NoLocalAccounts,
 
     /// A contract call reverted.
     #[error("contract call reverted with message: {0:?}")]
     Revert(Option<String>),
 
+    /// A contract call reverted with revert data that was decoded into a revert
+    /// reason, which can also be a panic or a custom error.
+    #[error("contract call reverted with {0}")]
+    RevertWithReason(RevertReason),
+
     /// A contract call executed an invalid opcode.
     #[error("contract call executed an invalid opcode")]
     InvalidOpcode,
@@ -88,6 +134,26 @@ pub enum ExecutionError {
     #[error("transaction failed: {:?}", .0.transaction_hash)]
     Failure(Box<TransactionReceipt>),
 
+    /// Transaction failure with the revert reason that was recovered by
+    /// replaying the transaction.
+    #[error("transaction failed: {:?}, reverted with {1}", .0.transaction_hash)]
+    FailureWithReason(Box<TransactionReceipt>, RevertReason),
+
+    /// A mined transaction was removed from the canonical chain by a reorg and
+    /// was not included in a block again.
+    #[error("transaction {:?} was reorged out of block {:?}", .0.transaction_hash, .0.block_hash)]
+    ReorgedTransaction(Box<TransactionReceipt>),
+
+    /// A pending transaction was dropped and is no longer known to the node.
+    #[error("transaction {0:?} was dropped")]
+    DroppedTransaction(H256),
+
+    /// A queued transaction was not sent because the transaction at the
+    /// specified index in the queue failed to be sent, which would have left a
+    /// gap in the sender's nonces.
+    #[error("transaction not sent because queued transaction {0} failed")]
+    QueueAborted(usize),
+
     /// Failed to find a transaction by hash.
     #[error("missing transaction {0:?}")]
     MissingTransaction(H256),
@@ -132,6 +198,53 @@ impl From<Web3Error> for ExecutionError {
     }
 }
 
+/// Tries to get the raw revert data from a JSON RPC error for a reverted
+/// contract call. Returns `None` when the error is not a revert or the node
+/// does not provide the revert data.
+pub(crate) fn get_revert_data(err: &Web3Error) -> Option<Vec<u8>> {
+    match err {
+        Web3Error::Rpc(jsonrpc_err) => ganache::get_revert_data(jsonrpc_err)
+            .or_else(|| parity::get_revert_data(jsonrpc_err))
+            .or_else(|| geth::get_revert_data(jsonrpc_err)),
+        _ => None,
+    }
+}
+
+/// Converts the error of a contract call into an execution error, decoding the
+/// revert data into a [`RevertReason`] when the node provides it.
+pub(crate) fn from_call_error(err: Web3Error) -> ExecutionError {
+    match get_revert_data(&err) {
+        Some(data) => match revert::decode_revert_data(&data) {
+            Some(reason) => ExecutionError::RevertWithReason(reason),
+            None => ExecutionError::Revert(None),
+        },
+        None => ExecutionError::from(err),
+    }
+}
+
+/// The reason a contract call or transaction reverted, decoded from the data
+/// returned by the revert.
+#[derive(Clone, Debug, PartialEq)]
+pub enum RevertReason {
+    /// A revert with a reason string, ABI encoded as `Error(string)`.
+    Message(String),
+    /// A panic with its error code, ABI encoded as `Panic(uint256)`.
+    Panic(U256),
+    /// A custom error or other revert data that could not be decoded. The data
+    /// includes the 4-byte error selector.
+    Custom(Bytes),
+}
+
+impl Display for RevertReason {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            RevertReason::Message(reason) => write!(f, "reason {:?}", reason),
+            RevertReason::Panic(code) => write!(f, "panic code {:#x}", code),
+            RevertReason::Custom(data) => write!(f, "custom error 0x{}", hex::encode(&data.0)),
+        }
+    }
+}
+
 /// Error that can occur while executing a contract call or transaction.
 #[derive(Debug, Error)]
 #[error("method '{signature}' failure: {inner}")]
@@ -182,6 +295,24 @@ impl EventError {
     }
 }
 
+/// Error that can occur while decoding a raw signed transaction.
+#[derive(Debug, Error)]
+pub enum DecodeTransactionError {
+    /// The raw transaction bytes are not a valid RLP encoded transaction.
+    #[error("RLP decoding error: {0}")]
+    Rlp(#[from] DecoderError),
+
+    /// The raw transaction is an EIP-2718 typed transaction envelope with an
+    /// unsupported transaction type.
+    #[error("unsupported transaction type {0:#04x}")]
+    UnsupportedType(u8),
+
+    /// The transaction signature is invalid and the sender could not be
+    /// recovered.
+    #[error("invalid transaction signature")]
+    InvalidSignature,
+}
+
 /// An error indicating an invalid private key. Private keys for secp256k1 must
 /// be exactly 32 bytes and fall within the range `[1, n)` where `n` is the
 /// order of the generator point of the curve.
@@ -267,6 +398,7 @@ mod tests {
         assert_boxable_error::<DeployError>();
         assert_boxable_error::<ExecutionError>();
         assert_boxable_error::<MethodError>();
+        assert_boxable_error::<DecodeTransactionError>();
         assert_boxable_error::<InvalidPrivateKey>();
     }
 }
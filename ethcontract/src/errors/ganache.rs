@@ -18,6 +18,17 @@ pub fn get_encoded_error(err: &JsonrpcError) -> Option<ExecutionError> {
     }
 }
 
+/// Tries to get the raw revert data from a Ganache JSON RPC error. Returns
+/// `None` when the error is not a revert.
+pub fn get_revert_data(err: &JsonrpcError) -> Option<Vec<u8>> {
+    if get_error_param(err, "error")? != "revert" {
+        return None;
+    }
+
+    let data = get_error_param(err, "return")?;
+    hex::decode(data.strip_prefix("0x").unwrap_or(data)).ok()
+}
+
 /// Gets an error parameters from a Ganache JSON RPC error.
 ///
 /// these parameters are the fields inside the transaction object (by tx hash)
@@ -109,4 +120,19 @@ mod tests {
             err
         );
     }
+
+    #[test]
+    fn revert_data_from_revert() {
+        let jsonrpc_err = rpc_error("revert", Some("message"));
+        assert_eq!(
+            get_revert_data(&jsonrpc_err),
+            Some(revert::encode_reason("message"))
+        );
+
+        let jsonrpc_err = rpc_error("revert", None);
+        assert_eq!(get_revert_data(&jsonrpc_err), Some(Vec::new()));
+
+        let jsonrpc_err = rpc_error("invalid opcode", None);
+        assert_eq!(get_revert_data(&jsonrpc_err), None);
+    }
 }
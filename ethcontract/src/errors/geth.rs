@@ -20,6 +20,18 @@ pub fn get_encoded_error(err: &JsonrpcError) -> Option<ExecutionError> {
     }
 }
 
+/// Tries to get the raw revert data from a Geth JSON RPC error. Returns `None`
+/// when the error is not a revert or when the node did not include the revert
+/// data with the error.
+pub fn get_revert_data(err: &JsonrpcError) -> Option<Vec<u8>> {
+    if !err.message.starts_with(REVERTED) {
+        return None;
+    }
+
+    let data = err.data.as_ref()?.as_str()?;
+    hex::decode(data.strip_prefix("0x").unwrap_or(data)).ok()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -46,4 +58,24 @@ mod tests {
         let result = get_encoded_error(&error);
         assert!(matches!(result, Some(ExecutionError::Revert(Some(reason_))) if reason_ == reason));
     }
+
+    #[test]
+    fn revert_data() {
+        let error = JsonrpcError {
+            code: 3.into(),
+            message: REVERTED.to_string(),
+            data: Some(
+                "0x4e487b710000000000000000000000000000000000000000000000000000000000000011".into(),
+            ),
+        };
+        let data = get_revert_data(&error).unwrap();
+        assert_eq!(data.len(), 36);
+
+        let error = JsonrpcError {
+            code: 3.into(),
+            message: REVERTED.to_string(),
+            data: None,
+        };
+        assert_eq!(get_revert_data(&error), None);
+    }
 }
@@ -28,6 +28,13 @@ pub fn get_encoded_error(err: &JsonrpcError) -> Option<ExecutionError> {
     None
 }
 
+/// Tries to get the raw revert data from a Parity JSON RPC error. Returns
+/// `None` when the error is not a revert.
+pub fn get_revert_data(err: &JsonrpcError) -> Option<Vec<u8>> {
+    let hex = get_error_message(err)?.strip_prefix(REVERTED)?;
+    hex::decode(hex).ok()
+}
+
 /// Returns the error message from the JSON RPC error data.
 fn get_error_message(err: &JsonrpcError) -> Option<&'_ str> {
     err.data.as_ref().and_then(|data| data.as_str())
@@ -91,4 +98,13 @@ mod tests {
             err
         );
     }
+
+    #[test]
+    fn revert_data_from_revert() {
+        let data = revert::encode_reason("message");
+        let jsonrpc_err = rpc_error(&format!("Reverted 0x{}", hex::encode(&data)));
+
+        assert_eq!(get_revert_data(&jsonrpc_err), Some(data));
+        assert_eq!(get_revert_data(&rpc_error("Bad instruction fd")), None);
+    }
 }
@@ -1,12 +1,17 @@
 //! Module implements decoding ABI encoded revert reasons.
 
+use crate::errors::RevertReason;
 use ethcontract_common::abi::{self, ParamType};
 use ethcontract_common::hash::{self, H32};
 use lazy_static::lazy_static;
+use web3::types::{Bytes, U256};
 
 lazy_static! {
     /// The ABI function selector for identifying encoded revert reasons.
     static ref ERROR_SELECTOR: H32 = hash::function_selector("Error(string)");
+
+    /// The ABI function selector for identifying encoded panics.
+    static ref PANIC_SELECTOR: H32 = hash::function_selector("Panic(uint256)");
 }
 
 /// Decodes an ABI encoded revert reason. Returns `Some(reason)` when the ABI
@@ -31,6 +36,37 @@ pub fn decode_reason(bytes: &[u8]) -> Option<String> {
     Some(reason)
 }
 
+/// Decodes an ABI encoded panic. Returns `Some(code)` when the ABI encoded
+/// bytes represent a panic and `None` otherwise.
+///
+/// Panics are emitted by the Solidity compiler for failed assertions, arithmetic
+/// overflows and other internal errors since Solidity 0.8.
+pub fn decode_panic(bytes: &[u8]) -> Option<U256> {
+    if bytes.len() != 36 || bytes[0..4] != PANIC_SELECTOR[..] {
+        return None;
+    }
+
+    Some(U256::from_big_endian(&bytes[4..]))
+}
+
+/// Decodes the data returned by a reverted contract call or transaction into a
+/// revert reason. Returns `None` when the revert has no data.
+pub fn decode_revert_data(bytes: &[u8]) -> Option<RevertReason> {
+    if bytes.is_empty() {
+        return None;
+    }
+
+    let reason = if let Some(reason) = decode_reason(bytes) {
+        RevertReason::Message(reason)
+    } else if let Some(code) = decode_panic(bytes) {
+        RevertReason::Panic(code)
+    } else {
+        RevertReason::Custom(Bytes(bytes.to_vec()))
+    };
+
+    Some(reason)
+}
+
 #[cfg(test)]
 pub use tests::*;
 
@@ -56,6 +92,12 @@ mod tests {
             .expect("error encoding revert reason")
     }
 
+    pub fn encode_panic(code: u64) -> Vec<u8> {
+        let mut encoded = PANIC_SELECTOR.to_vec();
+        encoded.extend_from_slice(&abi::encode(&[Token::Uint(code.into())]));
+        encoded
+    }
+
     pub fn encode_reason_hex(reason: &str) -> String {
         let encoded = encode_reason(reason);
         format!("0x{}", hex::encode(encoded))
@@ -68,4 +110,31 @@ mod tests {
 
         assert_eq!(decode_reason(&encoded).as_deref(), Some(reason));
     }
+
+    #[test]
+    fn decode_panic_code() {
+        assert_eq!(decode_panic(&encode_panic(0x11)), Some(0x11.into()));
+        assert_eq!(decode_panic(&encode_reason("0x11")), None);
+    }
+
+    #[test]
+    fn decode_revert_data_reasons() {
+        let custom =
+            hex::decode("cf4791810000000000000000000000000000000000000000000000000000000000000042")
+                .unwrap();
+
+        assert_eq!(decode_revert_data(&[]), None);
+        assert_eq!(
+            decode_revert_data(&encode_reason("reason")),
+            Some(RevertReason::Message("reason".into()))
+        );
+        assert_eq!(
+            decode_revert_data(&encode_panic(0x01)),
+            Some(RevertReason::Panic(0x01.into()))
+        );
+        assert_eq!(
+            decode_revert_data(&custom),
+            Some(RevertReason::Custom(Bytes(custom)))
+        );
+    }
 }
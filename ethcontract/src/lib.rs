@@ -122,7 +122,7 @@ pub mod prelude {
     pub use crate::int::I256;
     pub use crate::secret::{Password, PrivateKey};
     pub use crate::tokens::Bytes;
-    pub use crate::transaction::{Account, GasPrice};
+    pub use crate::transaction::{Account, GasPolicy, GasPrice};
     pub use ethcontract_common::TransactionHash;
     pub use web3::api::Web3;
     #[cfg(feature = "http")]
@@ -3,12 +3,23 @@
 
 mod build;
 pub mod confirm;
+mod decode;
+pub mod gas_policy;
 pub mod gas_price;
+mod queue;
+mod replay;
 mod send;
+mod simulate;
 
+pub(crate) use self::build::resolve_sender;
 pub use self::build::Transaction;
 use self::confirm::ConfirmParams;
+pub use self::confirm::TransactionEvent;
+pub use self::decode::DecodedTransaction;
+pub use self::gas_policy::GasPolicy;
 pub use self::gas_price::GasPrice;
+pub use self::queue::TransactionQueue;
+pub use self::replay::recover_revert_reason;
 pub use self::send::TransactionResult;
 use crate::errors::ExecutionError;
 use crate::secret::{Password, PrivateKey};
@@ -79,6 +90,9 @@ pub struct TransactionBuilder<T: Transport> {
     /// Optional gas price to use for transaction. Defaults to estimated gas
     /// price from the node (i.e. `GasPrice::Standard`).
     pub gas_price: Option<GasPrice>,
+    /// Optional gas policy to apply to the gas estimate when no gas amount is
+    /// specified. Defaults to using the gas estimate as is.
+    pub gas_policy: Option<GasPolicy>,
     /// The ETH value to send with the transaction. Defaults to 0.
     pub value: Option<U256>,
     /// The data for the transaction. Defaults to empty data.
@@ -89,6 +103,13 @@ pub struct TransactionBuilder<T: Transport> {
     /// Optional resolve conditions. Defaults to waiting the transaction to be
     /// mined without any extra confirmation blocks.
     pub resolve: Option<ResolveCondition>,
+    /// Simulate the transaction against the pending block before sending it,
+    /// aborting with the revert reason if the simulation fails. Defaults to
+    /// `false`.
+    pub preflight: bool,
+    /// Replay the transaction if it fails in order to recover its revert
+    /// reason. Defaults to `false`.
+    pub recover_revert_reason: bool,
 }
 
 impl<T: Transport> TransactionBuilder<T> {
@@ -100,10 +121,13 @@ impl<T: Transport> TransactionBuilder<T> {
             to: None,
             gas: None,
             gas_price: None,
+            gas_policy: None,
             value: None,
             data: None,
             nonce: None,
             resolve: None,
+            preflight: false,
+            recover_revert_reason: false,
         }
     }
 
@@ -135,6 +159,14 @@ impl<T: Transport> TransactionBuilder<T> {
         self
     }
 
+    /// Specify the gas policy to use for turning gas estimates into a gas
+    /// limit, if not specified then the gas estimate will be used as is. This
+    /// has no effect when an explicit amount of gas is specified.
+    pub fn gas_policy(mut self, value: GasPolicy) -> Self {
+        self.gas_policy = Some(value);
+        self
+    }
+
     /// Specify what how much ETH to transfer with the transaction, if not
     /// specified then no ETH will be sent.
     pub fn value(mut self, value: U256) -> Self {
@@ -163,6 +195,24 @@ impl<T: Transport> TransactionBuilder<T> {
         self
     }
 
+    /// Specify whether to simulate the transaction with `simulate` before
+    /// sending it. If the simulation fails, the transaction is not sent and the
+    /// simulation error, including the revert reason, is returned instead.
+    pub fn preflight(mut self, value: bool) -> Self {
+        self.preflight = value;
+        self
+    }
+
+    /// Specify whether to replay the transaction when it fails in order to
+    /// recover its revert reason. If a reason is recovered, sending fails with
+    /// `ExecutionError::FailureWithReason` instead of `ExecutionError::Failure`.
+    ///
+    /// See `recover_revert_reason` for more details.
+    pub fn recover_revert_reason(mut self, value: bool) -> Self {
+        self.recover_revert_reason = value;
+        self
+    }
+
     /// Specify the number of confirmations to use for the confirmation options.
     /// This is a utility method for specifying the resolve condition.
     pub fn confirmations(mut self, value: usize) -> Self {
@@ -208,10 +258,32 @@ impl<T: Transport> TransactionBuilder<T> {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::errors::ExecutionError;
+    use crate::errors::{ExecutionError, RevertReason};
     use crate::test::prelude::*;
+    use futures::stream::StreamExt as _;
     use hex_literal::hex;
-    use web3::types::{H2048, H256};
+    use web3::types::{H2048, H256, U64};
+
+    fn block(number: u64, hash: H256) -> serde_json::Value {
+        json!({
+            "hash": hash,
+            "parentHash": H256::zero(),
+            "sha3Uncles": H256::zero(),
+            "miner": Address::zero(),
+            "stateRoot": H256::zero(),
+            "transactionsRoot": H256::zero(),
+            "receiptsRoot": H256::zero(),
+            "number": U64::from(number),
+            "gasUsed": "0x0",
+            "gasLimit": "0x0",
+            "extraData": "0x",
+            "logsBloom": H2048::zero(),
+            "timestamp": "0x0",
+            "difficulty": "0x0",
+            "uncles": [],
+            "transactions": [],
+        })
+    }
 
     #[test]
     fn tx_builder_estimate_gas() {
@@ -310,6 +382,7 @@ mod tests {
             "logs": [],
             "status": "0x1",
         }));
+        transport.add_response(block(2, H256::repeat_byte(3)));
 
         let builder = TransactionBuilder::new(web3)
             .from(Account::Offline(key, Some(chain_id)))
@@ -337,6 +410,7 @@ mod tests {
         transport.assert_request("eth_blockNumber", &[]);
         transport.assert_request("eth_blockNumber", &[]);
         transport.assert_request("eth_getTransactionReceipt", &[json!(tx_hash)]);
+        transport.assert_request("eth_getBlockByNumber", &[json!("0x2"), json!(false)]);
         transport.assert_no_more_requests();
     }
 
@@ -393,4 +467,166 @@ mod tests {
         transport.assert_request("eth_getTransactionReceipt", &[json!(tx_hash)]);
         transport.assert_no_more_requests();
     }
+
+    #[test]
+    fn tx_failure_with_revert_reason() {
+        let mut transport = TestTransport::new();
+        let web3 = Web3::new(transport.clone());
+
+        let from = addr!("0x9876543210987654321098765432109876543210");
+        let to = addr!("0x0123456789012345678901234567890123456789");
+        let tx_hash = H256::repeat_byte(0x42);
+
+        transport.add_response(json!(tx_hash));
+        transport.add_response(json!("0x1"));
+        transport.add_response(json!({
+            "transactionHash": tx_hash,
+            "transactionIndex": "0x1",
+            "blockNumber": "0x1",
+            "blockHash": H256::repeat_byte(1),
+            "cumulativeGasUsed": "0x1337",
+            "gasUsed": "0x1337",
+            "logsBloom": H2048::zero(),
+            "logs": [],
+            "status": "0x0",
+        }));
+        transport.add_response(json!({
+            "hash": tx_hash,
+            "nonce": "0x0",
+            "blockHash": H256::repeat_byte(1),
+            "blockNumber": "0x1",
+            "transactionIndex": "0x1",
+            "from": from,
+            "to": to,
+            "value": "0x0",
+            "gasPrice": "0x2",
+            "gas": "0x1337",
+            "input": "0x",
+        }));
+        transport.add_error(web3::error::Error::Rpc(jsonrpc_core::Error {
+            code: jsonrpc_core::ErrorCode::from(3),
+            message: "execution reverted: not enough funds".to_owned(),
+            data: None,
+        }));
+
+        let result = TransactionBuilder::new(web3)
+            .from(Account::Local(from, None))
+            .to(to)
+            .gas(0x1337.into())
+            .gas_price(2.into())
+            .recover_revert_reason(true)
+            .send()
+            .immediate();
+
+        assert!(
+            matches!(
+                &result,
+                Err(ExecutionError::FailureWithReason(ref tx, RevertReason::Message(ref reason)))
+                    if tx.transaction_hash == tx_hash && reason == "not enough funds"
+            ),
+            "expected transaction failure with revert reason but got {:?}",
+            result
+        );
+        transport.assert_request(
+            "eth_sendTransaction",
+            &[json!({
+                "from": from,
+                "to": to,
+                "gas": "0x1337",
+                "gasPrice": "0x2",
+            })],
+        );
+        transport.assert_request("eth_blockNumber", &[]);
+        transport.assert_request("eth_getTransactionReceipt", &[json!(tx_hash)]);
+        transport.assert_request("eth_getTransactionByHash", &[json!(tx_hash)]);
+        transport.assert_request(
+            "eth_call",
+            &[
+                json!({
+                    "from": from,
+                    "to": to,
+                    "gas": "0x1337",
+                    "gasPrice": "0x2",
+                    "value": "0x0",
+                    "data": "0x",
+                }),
+                json!("0x0"),
+            ],
+        );
+        transport.assert_no_more_requests();
+    }
+
+    #[test]
+    fn tx_send_progress() {
+        let mut transport = TestTransport::new();
+        let web3 = Web3::new(transport.clone());
+
+        let from = addr!("0x9876543210987654321098765432109876543210");
+        let to = addr!("0x0123456789012345678901234567890123456789");
+        let tx_hash = H256::repeat_byte(0x42);
+        let receipt = json!({
+            "transactionHash": tx_hash,
+            "transactionIndex": "0x1",
+            "blockNumber": "0x1",
+            "blockHash": H256::repeat_byte(1),
+            "cumulativeGasUsed": "0x1337",
+            "gasUsed": "0x1337",
+            "logsBloom": H2048::zero(),
+            "logs": [],
+            "status": "0x1",
+        });
+
+        transport.add_response(json!(tx_hash));
+        // transaction mined in the latest block
+        transport.add_response(json!("0x1"));
+        transport.add_response(receipt.clone());
+        transport.add_response(block(1, H256::repeat_byte(1)));
+        // wait for one more block
+        transport.add_response(json!("0x2"));
+        transport.add_response(receipt);
+        transport.add_response(block(1, H256::repeat_byte(1)));
+
+        let events = TransactionBuilder::new(web3)
+            .from(Account::Local(from, None))
+            .to(to)
+            .gas(0x1337.into())
+            .gas_price(2.into())
+            .confirmations(1)
+            .send_progress()
+            .collect::<Vec<_>>()
+            .immediate();
+
+        transport.assert_request(
+            "eth_sendTransaction",
+            &[json!({
+                "from": from,
+                "to": to,
+                "gas": "0x1337",
+                "gasPrice": "0x2",
+            })],
+        );
+        transport.assert_request("eth_blockNumber", &[]);
+        transport.assert_request("eth_getTransactionReceipt", &[json!(tx_hash)]);
+        transport.assert_request("eth_getBlockByNumber", &[json!("0x1"), json!(false)]);
+        transport.assert_request("eth_blockNumber", &[]);
+        transport.assert_request("eth_getTransactionReceipt", &[json!(tx_hash)]);
+        transport.assert_request("eth_getBlockByNumber", &[json!("0x1"), json!(false)]);
+        transport.assert_no_more_requests();
+
+        assert!(
+            matches!(
+                &events[..],
+                [
+                    Ok(TransactionEvent::Broadcast(hash)),
+                    Ok(TransactionEvent::Mined(mined)),
+                    Ok(TransactionEvent::Confirmations(1, 1)),
+                    Ok(TransactionEvent::Confirmed(confirmed)),
+                ] if *hash == tx_hash
+                    && mined.block_number == Some(1.into())
+                    && confirmed.transaction_hash == tx_hash
+            ),
+            "unexpected transaction events {:?}",
+            events
+        );
+    }
 }
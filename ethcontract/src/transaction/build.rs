@@ -6,16 +6,15 @@
 
 use crate::errors::ExecutionError;
 use crate::secret::{Password, PrivateKey};
+use crate::transaction::gas_policy::GasPolicy;
 use crate::transaction::gas_price::GasPrice;
 use crate::transaction::{Account, TransactionBuilder};
-use primitive_types::H160;
 use web3::api::Web3;
 use web3::types::{
     Address, Bytes, CallRequest, RawTransaction, SignedTransaction, TransactionCondition,
     TransactionParameters, TransactionRequest, H256, U256,
 };
 use web3::Transport;
-use std::str::FromStr;
 
 impl<T: Transport> TransactionBuilder<T> {
     /// Build a prepared transaction that is ready to send.
@@ -28,6 +27,7 @@ impl<T: Transport> TransactionBuilder<T> {
         let options = TransactionOptions {
             to: self.to,
             gas: self.gas,
+            gas_policy: self.gas_policy.unwrap_or_default(),
             value: self.value,
             data: self.data,
             nonce: self.nonce,
@@ -124,6 +124,9 @@ struct TransactionOptions {
     pub to: Option<Address>,
     /// The amount of gas to use for the transaction.
     pub gas: Option<U256>,
+    /// The gas policy to apply to the gas estimate when no amount of gas is
+    /// specified.
+    pub gas_policy: GasPolicy,
     /// The ETH value to send with the transaction.
     pub value: Option<U256>,
     /// The data for the transaction.
@@ -169,8 +172,7 @@ async fn build_transaction_request_for_local_signing<T: Transport>(
     gas_price: GasPrice,
     options: TransactionRequestOptions,
 ) -> Result<TransactionRequest, ExecutionError> {
-    let from = H160::from_str("02bcac94c537b1ca9e92d1a7f3ca6cbd25e0f67c")?;
-    };
+    let from = resolve_sender(&web3, from).await?;
     let gas = resolve_gas_limit(&web3, from, gas_price, &options.0).await?;
     let gas_price = gas_price.resolve_for_transaction_request(&web3).await?;
 
@@ -232,31 +234,53 @@ async fn build_offline_signed_transaction<T: Transport>(
     Ok(signed)
 }
 
+/// Resolves the sender of a transaction, defaulting to the first local account
+/// of the node when it is not specified.
+pub(crate) async fn resolve_sender<T: Transport>(
+    web3: &Web3<T>,
+    from: Option<Address>,
+) -> Result<Address, ExecutionError> {
+    match from {
+        Some(address) => Ok(address),
+        None => web3
+            .eth()
+            .accounts()
+            .await?
+            .first()
+            .copied()
+            .ok_or(ExecutionError::NoLocalAccounts),
+    }
+}
+
 async fn resolve_gas_limit<T: Transport>(
     web3: &Web3<T>,
     from: Address,
     gas_price: GasPrice,
     options: &TransactionOptions,
 ) -> Result<U256, ExecutionError> {
-    match options.gas {
-        Some(value) => Ok(value),
-        None => Ok(web3
-            .eth()
-            .estimate_gas(
-                CallRequest {
-                    from: Some(from),
-                    to: options.to,
-                    gas: None,
-                    gas_price: gas_price.value(),
-                    value: options.value,
-                    data: options.data.clone(),
-                    transaction_type: None,
-                    access_list: None,
-                },
-                None,
-            )
-            .await?),
+    if let Some(value) = options.gas {
+        return Ok(value);
     }
+
+    let estimate = web3
+        .eth()
+        .estimate_gas(
+            CallRequest {
+                from: Some(from),
+                to: options.to,
+                gas: None,
+                gas_price: gas_price.value(),
+                value: options.value,
+                data: options.data.clone(),
+                transaction_type: None,
+                access_list: None,
+            },
+            None,
+        )
+        .await
+        .map_err(ExecutionError::from);
+
+    options.gas_policy.apply(estimate)
 }
 
 #[cfg(test)]
@@ -411,6 +435,64 @@ mod tests {
         assert_eq!(tx.gas_price, Some(1337.into()));
     }
 
+    #[test]
+    fn tx_build_local_with_gas_policy() {
+        let mut transport = TestTransport::new();
+        let web3 = Web3::new(transport.clone());
+
+        let from = addr!("0xffffffffffffffffffffffffffffffffffffffff");
+
+        transport.add_response(json!("0x2710")); // gas limit
+        let tx = build_transaction_request_for_local_signing(
+            web3,
+            Some(from),
+            GasPrice::Standard,
+            TransactionRequestOptions(
+                TransactionOptions {
+                    gas_policy: GasPolicy::scaled(1.5).cap(14_000.into()),
+                    ..Default::default()
+                },
+                None,
+            ),
+        )
+        .immediate()
+        .expect("failed to build local transaction");
+
+        transport.assert_request("eth_estimateGas", &[json!({ "from": json!(from) })]);
+        transport.assert_no_more_requests();
+
+        assert_eq!(tx.gas, Some(14_000.into()));
+    }
+
+    #[test]
+    fn tx_build_local_with_gas_policy_fallback() {
+        let mut transport = TestTransport::new();
+        let web3 = Web3::new(transport.clone());
+
+        let from = addr!("0xffffffffffffffffffffffffffffffffffffffff");
+
+        transport.add_error(web3::error::Error::Unreachable); // gas limit
+        let tx = build_transaction_request_for_local_signing(
+            web3,
+            Some(from),
+            GasPrice::Standard,
+            TransactionRequestOptions(
+                TransactionOptions {
+                    gas_policy: GasPolicy::scaled(1.5).fallback(1_000_000.into()),
+                    ..Default::default()
+                },
+                None,
+            ),
+        )
+        .immediate()
+        .expect("failed to build local transaction");
+
+        transport.assert_request("eth_estimateGas", &[json!({ "from": json!(from) })]);
+        transport.assert_no_more_requests();
+
+        assert_eq!(tx.gas, Some(1_000_000.into()));
+    }
+
     #[test]
     fn tx_build_local_no_local_accounts() {
         let mut transport = TestTransport::new();
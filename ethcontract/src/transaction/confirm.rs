@@ -8,12 +8,21 @@
 
 use crate::errors::ExecutionError;
 use crate::transaction::TransactionResult;
+use futures::stream::{self, Stream, StreamExt as _};
 use futures_timer::Delay;
+use serde_json::Value;
 use std::cmp::min;
+use std::collections::VecDeque;
+use std::pin::Pin;
+use std::task::{Context, Poll};
 use std::time::Duration;
 use web3::api::Web3;
-use web3::types::{TransactionReceipt, H256, U64};
-use web3::Transport;
+use web3::error::Error as Web3Error;
+use web3::helpers;
+use web3::types::{
+    BlockHeader, BlockId, BlockNumber, TransactionId, TransactionReceipt, H256, U64,
+};
+use web3::{DuplexTransport, Transport};
 
 /// A struct with the confirmation parameters.
 #[derive(Clone, Debug)]
@@ -140,34 +149,164 @@ impl Default for ConfirmParams {
     }
 }
 
+/// An event in the lifecycle of a sent transaction.
+#[derive(Clone, Debug)]
+pub enum TransactionEvent {
+    /// The transaction was broadcast to the network with the specified hash.
+    Broadcast(H256),
+    /// The transaction was mined in a block.
+    Mined(Box<TransactionReceipt>),
+    /// A block confirming the transaction was mined. Contains the current
+    /// number of confirmations and the number of confirmations required for
+    /// the transaction to be considered confirmed.
+    Confirmations(usize, usize),
+    /// The block the transaction was mined in was removed from the canonical
+    /// chain by a reorg. Contains the receipt of the transaction in that block.
+    Reorged(Box<TransactionReceipt>),
+    /// The transaction was mined in a different block after being reorged out.
+    Remined(Box<TransactionReceipt>),
+    /// The transaction was confirmed with the required number of blocks. This
+    /// is always the last event of a successful confirmation.
+    Confirmed(Box<TransactionReceipt>),
+}
+
 /// Waits for a transaction to be confirmed.
 pub async fn wait_for_confirmation<T: Transport>(
     web3: &Web3<T>,
     tx: H256,
     params: ConfirmParams,
 ) -> Result<TransactionReceipt, ExecutionError> {
-    let mut latest_block = None;
-    let mut context = ConfirmationContext {
-        web3,
+    confirmed_receipt(confirmation_events(web3.clone(), tx, params)).await
+}
+
+/// Waits for a transaction to be confirmed using a `newHeads` subscription to
+/// get notified of new blocks instead of polling the node for the latest block
+/// number. This reduces both the confirmation latency and the number of
+/// requests made to the node.
+///
+/// Falls back to polling when the node does not support the subscription or
+/// when the subscription stream closes.
+pub async fn wait_for_confirmation_with_subscription<T: DuplexTransport>(
+    web3: &Web3<T>,
+    tx: H256,
+    params: ConfirmParams,
+) -> Result<TransactionReceipt, ExecutionError> {
+    confirmed_receipt(confirmation_events_with_subscription(
+        web3.clone(),
         tx,
         params,
-        starting_block: None,
-    };
+    ))
+    .await
+}
 
-    loop {
-        let target_block = match context.check(latest_block).await? {
-            Check::Confirmed(tx) => return Ok(tx),
-            Check::Pending(target_block) => target_block,
-        };
+/// Returns a stream of events for the confirmation of a transaction. The
+/// stream ends once the transaction is confirmed or the confirmation fails.
+pub fn confirmation_events<T: Transport>(
+    web3: Web3<T>,
+    tx: H256,
+    params: ConfirmParams,
+) -> impl Stream<Item = Result<TransactionEvent, ExecutionError>> {
+    ConfirmationContext::<_, NoHeads>::new(web3, tx, params).into_events()
+}
+
+/// Returns a stream of events for the confirmation of a transaction using a
+/// `newHeads` subscription to get notified of new blocks. See
+/// [`wait_for_confirmation_with_subscription`] for more details.
+///
+/// The subscription is removed from the node once the stream ends. When the
+/// stream is dropped before it ends, the request for removing the subscription
+/// is sent without waiting for its response.
+pub fn confirmation_events_with_subscription<T: DuplexTransport>(
+    web3: Web3<T>,
+    tx: H256,
+    params: ConfirmParams,
+) -> impl Stream<Item = Result<TransactionEvent, ExecutionError>> {
+    stream::once(async move {
+        let mut context = ConfirmationContext::new(web3, tx, params);
+        if let Ok(id) = subscribe_new_heads(&context.web3).await {
+            context.heads = HeadSubscription::new(context.web3.transport().clone(), id.clone());
+            context.subscription = Some(id);
+        }
+
+        context.into_events()
+    })
+    .flatten()
+}
+
+/// Drives a confirmation event stream to completion, returning the receipt of
+/// the confirmed transaction.
+async fn confirmed_receipt(
+    events: impl Stream<Item = Result<TransactionEvent, ExecutionError>>,
+) -> Result<TransactionReceipt, ExecutionError> {
+    futures::pin_mut!(events);
+
+    let mut result = None;
+    while let Some(event) = events.next().await {
+        result = Some(match event {
+            Ok(TransactionEvent::Confirmed(receipt)) => Ok(*receipt),
+            Ok(_) => continue,
+            Err(err) => Err(err),
+        });
+    }
+
+    result.expect("confirmation ended without a receipt or an error")
+}
+
+/// Subscribes to new block headers, returning the subscription ID.
+async fn subscribe_new_heads<T: Transport>(web3: &Web3<T>) -> Result<String, Web3Error> {
+    let kind = helpers::serialize(&"newHeads");
+    let id = web3
+        .transport()
+        .execute("eth_subscribe", vec![kind])
+        .await?;
+    helpers::decode(id)
+}
+
+/// Stream type used for confirmation contexts that poll for new blocks.
+type NoHeads = stream::Empty<Value>;
+
+/// A stream of new block header notifications for a subscription. The
+/// subscription is removed from the transport when dropped.
+struct HeadSubscription<T: DuplexTransport> {
+    transport: T,
+    id: String,
+    notifications: Pin<Box<T::NotificationStream>>,
+}
+
+impl<T: DuplexTransport> HeadSubscription<T> {
+    /// Creates a new head subscription stream for the specified subscription
+    /// ID, returning `None` if the transport fails to subscribe.
+    fn new(transport: T, id: String) -> Option<Self> {
+        let notifications = transport.subscribe(id.clone().into()).ok()?;
+        Some(HeadSubscription {
+            transport,
+            id,
+            notifications: Box::pin(notifications),
+        })
+    }
+}
+
+// NOTE: The notification stream is boxed, and the transport is never pinned.
+impl<T: DuplexTransport> Unpin for HeadSubscription<T> {}
+
+impl<T: DuplexTransport> Stream for HeadSubscription<T> {
+    type Item = Value;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Option<Self::Item>> {
+        self.notifications.as_mut().poll_next(cx)
+    }
+}
 
-        latest_block = Some(context.wait_for_blocks(target_block).await?);
+impl<T: DuplexTransport> Drop for HeadSubscription<T> {
+    fn drop(&mut self) {
+        let _ = self.transport.unsubscribe(self.id.clone().into());
     }
 }
 
 /// The state used for waiting for a transaction confirmation.
 #[derive(Debug)]
-struct ConfirmationContext<'a, T: Transport> {
-    web3: &'a Web3<T>,
+struct ConfirmationContext<T: Transport, H> {
+    web3: Web3<T>,
     /// The transaction hash that is being confirmed.
     tx: H256,
     /// The confirmation parameters (like number of confirming blocks to wait
@@ -176,19 +315,120 @@ struct ConfirmationContext<'a, T: Transport> {
     /// The current block number when confirmation started. This is used for
     /// timeouts.
     starting_block: Option<U64>,
+    /// The stream of new block header notifications, if subscribed to new
+    /// blocks. The node is polled for new blocks when this is `None`.
+    heads: Option<H>,
+    /// The ID of the `newHeads` subscription to remove from the node once the
+    /// confirmation completes or the context is dropped.
+    subscription: Option<String>,
+    /// The block to wait for before checking the transaction again.
+    target_block: Option<U64>,
+    /// The receipt of the transaction from the last check if it was mined.
+    receipt: Option<TransactionReceipt>,
+    /// The receipt of the transaction from the block it was last reorged out
+    /// of, if it was reorged out since it was mined.
+    reorged: Option<TransactionReceipt>,
+    /// The number of confirmations last reported.
+    confirmations: Option<usize>,
+    /// Whether or not the confirmation completed, either successfully or with
+    /// an error.
+    done: bool,
 }
 
-impl<T: Transport> ConfirmationContext<'_, T> {
-    /// Checks if the transaction is confirmed.
+impl<T, H> ConfirmationContext<T, H>
+where
+    T: Transport,
+    H: Stream<Item = Value> + Unpin,
+{
+    /// Creates a new confirmation context that polls for new blocks.
+    fn new(web3: Web3<T>, tx: H256, params: ConfirmParams) -> Self {
+        ConfirmationContext {
+            web3,
+            tx,
+            params,
+            starting_block: None,
+            heads: None,
+            subscription: None,
+            target_block: None,
+            receipt: None,
+            reorged: None,
+            confirmations: None,
+            done: false,
+        }
+    }
+
+    /// Turns the context into a stream of confirmation events.
+    fn into_events(self) -> impl Stream<Item = Result<TransactionEvent, ExecutionError>> {
+        stream::unfold(
+            (self, VecDeque::new()),
+            |(mut context, mut events)| async move {
+                while events.is_empty() {
+                    if context.done {
+                        context.unsubscribe().await;
+                        return None;
+                    }
+
+                    match context.step().await {
+                        Ok(new_events) => events.extend(new_events),
+                        Err(err) => {
+                            context.done = true;
+                            return Some((Err(err), (context, events)));
+                        }
+                    }
+                }
+
+                let event = events.pop_front()?;
+                Some((Ok(event), (context, events)))
+            },
+        )
+    }
+
+    /// Advances the confirmation by waiting for the next target block if
+    /// needed and checking the transaction, returning the resulting events.
+    async fn step(&mut self) -> Result<Vec<TransactionEvent>, ExecutionError> {
+        let latest_block = match self.target_block.take() {
+            Some(target_block) => Some(self.wait_for_blocks(target_block).await?),
+            None => None,
+        };
+
+        let mut events = Vec::new();
+        match self.check(latest_block, &mut events).await? {
+            Check::Confirmed(tx) => {
+                self.done = true;
+                events.push(TransactionEvent::Confirmed(Box::new(tx)));
+            }
+            Check::Pending(target_block) => self.target_block = Some(target_block),
+        }
+
+        Ok(events)
+    }
+
+    /// Checks if the transaction is confirmed, recording events for changes
+    /// since the last check.
     ///
     /// Accepts an optional block number parameter to avoid re-querying the
     /// current block if it is already known.
-    async fn check(&mut self, latest_block: Option<U64>) -> Result<Check, ExecutionError> {
+    async fn check(
+        &mut self,
+        latest_block: Option<U64>,
+        events: &mut Vec<TransactionEvent>,
+    ) -> Result<Check, ExecutionError> {
+        let waited = latest_block.is_some();
+        let mut checked_dropped = false;
         let latest_block = match latest_block {
             Some(value) => value,
             None => self.web3.eth().block_number().await?,
         };
         let tx = self.web3.eth().transaction_receipt(self.tx).await?;
+        let tx = match tx.filter(|tx| tx.block_number.is_some()) {
+            // NOTE: Nodes can return receipts from blocks that are no longer
+            //   part of the canonical chain, so verify the block hash before
+            //   counting confirmations for it and before considering the
+            //   transaction confirmed.
+            Some(tx) if self.needs_verification(&tx, latest_block) => self.verify_block(tx).await?,
+            tx => tx,
+        };
+        self.record(tx.as_ref(), latest_block, events);
 
         let (target_block, tx_result) = match tx.and_then(|tx| Some((tx.block_number?, tx))) {
             Some((tx_block, tx)) => {
@@ -205,6 +445,15 @@ impl<T: Transport> ConfirmationContext<'_, T> {
                 (target_block, TransactionResult::Receipt(tx))
             }
             None => {
+                // NOTE: Check that the transaction is still known to the node
+                //   whenever it is still not mined after waiting for blocks,
+                //   so that dropped transactions are detected even without a
+                //   block timeout.
+                if waited || self.reorged.is_some() {
+                    self.check_dropped().await?;
+                    checked_dropped = true;
+                }
+
                 // We know that transaction was not mined at block `latest_block` because
                 // we've fetched `latest_block` before we've fetched transaction receipt.
                 // Thus, we need to wait at least one block after the `latest_block`,
@@ -221,6 +470,9 @@ impl<T: Transport> ConfirmationContext<'_, T> {
             let remaining_blocks = target_block.saturating_sub(starting_block);
 
             if remaining_blocks > U64::from(block_timeout) {
+                if tx_result.is_hash() && !checked_dropped {
+                    self.check_dropped().await?;
+                }
                 return Err(ExecutionError::ConfirmTimeout(Box::new(tx_result)));
             }
         }
@@ -228,11 +480,150 @@ impl<T: Transport> ConfirmationContext<'_, T> {
         Ok(Check::Pending(target_block))
     }
 
-    /// Waits for blocks to be mined. This method polls the latest block number
-    /// and waits till the target block number is reached.
+    /// Returns true if the block of a mined transaction needs verifying, that
+    /// is when confirmations are counted for it and the transaction either
+    /// appears in a block for the first time, was moved to a different block
+    /// or has the required confirmations on top of its block.
+    fn needs_verification(&self, tx: &TransactionReceipt, latest_block: U64) -> bool {
+        let tx_block = match tx.block_number {
+            Some(tx_block) if self.params.confirmations > 0 => tx_block,
+            _ => return false,
+        };
+        let is_new_block = match &self.receipt {
+            Some(previous) => {
+                (previous.block_number, previous.block_hash) != (tx.block_number, tx.block_hash)
+            }
+            None => true,
+        };
+
+        is_new_block || latest_block >= tx_block + self.params.confirmations
+    }
+
+    /// Verifies that the block of a transaction receipt is still part of the
+    /// canonical chain. Returns `None` if the transaction was reorged out.
+    async fn verify_block(
+        &self,
+        tx: TransactionReceipt,
+    ) -> Result<Option<TransactionReceipt>, ExecutionError> {
+        let tx_block = match tx.block_number {
+            Some(tx_block) => tx_block,
+            None => return Ok(None),
+        };
+        let block = self
+            .web3
+            .eth()
+            .block(BlockId::Number(BlockNumber::Number(tx_block)))
+            .await?;
+
+        Ok(block
+            .filter(|block| block.hash.is_some() && block.hash == tx.block_hash)
+            .map(|_| tx))
+    }
+
+    /// Checks that a transaction without a receipt is still known to the node.
+    /// Returns an error if the transaction was dropped, or reorged out and not
+    /// included in a block again.
+    async fn check_dropped(&self) -> Result<(), ExecutionError> {
+        let tx = self
+            .web3
+            .eth()
+            .transaction(TransactionId::Hash(self.tx))
+            .await?;
+
+        match (tx, &self.reorged) {
+            (Some(_), _) => Ok(()),
+            (None, Some(reorged)) => Err(ExecutionError::ReorgedTransaction(Box::new(
+                reorged.clone(),
+            ))),
+            (None, None) => Err(ExecutionError::DroppedTransaction(self.tx)),
+        }
+    }
+
+    /// Records the progress of the transaction, emitting events when it gets
+    /// mined, reorged out or confirmed by additional blocks.
+    fn record(
+        &mut self,
+        tx: Option<&TransactionReceipt>,
+        latest_block: U64,
+        events: &mut Vec<TransactionEvent>,
+    ) {
+        let previous = self.receipt.take();
+        self.receipt = tx.cloned();
+
+        match (previous, tx) {
+            (None, Some(tx)) if self.reorged.is_some() => {
+                events.push(TransactionEvent::Remined(Box::new(tx.clone())))
+            }
+            (None, Some(tx)) => events.push(TransactionEvent::Mined(Box::new(tx.clone()))),
+            (Some(previous), Some(tx))
+                if (previous.block_number, previous.block_hash)
+                    != (tx.block_number, tx.block_hash) =>
+            {
+                self.reorged = Some(previous.clone());
+                self.confirmations = None;
+                events.push(TransactionEvent::Reorged(Box::new(previous)));
+                events.push(TransactionEvent::Remined(Box::new(tx.clone())));
+            }
+            (Some(previous), None) => {
+                self.reorged = Some(previous.clone());
+                self.confirmations = None;
+                events.push(TransactionEvent::Reorged(Box::new(previous)));
+            }
+            _ => {}
+        }
+
+        let tx_block = match tx.and_then(|tx| tx.block_number) {
+            Some(tx_block) if self.params.confirmations > 0 => tx_block,
+            _ => return,
+        };
+        let confirmations = min(
+            latest_block.saturating_sub(tx_block).as_usize(),
+            self.params.confirmations,
+        );
+        if confirmations > 0 && self.confirmations != Some(confirmations) {
+            self.confirmations = Some(confirmations);
+            events.push(TransactionEvent::Confirmations(
+                confirmations,
+                self.params.confirmations,
+            ));
+        }
+    }
+
+    /// Removes the `newHeads` subscription from the node, if any.
+    async fn unsubscribe(&mut self) {
+        self.heads = None;
+        if let Some(id) = self.subscription.take() {
+            // NOTE: Failing to unsubscribe does not affect the confirmation,
+            //   the node just keeps sending notifications that get ignored.
+            let _ = self
+                .web3
+                .transport()
+                .execute("eth_unsubscribe", vec![helpers::serialize(&id)])
+                .await;
+        }
+    }
+
+    /// Waits for blocks to be mined. This method waits for new block header
+    /// notifications if subscribed to new blocks, and polls the latest block
+    /// number otherwise, until the target block number is reached.
     ///
     /// This method returns the latest block number if it is known.
-    async fn wait_for_blocks(&self, target_block: U64) -> Result<U64, ExecutionError> {
+    async fn wait_for_blocks(&mut self, target_block: U64) -> Result<U64, ExecutionError> {
+        if let Some(heads) = &mut self.heads {
+            while let Some(head) = heads.next().await {
+                let latest_block = serde_json::from_value::<BlockHeader>(head)
+                    .ok()
+                    .and_then(|head| head.number);
+                match latest_block {
+                    Some(latest_block) if target_block <= latest_block => return Ok(latest_block),
+                    _ => continue,
+                }
+            }
+
+            // NOTE: The subscription was closed, so fall back to polling.
+            self.heads = None;
+        }
+
         let mut cur_delay = self.params.poll_interval_min;
 
         loop {
@@ -251,6 +642,23 @@ impl<T: Transport> ConfirmationContext<'_, T> {
     }
 }
 
+impl<T: Transport, H> Drop for ConfirmationContext<T, H> {
+    fn drop(&mut self) {
+        self.heads = None;
+        if let Some(id) = self.subscription.take() {
+            // NOTE: The confirmation was dropped before it completed, so the
+            //   response can't be waited for. The duplex transports from
+            //   `web3` dispatch requests when they are sent and not when the
+            //   response is polled, so the request is made regardless.
+            drop(
+                self.web3
+                    .transport()
+                    .execute("eth_unsubscribe", vec![helpers::serialize(&id)]),
+            );
+        }
+    }
+}
+
 /// The result of checking a transaction confirmation.
 #[allow(clippy::large_enum_variant)]
 #[derive(Debug)]
@@ -285,8 +693,9 @@ async fn delay(duration: Duration) {
 mod tests {
     use super::*;
     use crate::test::prelude::*;
+    use crate::transport::DynTransport;
     use serde_json::Value;
-    use web3::types::H2048;
+    use web3::types::{H160, H2048};
 
     fn generate_tx_receipt<U: Into<U64>>(hash: H256, block_num: U) -> Value {
         json!({
@@ -301,6 +710,48 @@ mod tests {
         })
     }
 
+    fn generate_block_header<U: Into<U64>>(block_num: U) -> Value {
+        json!({
+            "hash": H256::zero(),
+            "parentHash": H256::zero(),
+            "sha3Uncles": H256::zero(),
+            "miner": H160::zero(),
+            "stateRoot": H256::zero(),
+            "transactionsRoot": H256::zero(),
+            "receiptsRoot": H256::zero(),
+            "number": block_num.into(),
+            "gasUsed": "0x0",
+            "gasLimit": "0x0",
+            "extraData": "0x",
+            "logsBloom": H2048::zero(),
+            "timestamp": "0x0",
+            "difficulty": "0x0",
+        })
+    }
+
+    fn generate_block<U: Into<U64>>(block_num: U) -> Value {
+        let mut block = generate_block_header(block_num);
+        block["uncles"] = json!([]);
+        block["transactions"] = json!([]);
+        block
+    }
+
+    fn generate_pending_tx(hash: H256) -> Value {
+        json!({
+            "hash": hash,
+            "nonce": "0x0",
+            "blockHash": null,
+            "blockNumber": null,
+            "transactionIndex": null,
+            "from": H160::zero(),
+            "to": H160::zero(),
+            "value": "0x0",
+            "gasPrice": "0x0",
+            "gas": "0x0",
+            "input": "0x",
+        })
+    }
+
     #[test]
     fn confirm_mined_transaction() {
         let mut transport = TestTransport::new();
@@ -359,8 +810,9 @@ mod tests {
         transport.add_response(json!(null));
         // poll for one block
         transport.add_response(json!("0x2"));
-        // transaction still not mined
+        // transaction still not mined but known to the node
         transport.add_response(json!(null));
+        transport.add_response(generate_pending_tx(hash));
         // poll for one more block
         transport.add_response(json!("0x3"));
         // now it's mined
@@ -375,6 +827,7 @@ mod tests {
         transport.assert_request("eth_getTransactionReceipt", &[json!(hash)]);
         transport.assert_request("eth_blockNumber", &[]);
         transport.assert_request("eth_getTransactionReceipt", &[json!(hash)]);
+        transport.assert_request("eth_getTransactionByHash", &[json!(hash)]);
         transport.assert_request("eth_blockNumber", &[]);
         transport.assert_request("eth_getTransactionReceipt", &[json!(hash)]);
         transport.assert_no_more_requests();
@@ -411,6 +864,7 @@ mod tests {
         // current block is 3, tx was mined on block 1, so we can confirm it
         transport.add_response(json!("0x3"));
         transport.add_response(generate_tx_receipt(hash, 1));
+        transport.add_response(generate_block(1));
 
         let confirm = wait_for_confirmation(&web3, hash, ConfirmParams::with_confirmations(2))
             .immediate()
@@ -419,6 +873,7 @@ mod tests {
         assert_eq!(confirm.transaction_hash, hash);
         transport.assert_request("eth_blockNumber", &[]);
         transport.assert_request("eth_getTransactionReceipt", &[json!(hash)]);
+        transport.assert_request("eth_getBlockByNumber", &[json!("0x1"), json!(false)]);
         transport.assert_no_more_requests();
     }
 
@@ -442,6 +897,7 @@ mod tests {
         // check transaction was mined - note that the block number doesn't get
         // re-queried and is re-used from the polling loop.
         transport.add_response(generate_tx_receipt(hash, 2));
+        transport.add_response(generate_block(2));
 
         let confirm = wait_for_confirmation(&web3, hash, ConfirmParams::with_confirmations(1))
             .wait()
@@ -457,6 +913,7 @@ mod tests {
         transport.assert_request("eth_blockNumber", &[]);
         transport.assert_request("eth_blockNumber", &[]);
         transport.assert_request("eth_getTransactionReceipt", &[json!(hash)]);
+        transport.assert_request("eth_getBlockByNumber", &[json!("0x2"), json!(false)]);
         transport.assert_no_more_requests();
     }
 
@@ -470,10 +927,12 @@ mod tests {
         // current block is 2, tx was mined on block 1
         transport.add_response(json!("0x2"));
         transport.add_response(generate_tx_receipt(hash, 1));
+        transport.add_response(generate_block(1));
         // still waiting for one more block
         transport.add_response(json!("0x2"));
         transport.add_response(json!("0x3"));
         transport.add_response(generate_tx_receipt(hash, 1));
+        transport.add_response(generate_block(1));
 
         let confirm = wait_for_confirmation(&web3, hash, ConfirmParams::with_confirmations(2))
             .immediate()
@@ -482,9 +941,11 @@ mod tests {
         assert_eq!(confirm.transaction_hash, hash);
         transport.assert_request("eth_blockNumber", &[]);
         transport.assert_request("eth_getTransactionReceipt", &[json!(hash)]);
+        transport.assert_request("eth_getBlockByNumber", &[json!("0x1"), json!(false)]);
         transport.assert_request("eth_blockNumber", &[]);
         transport.assert_request("eth_blockNumber", &[]);
         transport.assert_request("eth_getTransactionReceipt", &[json!(hash)]);
+        transport.assert_request("eth_getBlockByNumber", &[json!("0x1"), json!(false)]);
         transport.assert_no_more_requests();
     }
 
@@ -502,6 +963,7 @@ mod tests {
         transport.add_response(json!("0x4"));
         // check transaction was mined (`eth_blockNumber` request is reused)
         transport.add_response(generate_tx_receipt(hash, 2));
+        transport.add_response(generate_block(2));
 
         let confirm = wait_for_confirmation(&web3, hash, ConfirmParams::with_confirmations(1))
             .immediate()
@@ -512,6 +974,7 @@ mod tests {
         transport.assert_request("eth_getTransactionReceipt", &[json!(hash)]);
         transport.assert_request("eth_blockNumber", &[]);
         transport.assert_request("eth_getTransactionReceipt", &[json!(hash)]);
+        transport.assert_request("eth_getBlockByNumber", &[json!("0x2"), json!(false)]);
         transport.assert_no_more_requests();
     }
 
@@ -530,15 +993,18 @@ mod tests {
         transport.add_response(json!("0x3"));
         // check confirmation again - transaction mined on block 3
         transport.add_response(generate_tx_receipt(hash, 3));
+        transport.add_response(generate_block(3));
         // needs to wait 1 more block
         transport.add_response(json!("0x3"));
         transport.add_response(json!("0x4"));
         // check confirmation - reorg happened, tx mined on block 4!
         transport.add_response(generate_tx_receipt(hash, 4));
+        transport.add_response(generate_block(4));
         // wait for another block
         transport.add_response(json!("0x5"));
         // check confirmation - and we are satisfied.
         transport.add_response(generate_tx_receipt(hash, 4));
+        transport.add_response(generate_block(4));
 
         let confirm = wait_for_confirmation(&web3, hash, ConfirmParams::with_confirmations(1))
             .wait()
@@ -550,11 +1016,14 @@ mod tests {
         transport.assert_request("eth_blockNumber", &[]);
         transport.assert_request("eth_blockNumber", &[]);
         transport.assert_request("eth_getTransactionReceipt", &[json!(hash)]);
+        transport.assert_request("eth_getBlockByNumber", &[json!("0x3"), json!(false)]);
         transport.assert_request("eth_blockNumber", &[]);
         transport.assert_request("eth_blockNumber", &[]);
         transport.assert_request("eth_getTransactionReceipt", &[json!(hash)]);
+        transport.assert_request("eth_getBlockByNumber", &[json!("0x4"), json!(false)]);
         transport.assert_request("eth_blockNumber", &[]);
         transport.assert_request("eth_getTransactionReceipt", &[json!(hash)]);
+        transport.assert_request("eth_getBlockByNumber", &[json!("0x4"), json!(false)]);
         transport.assert_no_more_requests();
     }
 
@@ -573,14 +1042,16 @@ mod tests {
         // Initial check
         transport.add_response(json!("0x0"));
         transport.add_response(json!(null));
-        // Check again, at block 4
+        // Check again, at block 4, transaction is still pending
         transport.add_response(json!("0x4"));
         transport.add_response(json!(null));
+        transport.add_response(generate_pending_tx(hash));
         // Wait for more blocks
         // Final check at block 8, since the earliest the transaction can be
         // confirmed is at block 12 which is past the block timeout.
         transport.add_response(json!("0x8"));
         transport.add_response(json!(null));
+        transport.add_response(generate_pending_tx(hash));
 
         let confirm = wait_for_confirmation(&web3, hash, params).wait();
 
@@ -597,8 +1068,420 @@ mod tests {
         transport.assert_request("eth_getTransactionReceipt", &[json!(hash)]);
         transport.assert_request("eth_blockNumber", &[]);
         transport.assert_request("eth_getTransactionReceipt", &[json!(hash)]);
+        transport.assert_request("eth_getTransactionByHash", &[json!(hash)]);
+        transport.assert_request("eth_blockNumber", &[]);
+        transport.assert_request("eth_getTransactionReceipt", &[json!(hash)]);
+        transport.assert_request("eth_getTransactionByHash", &[json!(hash)]);
+        transport.assert_no_more_requests();
+    }
+
+    #[test]
+    fn confirmation_dropped_transaction() {
+        let mut transport = TestTransport::new();
+        let web3 = Web3::new(transport.clone());
+
+        let hash = H256::repeat_byte(0xff);
+        let params = ConfirmParams {
+            confirmations: 0,
+            block_timeout: Some(1),
+            ..Default::default()
+        };
+
+        // Initial check
+        transport.add_response(json!("0x0"));
+        transport.add_response(json!(null));
+        // Check again past the block timeout
+        transport.add_response(json!("0x1"));
+        transport.add_response(json!(null));
+        // Transaction is no longer known to the node
+        transport.add_response(json!(null));
+
+        let confirm = wait_for_confirmation(&web3, hash, params).immediate();
+
+        assert!(
+            matches!(&confirm, Err(ExecutionError::DroppedTransaction(tx)) if *tx == hash),
+            "expected transaction to be dropped but got {:?}",
+            confirm
+        );
+
+        transport.assert_request("eth_blockNumber", &[]);
+        transport.assert_request("eth_getTransactionReceipt", &[json!(hash)]);
+        transport.assert_request("eth_blockNumber", &[]);
+        transport.assert_request("eth_getTransactionReceipt", &[json!(hash)]);
+        transport.assert_request("eth_getTransactionByHash", &[json!(hash)]);
+        transport.assert_no_more_requests();
+    }
+
+    #[test]
+    fn confirmation_dropped_transaction_without_block_timeout() {
+        let mut transport = TestTransport::new();
+        let web3 = Web3::new(transport.clone());
+
+        let hash = H256::repeat_byte(0xff);
+        let params = ConfirmParams {
+            confirmations: 0,
+            block_timeout: None,
+            ..Default::default()
+        };
+
+        // Initial check
+        transport.add_response(json!("0x0"));
+        transport.add_response(json!(null));
+        // Check again after waiting for a block
+        transport.add_response(json!("0x1"));
+        transport.add_response(json!(null));
+        // Transaction is no longer known to the node
+        transport.add_response(json!(null));
+
+        let confirm = wait_for_confirmation(&web3, hash, params).immediate();
+
+        assert!(
+            matches!(&confirm, Err(ExecutionError::DroppedTransaction(tx)) if *tx == hash),
+            "expected transaction to be dropped but got {:?}",
+            confirm
+        );
+
+        transport.assert_request("eth_blockNumber", &[]);
+        transport.assert_request("eth_getTransactionReceipt", &[json!(hash)]);
+        transport.assert_request("eth_blockNumber", &[]);
+        transport.assert_request("eth_getTransactionReceipt", &[json!(hash)]);
+        transport.assert_request("eth_getTransactionByHash", &[json!(hash)]);
+        transport.assert_no_more_requests();
+    }
+
+    #[test]
+    fn confirmation_events_only_for_verified_blocks() {
+        let mut transport = TestTransport::new();
+        let web3 = Web3::new(transport.clone());
+
+        let hash = H256::repeat_byte(0xff);
+
+        // receipt from block 1 which is no longer canonical
+        transport.add_response(json!("0x2"));
+        transport.add_response(generate_tx_receipt(hash, 1));
+        let mut block = generate_block(1);
+        block["hash"] = json!(H256::repeat_byte(1));
+        transport.add_response(block);
+        // transaction mined on block 4 instead
+        transport.add_response(json!("0x5"));
+        transport.add_response(generate_tx_receipt(hash, 4));
+        transport.add_response(generate_block(4));
+        // confirmed by one more block
+        transport.add_response(json!("0x6"));
+        transport.add_response(generate_tx_receipt(hash, 4));
+        transport.add_response(generate_block(4));
+
+        let events = confirmation_events(web3, hash, ConfirmParams::with_confirmations(2))
+            .collect::<Vec<_>>()
+            .immediate();
+
+        assert!(
+            matches!(
+                &events[..],
+                [
+                    Ok(TransactionEvent::Mined(mined)),
+                    Ok(TransactionEvent::Confirmations(1, 2)),
+                    Ok(TransactionEvent::Confirmations(2, 2)),
+                    Ok(TransactionEvent::Confirmed(confirmed)),
+                ] if mined.block_number == Some(4.into())
+                    && confirmed.block_number == Some(4.into())
+            ),
+            "unexpected transaction events {:?}",
+            events
+        );
+
+        transport.assert_request("eth_blockNumber", &[]);
+        transport.assert_request("eth_getTransactionReceipt", &[json!(hash)]);
+        transport.assert_request("eth_getBlockByNumber", &[json!("0x1"), json!(false)]);
+        transport.assert_request("eth_blockNumber", &[]);
+        transport.assert_request("eth_getTransactionReceipt", &[json!(hash)]);
+        transport.assert_request("eth_getBlockByNumber", &[json!("0x4"), json!(false)]);
+        transport.assert_request("eth_blockNumber", &[]);
+        transport.assert_request("eth_getTransactionReceipt", &[json!(hash)]);
+        transport.assert_request("eth_getBlockByNumber", &[json!("0x4"), json!(false)]);
+        transport.assert_no_more_requests();
+    }
+
+    #[test]
+    fn confirmation_reorged_transaction() {
+        let mut transport = TestTransport::new();
+        let web3 = Web3::new(transport.clone());
+
+        let hash = H256::repeat_byte(0xff);
+
+        // transaction mined on block 1
+        transport.add_response(json!("0x1"));
+        transport.add_response(generate_tx_receipt(hash, 1));
+        transport.add_response(generate_block(1));
+        // wait for a confirmation, the receipt's block is no longer canonical
+        transport.add_response(json!("0x2"));
+        transport.add_response(generate_tx_receipt(hash, 1));
+        let mut block = generate_block(1);
+        block["hash"] = json!(H256::repeat_byte(1));
+        transport.add_response(block);
+        // the transaction was not included again and is no longer known
+        transport.add_response(json!(null));
+
+        let confirm =
+            wait_for_confirmation(&web3, hash, ConfirmParams::with_confirmations(1)).immediate();
+
+        assert!(
+            matches!(
+                &confirm,
+                Err(ExecutionError::ReorgedTransaction(tx)) if tx.transaction_hash == hash
+            ),
+            "expected transaction to be reorged out but got {:?}",
+            confirm
+        );
+
+        transport.assert_request("eth_blockNumber", &[]);
+        transport.assert_request("eth_getTransactionReceipt", &[json!(hash)]);
+        transport.assert_request("eth_getBlockByNumber", &[json!("0x1"), json!(false)]);
+        transport.assert_request("eth_blockNumber", &[]);
+        transport.assert_request("eth_getTransactionReceipt", &[json!(hash)]);
+        transport.assert_request("eth_getBlockByNumber", &[json!("0x1"), json!(false)]);
+        transport.assert_request("eth_getTransactionByHash", &[json!(hash)]);
+        transport.assert_no_more_requests();
+    }
+
+    #[test]
+    fn confirmations_with_subscription() {
+        let mut transport = TestTransport::new();
+        let web3 = Web3::new(transport.clone());
+
+        let hash = H256::repeat_byte(0xff);
+
+        // subscribe to new heads
+        transport.add_response(json!("0x42"));
+        // transaction pending
+        transport.add_response(json!("0x1"));
+        transport.add_response(json!(null));
+        // new heads are notified, no polling needed
+        transport.add_notification(generate_block_header(2));
+        transport.add_notification(generate_block_header(3));
+        // check transaction was mined with the notified block number
+        transport.add_response(generate_tx_receipt(hash, 2));
+        transport.add_response(generate_block(2));
+        // unsubscribe
+        transport.add_response(json!(true));
+
+        let confirm = wait_for_confirmation_with_subscription(
+            &web3,
+            hash,
+            ConfirmParams::with_confirmations(1),
+        )
+        .immediate()
+        .expect("transaction confirmation failed");
+
+        assert_eq!(confirm.transaction_hash, hash);
+        transport.assert_request("eth_subscribe", &[json!("newHeads")]);
+        transport.assert_request("eth_blockNumber", &[]);
+        transport.assert_request("eth_getTransactionReceipt", &[json!(hash)]);
+        transport.assert_request("eth_getTransactionReceipt", &[json!(hash)]);
+        transport.assert_request("eth_getBlockByNumber", &[json!("0x2"), json!(false)]);
+        transport.assert_request("eth_unsubscribe", &[json!("0x42")]);
+        transport.assert_no_more_requests();
+    }
+
+    #[test]
+    fn confirmations_with_dropped_subscription() {
+        let mut transport = TestTransport::new();
+        let web3 = Web3::new(transport.clone());
+
+        let hash = H256::repeat_byte(0xff);
+
+        // subscribe to new heads
+        transport.add_response(json!("0x42"));
+        // transaction mined on block 1
+        transport.add_response(json!("0x1"));
+        transport.add_response(generate_tx_receipt(hash, 1));
+        transport.add_response(generate_block(1));
+        // unsubscribe
+        transport.add_response(json!(true));
+
+        let mut events = Box::pin(confirmation_events_with_subscription(
+            web3,
+            hash,
+            ConfirmParams::with_confirmations(2),
+        ));
+        let event = events.next().immediate();
+        assert!(
+            matches!(&event, Some(Ok(TransactionEvent::Mined(_)))),
+            "expected mined event but got {:?}",
+            event
+        );
+
+        transport.assert_request("eth_subscribe", &[json!("newHeads")]);
+        transport.assert_request("eth_blockNumber", &[]);
+        transport.assert_request("eth_getTransactionReceipt", &[json!(hash)]);
+        transport.assert_request("eth_getBlockByNumber", &[json!("0x1"), json!(false)]);
+        transport.assert_no_more_requests();
+
+        // dropping the stream before it ends still removes the subscription
+        drop(events);
+        transport.assert_request("eth_unsubscribe", &[json!("0x42")]);
+        transport.assert_no_more_requests();
+    }
+
+    #[test]
+    fn confirmations_with_dyn_transport_subscription() {
+        let mut transport = TestTransport::new();
+        let web3 = Web3::new(DynTransport::duplex(transport.clone()));
+
+        let hash = H256::repeat_byte(0xff);
+
+        // subscribe to new heads
+        transport.add_response(json!("0x42"));
+        // transaction pending
+        transport.add_response(json!("0x1"));
+        transport.add_response(json!(null));
+        // new head is notified, no polling needed
+        transport.add_notification(generate_block_header(2));
+        // check transaction was mined
+        transport.add_response(generate_tx_receipt(hash, 2));
+        // unsubscribe
+        transport.add_response(json!(true));
+
+        let confirm = wait_for_confirmation_with_subscription(&web3, hash, ConfirmParams::mined())
+            .immediate()
+            .expect("transaction confirmation failed");
+
+        assert_eq!(confirm.transaction_hash, hash);
+        transport.assert_request("eth_subscribe", &[json!("newHeads")]);
+        transport.assert_request("eth_blockNumber", &[]);
+        transport.assert_request("eth_getTransactionReceipt", &[json!(hash)]);
+        transport.assert_request("eth_getTransactionReceipt", &[json!(hash)]);
+        transport.assert_request("eth_unsubscribe", &[json!("0x42")]);
+        transport.assert_no_more_requests();
+    }
+
+    #[test]
+    fn confirmations_with_unsupported_subscription() {
+        let mut transport = TestTransport::new();
+        let web3 = Web3::new(transport.clone());
+
+        let hash = H256::repeat_byte(0xff);
+
+        // subscriptions are not supported
+        transport.add_error(web3::error::Error::Unreachable);
+        // fall back to polling
+        transport.add_response(json!("0x1"));
+        transport.add_response(json!(null));
+        transport.add_response(json!("0x2"));
+        transport.add_response(generate_tx_receipt(hash, 2));
+
+        let confirm = wait_for_confirmation_with_subscription(&web3, hash, ConfirmParams::mined())
+            .immediate()
+            .expect("transaction confirmation failed");
+
+        assert_eq!(confirm.transaction_hash, hash);
+        transport.assert_request("eth_subscribe", &[json!("newHeads")]);
+        transport.assert_request("eth_blockNumber", &[]);
+        transport.assert_request("eth_getTransactionReceipt", &[json!(hash)]);
         transport.assert_request("eth_blockNumber", &[]);
         transport.assert_request("eth_getTransactionReceipt", &[json!(hash)]);
         transport.assert_no_more_requests();
     }
+
+    #[test]
+    fn confirmations_with_closed_subscription() {
+        let mut transport = TestTransport::new();
+        let web3 = Web3::new(transport.clone());
+
+        let hash = H256::repeat_byte(0xff);
+
+        // subscribe to new heads
+        transport.add_response(json!("0x42"));
+        // transaction pending
+        transport.add_response(json!("0x1"));
+        transport.add_response(json!(null));
+        // subscription closes after notifying a block below the target, so
+        // fall back to polling
+        transport.add_notification(generate_block_header(1));
+        transport.add_response(json!("0x2"));
+        transport.add_response(generate_tx_receipt(hash, 2));
+        // unsubscribe
+        transport.add_response(json!(true));
+
+        let confirm = wait_for_confirmation_with_subscription(&web3, hash, ConfirmParams::mined())
+            .immediate()
+            .expect("transaction confirmation failed");
+
+        assert_eq!(confirm.transaction_hash, hash);
+        transport.assert_request("eth_subscribe", &[json!("newHeads")]);
+        transport.assert_request("eth_blockNumber", &[]);
+        transport.assert_request("eth_getTransactionReceipt", &[json!(hash)]);
+        transport.assert_request("eth_blockNumber", &[]);
+        transport.assert_request("eth_getTransactionReceipt", &[json!(hash)]);
+        transport.assert_request("eth_unsubscribe", &[json!("0x42")]);
+        transport.assert_no_more_requests();
+    }
+
+    #[test]
+    fn confirmation_events_with_reorgs() {
+        let mut transport = TestTransport::new();
+        let web3 = Web3::new(transport.clone());
+
+        let hash = H256::repeat_byte(0xff);
+
+        // transaction mined on block 1
+        transport.add_response(json!("0x1"));
+        transport.add_response(generate_tx_receipt(hash, 1));
+        transport.add_response(generate_block(1));
+        // reorg happened, transaction no longer mined but still pending
+        transport.add_response(json!("0x3"));
+        transport.add_response(json!(null));
+        transport.add_response(generate_pending_tx(hash));
+        // transaction mined again on block 5
+        transport.add_response(json!("0x6"));
+        transport.add_response(generate_tx_receipt(hash, 5));
+        transport.add_response(generate_block(5));
+        // reorg happened, transaction mined on block 6 instead
+        transport.add_response(json!("0x7"));
+        transport.add_response(generate_tx_receipt(hash, 6));
+        transport.add_response(generate_block(6));
+        // confirmed by 2 more blocks
+        transport.add_response(json!("0x8"));
+        transport.add_response(generate_tx_receipt(hash, 6));
+        transport.add_response(generate_block(6));
+
+        let events = confirmation_events(web3, hash, ConfirmParams::with_confirmations(2))
+            .collect::<Vec<_>>()
+            .immediate()
+            .into_iter()
+            .map(
+                |event| match event.expect("transaction confirmation failed") {
+                    TransactionEvent::Broadcast(_) => "broadcast".to_owned(),
+                    TransactionEvent::Mined(tx) => format!("mined {}", tx.block_number.unwrap()),
+                    TransactionEvent::Confirmations(count, total) => {
+                        format!("confirmations {}/{}", count, total)
+                    }
+                    TransactionEvent::Reorged(tx) => {
+                        format!("reorged {}", tx.block_number.unwrap())
+                    }
+                    TransactionEvent::Remined(tx) => {
+                        format!("remined {}", tx.block_number.unwrap())
+                    }
+                    TransactionEvent::Confirmed(tx) => {
+                        format!("confirmed {}", tx.block_number.unwrap())
+                    }
+                },
+            )
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            events,
+            [
+                "mined 1",
+                "reorged 1",
+                "remined 5",
+                "confirmations 1/2",
+                "reorged 5",
+                "remined 6",
+                "confirmations 1/2",
+                "confirmations 2/2",
+                "confirmed 6",
+            ]
+        );
+    }
 }
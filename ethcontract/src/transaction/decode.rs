@@ -0,0 +1,435 @@
+//! This module implements decoding of raw signed transactions into their
+//! structured parameters, including recovering the address of the account that
+//! signed them.
+
+use crate::errors::DecodeTransactionError;
+use ethcontract_common::hash;
+use rlp::{DecoderError, Rlp, RlpStream};
+use web3::signing::{self, Signature};
+use web3::types::{AccessList, AccessListItem, Address, Bytes, H256, U256, U64};
+
+/// The EIP-2718 transaction type for EIP-2930 access list transactions.
+const ACCESS_LIST_TRANSACTION_TYPE: u8 = 0x01;
+
+/// The EIP-2718 transaction type for EIP-1559 dynamic fee transactions.
+const DYNAMIC_FEE_TRANSACTION_TYPE: u8 = 0x02;
+
+/// A raw signed transaction decoded into its parameters.
+///
+/// Both legacy transactions (with or without EIP-155 replay protection) and
+/// EIP-2718 typed transaction envelopes for EIP-2930 and EIP-1559 transactions
+/// are supported.
+#[derive(Clone, Debug, PartialEq)]
+pub struct DecodedTransaction {
+    /// The EIP-2718 transaction type, `None` for legacy transactions.
+    pub transaction_type: Option<U64>,
+    /// The chain ID the transaction was signed for. This is `None` for legacy
+    /// transactions that were signed without EIP-155 replay protection.
+    pub chain_id: Option<u64>,
+    /// The address of the account that signed the transaction.
+    pub from: Address,
+    /// The receiver of the transaction, `None` for contract deployments.
+    pub to: Option<Address>,
+    /// The transaction nonce.
+    pub nonce: U256,
+    /// The gas limit of the transaction.
+    pub gas: U256,
+    /// The gas price of the transaction, `None` for EIP-1559 transactions.
+    pub gas_price: Option<U256>,
+    /// The maximum fee per gas, only specified for EIP-1559 transactions.
+    pub max_fee_per_gas: Option<U256>,
+    /// The maximum priority fee per gas, only specified for EIP-1559
+    /// transactions.
+    pub max_priority_fee_per_gas: Option<U256>,
+    /// The ETH value sent with the transaction.
+    pub value: U256,
+    /// The data for the transaction.
+    pub data: Bytes,
+    /// The access list of the transaction, `None` for legacy transactions.
+    pub access_list: Option<AccessList>,
+    /// The `v` value of the transaction signature. For typed transactions,
+    /// this is the recovery parity of the signature.
+    pub v: u64,
+    /// The `r` value of the transaction signature.
+    pub r: H256,
+    /// The `s` value of the transaction signature.
+    pub s: H256,
+    /// The transaction hash.
+    pub hash: H256,
+}
+
+impl DecodedTransaction {
+    /// Decodes raw signed transaction bytes, as produced by offline signing or
+    /// by `personal_signTransaction`, and recovers the transaction sender.
+    pub fn decode(raw: &[u8]) -> Result<Self, DecodeTransactionError> {
+        let hash = H256(hash::keccak256(raw));
+        match raw.first() {
+            Some(&ACCESS_LIST_TRANSACTION_TYPE) => decode_access_list_transaction(&raw[1..], hash),
+            Some(&DYNAMIC_FEE_TRANSACTION_TYPE) => decode_dynamic_fee_transaction(&raw[1..], hash),
+            // NOTE: RLP lists always start with a byte greater or equal to
+            //   `0xc0`, so any other leading byte must be an unknown transaction
+            //   type from an EIP-2718 envelope.
+            Some(&transaction_type) if transaction_type < 0xc0 => {
+                Err(DecodeTransactionError::UnsupportedType(transaction_type))
+            }
+            _ => decode_legacy_transaction(raw, hash),
+        }
+    }
+}
+
+/// Decodes a legacy RLP encoded transaction.
+fn decode_legacy_transaction(
+    raw: &[u8],
+    hash: H256,
+) -> Result<DecodedTransaction, DecodeTransactionError> {
+    let rlp = decode_list(raw, 9)?;
+
+    let signature = decode_signature(&rlp, 6)?;
+    let (chain_id, recovery_id) = match signature.v {
+        27 | 28 => (None, signature.v - 27),
+        v if v >= 35 => (Some((v - 35) / 2), (v - 35) % 2),
+        _ => return Err(DecodeTransactionError::InvalidSignature),
+    };
+
+    // NOTE: The signed message for legacy transactions is the RLP encoding of
+    //   the first 6 transaction fields, followed by the chain ID and two empty
+    //   values when using EIP-155 replay protection.
+    let mut message = RlpStream::new_list(if chain_id.is_some() { 9 } else { 6 });
+    for i in 0..6 {
+        message.append_raw(rlp.at(i)?.as_raw(), 1);
+    }
+    if let Some(chain_id) = chain_id {
+        message.append(&chain_id).append(&0u8).append(&0u8);
+    }
+    let from = recover(&message.out(), &signature, recovery_id)?;
+
+    Ok(DecodedTransaction {
+        transaction_type: None,
+        chain_id,
+        from,
+        to: decode_to(&rlp, 3)?,
+        nonce: rlp.val_at(0)?,
+        gas: rlp.val_at(2)?,
+        gas_price: Some(rlp.val_at(1)?),
+        max_fee_per_gas: None,
+        max_priority_fee_per_gas: None,
+        value: rlp.val_at(4)?,
+        data: Bytes(rlp.val_at(5)?),
+        access_list: None,
+        v: signature.v,
+        r: signature.r,
+        s: signature.s,
+        hash,
+    })
+}
+
+/// Decodes the payload of an EIP-2930 typed transaction envelope.
+fn decode_access_list_transaction(
+    payload: &[u8],
+    hash: H256,
+) -> Result<DecodedTransaction, DecodeTransactionError> {
+    let rlp = decode_list(payload, 11)?;
+
+    let signature = decode_signature(&rlp, 8)?;
+    let from = recover_typed(ACCESS_LIST_TRANSACTION_TYPE, &rlp, 8, &signature)?;
+
+    Ok(DecodedTransaction {
+        transaction_type: Some(ACCESS_LIST_TRANSACTION_TYPE.into()),
+        chain_id: Some(rlp.val_at(0)?),
+        from,
+        to: decode_to(&rlp, 4)?,
+        nonce: rlp.val_at(1)?,
+        gas: rlp.val_at(3)?,
+        gas_price: Some(rlp.val_at(2)?),
+        max_fee_per_gas: None,
+        max_priority_fee_per_gas: None,
+        value: rlp.val_at(5)?,
+        data: Bytes(rlp.val_at(6)?),
+        access_list: Some(decode_access_list(&rlp.at(7)?)?),
+        v: signature.v,
+        r: signature.r,
+        s: signature.s,
+        hash,
+    })
+}
+
+/// Decodes the payload of an EIP-1559 typed transaction envelope.
+fn decode_dynamic_fee_transaction(
+    payload: &[u8],
+    hash: H256,
+) -> Result<DecodedTransaction, DecodeTransactionError> {
+    let rlp = decode_list(payload, 12)?;
+
+    let signature = decode_signature(&rlp, 9)?;
+    let from = recover_typed(DYNAMIC_FEE_TRANSACTION_TYPE, &rlp, 9, &signature)?;
+
+    Ok(DecodedTransaction {
+        transaction_type: Some(DYNAMIC_FEE_TRANSACTION_TYPE.into()),
+        chain_id: Some(rlp.val_at(0)?),
+        from,
+        to: decode_to(&rlp, 5)?,
+        nonce: rlp.val_at(1)?,
+        gas: rlp.val_at(4)?,
+        gas_price: None,
+        max_fee_per_gas: Some(rlp.val_at(3)?),
+        max_priority_fee_per_gas: Some(rlp.val_at(2)?),
+        value: rlp.val_at(6)?,
+        data: Bytes(rlp.val_at(7)?),
+        access_list: Some(decode_access_list(&rlp.at(8)?)?),
+        v: signature.v,
+        r: signature.r,
+        s: signature.s,
+        hash,
+    })
+}
+
+/// Decodes an RLP list with the specified number of items that spans all of
+/// the encoded bytes.
+fn decode_list(encoded: &[u8], len: usize) -> Result<Rlp<'_>, DecodeTransactionError> {
+    let rlp = Rlp::new(encoded);
+    let info = rlp.payload_info()?;
+    if info.header_len + info.value_len != encoded.len() {
+        return Err(DecoderError::RlpInconsistentLengthAndData.into());
+    }
+    if rlp.item_count()? != len {
+        return Err(DecoderError::RlpIncorrectListLen.into());
+    }
+    Ok(rlp)
+}
+
+/// Decodes the `to` field of a transaction, where an empty value indicates a
+/// contract deployment.
+fn decode_to(rlp: &Rlp, index: usize) -> Result<Option<Address>, DecodeTransactionError> {
+    let to = rlp.at(index)?;
+    if to.is_empty() {
+        Ok(None)
+    } else {
+        Ok(Some(to.as_val()?))
+    }
+}
+
+/// Decodes an EIP-2930 access list.
+fn decode_access_list(rlp: &Rlp) -> Result<AccessList, DecodeTransactionError> {
+    rlp.iter()
+        .map(|item| {
+            Ok(AccessListItem {
+                address: item.val_at(0)?,
+                storage_keys: item.list_at(1)?,
+            })
+        })
+        .collect()
+}
+
+/// Decodes the `v`, `r` and `s` signature values starting at the specified
+/// index.
+fn decode_signature(rlp: &Rlp, index: usize) -> Result<Signature, DecodeTransactionError> {
+    let r: U256 = rlp.val_at(index + 1)?;
+    let s: U256 = rlp.val_at(index + 2)?;
+
+    Ok(Signature {
+        v: rlp.val_at(index)?,
+        r: H256(r.into()),
+        s: H256(s.into()),
+    })
+}
+
+/// Recovers the signer of a typed transaction. The signed message is the
+/// transaction type followed by the RLP encoding of the unsigned fields, which
+/// are all the fields preceding the signature.
+fn recover_typed(
+    transaction_type: u8,
+    rlp: &Rlp,
+    signature_index: usize,
+    signature: &Signature,
+) -> Result<Address, DecodeTransactionError> {
+    if signature.v > 1 {
+        return Err(DecodeTransactionError::InvalidSignature);
+    }
+
+    let mut fields = RlpStream::new_list(signature_index);
+    for i in 0..signature_index {
+        fields.append_raw(rlp.at(i)?.as_raw(), 1);
+    }
+
+    let mut message = vec![transaction_type];
+    message.extend_from_slice(&fields.out());
+
+    recover(&message, signature, signature.v)
+}
+
+/// Recovers the address that signed the specified message.
+fn recover(
+    message: &[u8],
+    signature: &Signature,
+    recovery_id: u64,
+) -> Result<Address, DecodeTransactionError> {
+    let mut compact = [0u8; 64];
+    compact[..32].copy_from_slice(signature.r.as_bytes());
+    compact[32..].copy_from_slice(signature.s.as_bytes());
+
+    signing::recover(&hash::keccak256(message), &compact, recovery_id as _)
+        .map_err(|_| DecodeTransactionError::InvalidSignature)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test::prelude::*;
+    use crate::transaction::{Account, Transaction, TransactionBuilder};
+    use hex_literal::hex;
+    use web3::signing::Key;
+
+    #[test]
+    fn decode_offline_signed_transaction() {
+        let transport = TestTransport::new();
+        let web3 = Web3::new(transport);
+
+        let key = key!("0x0102030405060708091011121314151617181920212223242526272829303132");
+        let to = addr!("0x0123456789012345678901234567890123456789");
+
+        let tx = TransactionBuilder::new(web3)
+            .from(Account::Offline(key.clone(), Some(77777)))
+            .to(to)
+            .gas(0x1337.into())
+            .gas_price(0x00ba_b10c.into())
+            .value(42.into())
+            .data(Bytes(vec![0x13, 0x37]))
+            .nonce(0x42.into())
+            .build()
+            .immediate()
+            .expect("failed to sign transaction");
+        let (raw, hash) = match tx {
+            Transaction::Raw { bytes, hash } => (bytes, hash),
+            _ => panic!("offline transactions always build into raw transactions"),
+        };
+
+        let decoded = DecodedTransaction::decode(&raw.0).expect("failed to decode transaction");
+
+        assert_eq!(decoded.transaction_type, None);
+        assert_eq!(decoded.chain_id, Some(77777));
+        assert_eq!(decoded.from, key.public_address());
+        assert_eq!(decoded.to, Some(to));
+        assert_eq!(decoded.nonce, 0x42.into());
+        assert_eq!(decoded.gas, 0x1337.into());
+        assert_eq!(decoded.gas_price, Some(0x00ba_b10c.into()));
+        assert_eq!(decoded.value, 42.into());
+        assert_eq!(decoded.data, Bytes(vec![0x13, 0x37]));
+        assert_eq!(decoded.access_list, None);
+        assert_eq!(decoded.hash, hash);
+    }
+
+    #[test]
+    fn decode_eip155_transaction() {
+        // Example transaction from the EIP-155 specification.
+        let raw = hex!(
+            "f86c098504a817c800825208943535353535353535353535353535353535353535880de0b6"
+            "b3a76400008025a028ef61340bd939bc2195fe537567866003e1a15d3c71ff63e1590620aa"
+            "636276a067cbe9d8997f761aecb703304b3800ccf555c9f3dc64214b297fb1966a3b6d83"
+        );
+        let decoded = DecodedTransaction::decode(&raw).expect("failed to decode transaction");
+
+        assert_eq!(decoded.chain_id, Some(1));
+        assert_eq!(
+            decoded.from,
+            addr!("0x9d8A62f656a8d1615C1294fd71e9CFb3E4855A4F")
+        );
+        assert_eq!(
+            decoded.to,
+            Some(addr!("0x3535353535353535353535353535353535353535"))
+        );
+        assert_eq!(decoded.nonce, 9.into());
+        assert_eq!(decoded.gas, 21000.into());
+        assert_eq!(decoded.gas_price, Some(20_000_000_000u64.into()));
+        assert_eq!(decoded.value, 1_000_000_000_000_000_000u64.into());
+        assert_eq!(decoded.data, Bytes::default());
+    }
+
+    #[test]
+    fn decode_access_list_transaction() {
+        let key = key!("0x0102030405060708091011121314151617181920212223242526272829303132");
+        let storage_key = H256::repeat_byte(0x42);
+        let access_list = vec![AccessListItem {
+            address: addr!("0x0123456789012345678901234567890123456789"),
+            storage_keys: vec![storage_key],
+        }];
+
+        // NOTE: `web3` does not support signing typed transactions, so encode
+        //   and sign the EIP-2930 transaction by hand.
+        let fields = |rlp: &mut RlpStream| {
+            rlp.append(&77777u64)
+                .append(&1u8)
+                .append(&2u8)
+                .append(&3u8)
+                .append_empty_data()
+                .append(&4u8)
+                .append(&vec![0x13u8, 0x37]);
+            rlp.begin_list(1)
+                .begin_list(2)
+                .append(&access_list[0].address)
+                .begin_list(1)
+                .append(&storage_key);
+        };
+
+        let mut unsigned = RlpStream::new_list(8);
+        fields(&mut unsigned);
+        let mut message = vec![ACCESS_LIST_TRANSACTION_TYPE];
+        message.extend_from_slice(&unsigned.out());
+        let signature = key
+            .sign(&hash::keccak256(&message), None)
+            .expect("failed to sign transaction");
+
+        let mut signed = RlpStream::new_list(11);
+        fields(&mut signed);
+        signed
+            .append(&(signature.v - 27))
+            .append(&U256::from_big_endian(signature.r.as_bytes()))
+            .append(&U256::from_big_endian(signature.s.as_bytes()));
+        let mut raw = vec![ACCESS_LIST_TRANSACTION_TYPE];
+        raw.extend_from_slice(&signed.out());
+
+        let decoded = DecodedTransaction::decode(&raw).expect("failed to decode transaction");
+
+        assert_eq!(decoded.transaction_type, Some(1.into()));
+        assert_eq!(decoded.chain_id, Some(77777));
+        assert_eq!(decoded.from, key.public_address());
+        assert_eq!(decoded.to, None);
+        assert_eq!(decoded.nonce, 1.into());
+        assert_eq!(decoded.gas_price, Some(2.into()));
+        assert_eq!(decoded.gas, 3.into());
+        assert_eq!(decoded.value, 4.into());
+        assert_eq!(decoded.data, Bytes(vec![0x13, 0x37]));
+        assert_eq!(decoded.access_list, Some(access_list));
+        assert_eq!(decoded.hash, H256(hash::keccak256(&raw)));
+    }
+
+    #[test]
+    fn decode_rejects_trailing_bytes() {
+        let mut raw = hex!(
+            "f86c098504a817c800825208943535353535353535353535353535353535353535880de0b6"
+            "b3a76400008025a028ef61340bd939bc2195fe537567866003e1a15d3c71ff63e1590620aa"
+            "636276a067cbe9d8997f761aecb703304b3800ccf555c9f3dc64214b297fb1966a3b6d83"
+        )
+        .to_vec();
+        raw.push(0x00);
+
+        let err = DecodedTransaction::decode(&raw).unwrap_err();
+        assert!(
+            matches!(
+                err,
+                DecodeTransactionError::Rlp(DecoderError::RlpInconsistentLengthAndData)
+            ),
+            "expected inconsistent length error but got '{:?}'",
+            err
+        );
+    }
+
+    #[test]
+    fn decode_unsupported_transaction_type() {
+        let err = DecodedTransaction::decode(&[0x03, 0xc0]).unwrap_err();
+        assert!(
+            matches!(err, DecodeTransactionError::UnsupportedType(0x03)),
+            "expected unsupported transaction type error but got '{:?}'",
+            err
+        );
+    }
+}
@@ -0,0 +1,199 @@
+//! Implementation of the policy used for turning gas estimates into transaction
+//! gas limits.
+
+use crate::errors::ExecutionError;
+use primitive_types::U256;
+
+/// A margin to add on top of gas estimates.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum GasMargin {
+    /// Scale the estimated gas by a factor. For example, a factor of `1.2`
+    /// adds a 20% margin to the gas estimate. The scaled gas is never lower
+    /// than the estimate, so factors below `1.0` or `NaN` have no effect.
+    Scaled(f64),
+    /// Add a fixed amount of gas to the gas estimate.
+    Fixed(U256),
+}
+
+/// The policy for determining the gas limit of a transaction when no explicit
+/// gas amount is specified and it is estimated by the node.
+///
+/// Gas estimates can be slightly too low for transactions that follow a state
+/// dependent execution path or that are eligible for gas refunds, so a margin
+/// can be added on top of the estimate. Additionally, a cap can be applied to
+/// the resulting gas limit and a fallback gas limit can be used when the gas
+/// estimation fails.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct GasPolicy {
+    /// The margin to add on top of the estimated gas.
+    pub margin: Option<GasMargin>,
+    /// The maximum gas limit to use. Note that this caps the gas limit after
+    /// applying the margin, so it can be lower than the gas estimate itself.
+    pub cap: Option<U256>,
+    /// The gas limit to use when gas estimation fails for reasons other than
+    /// the transaction reverting. The fallback is also capped by the maximum
+    /// gas limit. When not specified, gas estimation failures are returned as
+    /// errors.
+    pub fallback: Option<U256>,
+}
+
+impl GasPolicy {
+    /// Create a gas policy that scales gas estimates by the specified factor.
+    pub fn scaled(factor: f64) -> Self {
+        GasPolicy {
+            margin: Some(GasMargin::Scaled(factor)),
+            ..Default::default()
+        }
+    }
+
+    /// Create a gas policy that adds a fixed margin to gas estimates.
+    pub fn fixed(margin: U256) -> Self {
+        GasPolicy {
+            margin: Some(GasMargin::Fixed(margin)),
+            ..Default::default()
+        }
+    }
+
+    /// Set new value for [`margin`].
+    ///
+    /// [`margin`]: #structfield.margin
+    #[inline]
+    pub fn margin(mut self, margin: GasMargin) -> Self {
+        self.margin = Some(margin);
+        self
+    }
+
+    /// Set new value for [`cap`].
+    ///
+    /// [`cap`]: #structfield.cap
+    #[inline]
+    pub fn cap(mut self, cap: U256) -> Self {
+        self.cap = Some(cap);
+        self
+    }
+
+    /// Set new value for [`fallback`].
+    ///
+    /// [`fallback`]: #structfield.fallback
+    #[inline]
+    pub fn fallback(mut self, fallback: U256) -> Self {
+        self.fallback = Some(fallback);
+        self
+    }
+
+    /// Applies the gas policy to the result of a gas estimation, returning the
+    /// gas limit to use for the transaction.
+    pub fn apply(&self, estimate: Result<U256, ExecutionError>) -> Result<U256, ExecutionError> {
+        let estimate = match (estimate, self.fallback) {
+            (Ok(estimate), _) => estimate,
+            // NOTE: Transactions that are known to revert would only burn the
+            //   fallback gas, so never fall back for them.
+            (Err(err @ ExecutionError::Revert(_)), _)
+            | (Err(err @ ExecutionError::RevertWithReason(_)), _)
+            | (Err(err @ ExecutionError::InvalidOpcode), _) => return Err(err),
+            (Err(_), Some(fallback)) => return Ok(self.apply_cap(fallback)),
+            (Err(err), None) => return Err(err),
+        };
+
+        let gas = match self.margin {
+            Some(GasMargin::Scaled(factor)) => scale_gas(estimate, factor),
+            Some(GasMargin::Fixed(margin)) => estimate.saturating_add(margin),
+            None => estimate,
+        };
+
+        Ok(self.apply_cap(gas))
+    }
+
+    /// Caps a gas limit to the policy's maximum gas limit.
+    fn apply_cap(&self, gas: U256) -> U256 {
+        match self.cap {
+            Some(cap) => gas.min(cap),
+            None => gas,
+        }
+    }
+}
+
+/// Apply a scaling factor to a gas estimate, never going below the estimate.
+fn scale_gas(gas: U256, factor: f64) -> U256 {
+    // NOTE: Same as for gas prices, `U256` does not support floating point
+    //   multiplication so we convert to floats and back. We are OK with the
+    //   loss of precision here. Negative and `NaN` products convert to 0, so
+    //   clamp the result to the estimate.
+    U256::from_f64_lossy(gas.to_f64_lossy() * factor).max(gas)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn gas_policy_margin() {
+        let estimate = U256::from(100_000);
+
+        assert_eq!(GasPolicy::default().apply(Ok(estimate)).unwrap(), estimate);
+        assert_eq!(
+            GasPolicy::scaled(1.2).apply(Ok(estimate)).unwrap(),
+            120_000.into()
+        );
+        assert_eq!(
+            GasPolicy::fixed(5_000.into()).apply(Ok(estimate)).unwrap(),
+            105_000.into()
+        );
+        assert_eq!(
+            GasPolicy::fixed(1.into()).apply(Ok(U256::MAX)).unwrap(),
+            U256::MAX
+        );
+    }
+
+    #[test]
+    fn gas_policy_scaled_never_below_estimate() {
+        let estimate = U256::from(100_000);
+
+        for factor in &[0.5, 0.0, -1.0, f64::NAN, f64::NEG_INFINITY] {
+            assert_eq!(
+                GasPolicy::scaled(*factor).apply(Ok(estimate)).unwrap(),
+                estimate,
+                "factor {}",
+                factor
+            );
+        }
+        assert_eq!(
+            GasPolicy::scaled(f64::INFINITY)
+                .apply(Ok(estimate))
+                .unwrap(),
+            U256::MAX
+        );
+    }
+
+    #[test]
+    fn gas_policy_cap() {
+        let policy = GasPolicy::scaled(2.0).cap(150_000.into());
+
+        assert_eq!(policy.apply(Ok(50_000.into())).unwrap(), 100_000.into());
+        assert_eq!(policy.apply(Ok(100_000.into())).unwrap(), 150_000.into());
+    }
+
+    #[test]
+    fn gas_policy_fallback() {
+        let err = || Err(ExecutionError::Web3(web3::Error::Internal));
+        let policy = GasPolicy::scaled(1.2).fallback(500_000.into());
+
+        assert!(GasPolicy::scaled(1.2).apply(err()).is_err());
+        assert_eq!(policy.apply(err()).unwrap(), 500_000.into());
+        assert_eq!(policy.cap(1_000.into()).apply(err()).unwrap(), 1_000.into());
+    }
+
+    #[test]
+    fn gas_policy_no_fallback_on_revert() {
+        let policy = GasPolicy::default().fallback(500_000.into());
+
+        assert!(matches!(
+            policy.apply(Err(ExecutionError::Revert(None))),
+            Err(ExecutionError::Revert(None))
+        ));
+        assert!(matches!(
+            policy.apply(Err(ExecutionError::InvalidOpcode)),
+            Err(ExecutionError::InvalidOpcode)
+        ));
+    }
+}
@@ -0,0 +1,288 @@
+//! Implementation of a queue for sending many transactions from a single
+//! account. Transactions are assigned sequential nonces so that they can be
+//! built and signed concurrently instead of paying full round trips for each
+//! transaction one after the other.
+
+use crate::errors::ExecutionError;
+use crate::transaction::send::{self, TransactionResult};
+use crate::transaction::{confirm, Account, ResolveCondition, TransactionBuilder};
+use futures::stream::{self, StreamExt as _};
+use web3::api::Web3;
+use web3::types::{BlockNumber, U256};
+use web3::Transport;
+
+/// The default maximum number of transactions that are built or confirmed
+/// concurrently by a transaction queue.
+pub const DEFAULT_CONCURRENCY: usize = 8;
+
+/// A queue of transactions to send from a single account with sequential
+/// nonces.
+///
+/// Transactions are built (which includes estimating gas, getting the gas
+/// price and signing) concurrently, but are always sent to the node in the
+/// order they were pushed to the queue. Once sent, transactions are confirmed
+/// independently of each other.
+///
+/// If a transaction fails to be built or sent, its nonce is never used. In
+/// order to avoid leaving a gap in the account's nonces that would cause all
+/// following transactions to never get mined, the queue stops sending at the
+/// first failure: the failed transaction resolves to its error and all of the
+/// following transactions resolve to [`ExecutionError::QueueAborted`] without
+/// being sent. Transactions that were already sent are still confirmed. Note
+/// that failures while confirming a transaction (for example, a transaction
+/// that reverts) do not affect other transactions since its nonce was used.
+///
+/// Since transactions are built concurrently, gas for transactions depending
+/// on the effects of earlier transactions in the queue may be estimated
+/// against a state where these have not yet been applied. Specify the gas
+/// limit explicitly for such transactions or use a concurrency of 1.
+#[derive(Debug)]
+#[must_use = "transaction queues do nothing unless you `.send()` them"]
+pub struct TransactionQueue<T: Transport> {
+    web3: Web3<T>,
+    /// The account used for sending all queued transactions.
+    from: Account,
+    /// The nonce of the first transaction. Defaults to the account's
+    /// transaction count including pending transactions.
+    nonce: Option<U256>,
+    /// The maximum number of transactions to build or confirm concurrently.
+    concurrency: usize,
+    /// The queued transactions.
+    transactions: Vec<TransactionBuilder<T>>,
+}
+
+impl<T: Transport> TransactionQueue<T> {
+    /// Creates a new empty transaction queue for sending transactions from the
+    /// specified account.
+    pub fn new(web3: Web3<T>, from: Account) -> Self {
+        TransactionQueue {
+            web3,
+            from,
+            nonce: None,
+            concurrency: DEFAULT_CONCURRENCY,
+            transactions: Vec::new(),
+        }
+    }
+
+    /// Specify the nonce of the first transaction in the queue. If not
+    /// specified, the account's transaction count including pending
+    /// transactions is used.
+    pub fn nonce(mut self, value: U256) -> Self {
+        self.nonce = Some(value);
+        self
+    }
+
+    /// Specify the maximum number of transactions to build or confirm
+    /// concurrently. Defaults to [`DEFAULT_CONCURRENCY`].
+    pub fn concurrency(mut self, value: usize) -> Self {
+        self.concurrency = value.max(1);
+        self
+    }
+
+    /// Adds a transaction to the queue. The sender and nonce of the
+    /// transaction are overridden by the queue. For contract method calls, the
+    /// transaction builder is available as `MethodBuilder::tx`.
+    pub fn push(&mut self, tx: TransactionBuilder<T>) {
+        self.transactions.push(tx);
+    }
+
+    /// Sends all queued transactions and waits for them to resolve according
+    /// to their resolve conditions. Returns the results of the transactions in
+    /// the order they were pushed to the queue.
+    ///
+    /// Only fails if the nonce of the first transaction could not be
+    /// retrieved, in which case no transactions are sent.
+    pub async fn send(
+        self,
+    ) -> Result<Vec<Result<TransactionResult, ExecutionError>>, ExecutionError> {
+        let TransactionQueue {
+            web3,
+            from,
+            nonce,
+            concurrency,
+            transactions,
+        } = self;
+
+        let count = transactions.len();
+        let nonce = match nonce {
+            Some(nonce) => nonce,
+            None => {
+                web3.eth()
+                    .transaction_count(from.address(), Some(BlockNumber::Pending))
+                    .await?
+            }
+        };
+
+        let mut prepared = stream::iter(transactions.into_iter().enumerate().map(|(i, tx)| {
+            let tx = tx.from(from.clone()).nonce(nonce + i);
+            let recover_revert_reason = tx.recover_revert_reason;
+            async move {
+                let (tx, resolve) = tx.prepare().await?;
+                Ok((tx, resolve, recover_revert_reason))
+            }
+        }))
+        .buffered(concurrency);
+
+        let mut sent = Vec::with_capacity(count);
+        while let Some(result) = prepared.next().await {
+            let result = match result {
+                Ok((tx, resolve, recover_revert_reason)) => send::send_built(&web3, tx)
+                    .await
+                    .map(|tx_hash| (tx_hash, resolve, recover_revert_reason)),
+                Err(err) => Err(err),
+            };
+
+            let failed = result.is_err();
+            sent.push(result);
+            if failed {
+                break;
+            }
+        }
+        drop(prepared);
+
+        let failed = sent.iter().position(Result::is_err);
+        let mut results = stream::iter(sent.into_iter().map(|sent| {
+            let web3 = web3.clone();
+            async move {
+                let (tx_hash, resolve, recover_revert_reason) = sent?;
+                let confirmations = match resolve {
+                    ResolveCondition::Pending => None,
+                    ResolveCondition::Confirmed(params) => {
+                        Some(confirm::confirmation_events(web3.clone(), tx_hash, params))
+                    }
+                };
+
+                send::transaction_result(send::sent_progress(
+                    web3,
+                    tx_hash,
+                    confirmations,
+                    recover_revert_reason,
+                ))
+                .await
+            }
+        }))
+        .buffered(concurrency)
+        .collect::<Vec<_>>()
+        .await;
+
+        if let Some(failed) = failed {
+            results.resize_with(count, || Err(ExecutionError::QueueAborted(failed)));
+        }
+
+        Ok(results)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test::prelude::*;
+    use web3::error::Error as Web3Error;
+    use web3::types::{Address, H256};
+
+    fn queue(
+        web3: Web3<TestTransport>,
+        from: Account,
+        count: usize,
+    ) -> TransactionQueue<TestTransport> {
+        let mut queue = TransactionQueue::new(web3.clone(), from).concurrency(1);
+        for i in 0..count {
+            queue.push(
+                TransactionBuilder::new(web3.clone())
+                    .to(Address::repeat_byte(i as _))
+                    .gas(1.into())
+                    .gas_price(2.into())
+                    .resolve(ResolveCondition::Pending),
+            );
+        }
+        queue
+    }
+
+    #[test]
+    fn queue_sequential_nonces() {
+        let mut transport = TestTransport::new();
+        let web3 = Web3::new(transport.clone());
+
+        let from = addr!("0x9876543210987654321098765432109876543210");
+
+        transport.add_response(json!("0x2a"));
+        for i in 0..3 {
+            transport.add_response(json!(H256::repeat_byte(i)));
+        }
+        let results = queue(web3, Account::Local(from, None), 3)
+            .send()
+            .immediate()
+            .expect("failed to get nonce");
+
+        transport.assert_request("eth_getTransactionCount", &[json!(from), json!("pending")]);
+        for i in 0..3 {
+            transport.assert_request(
+                "eth_sendTransaction",
+                &[json!({
+                    "from": from,
+                    "to": Address::repeat_byte(i),
+                    "gas": "0x1",
+                    "gasPrice": "0x2",
+                    "nonce": U256::from(42 + i),
+                })],
+            );
+        }
+        transport.assert_no_more_requests();
+
+        let hashes = results
+            .into_iter()
+            .map(|result| result.expect("transaction failed").hash())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            hashes,
+            [
+                H256::repeat_byte(0),
+                H256::repeat_byte(1),
+                H256::repeat_byte(2)
+            ]
+        );
+    }
+
+    #[test]
+    fn queue_aborts_after_failure() {
+        let mut transport = TestTransport::new();
+        let web3 = Web3::new(transport.clone());
+
+        let from = addr!("0x9876543210987654321098765432109876543210");
+
+        transport.add_response(json!(H256::repeat_byte(0)));
+        transport.add_error(Web3Error::Internal);
+        let results = queue(web3, Account::Local(from, None), 3)
+            .nonce(0.into())
+            .send()
+            .immediate()
+            .expect("failed to get nonce");
+
+        transport.assert_request(
+            "eth_sendTransaction",
+            &[json!({
+                "from": from,
+                "to": Address::repeat_byte(0),
+                "gas": "0x1",
+                "gasPrice": "0x2",
+                "nonce": "0x0",
+            })],
+        );
+        transport.assert_request(
+            "eth_sendTransaction",
+            &[json!({
+                "from": from,
+                "to": Address::repeat_byte(1),
+                "gas": "0x1",
+                "gasPrice": "0x2",
+                "nonce": "0x1",
+            })],
+        );
+        transport.assert_no_more_requests();
+
+        assert_eq!(results.len(), 3);
+        assert!(matches!(&results[0], Ok(TransactionResult::Hash(_))));
+        assert!(matches!(&results[1], Err(ExecutionError::Web3(_))));
+        assert!(matches!(&results[2], Err(ExecutionError::QueueAborted(1))));
+    }
+}
@@ -0,0 +1,178 @@
+//! Implementation of revert reason recovery for failed transactions. Since
+//! transaction receipts do not include any information on why a transaction
+//! failed, the transaction is replayed with an `eth_call` on top of its parent
+//! block in order to retrieve the revert data.
+
+use crate::errors::{self, revert, ExecutionError, RevertReason};
+use web3::api::Web3;
+use web3::types::{BlockId, CallRequest, TransactionId, TransactionReceipt, U64};
+use web3::Transport;
+
+/// Recovers the revert reason of a failed mined transaction by replaying it as
+/// an `eth_call` on top of the parent of the block it was mined in.
+///
+/// Returns `None` when no revert reason could be recovered. This can happen
+/// when the transaction failed for reasons other than a revert (for example
+/// running out of gas), when it reverted without any data, or when the replay
+/// succeeds because it depended on state changed by transactions preceding it
+/// in the same block.
+pub async fn recover_revert_reason<T: Transport>(
+    web3: &Web3<T>,
+    receipt: &TransactionReceipt,
+) -> Result<Option<RevertReason>, ExecutionError> {
+    let hash = receipt.transaction_hash;
+    let block = receipt
+        .block_number
+        .ok_or(ExecutionError::PendingTransaction(hash))?;
+    let tx = web3
+        .eth()
+        .transaction(TransactionId::Hash(hash))
+        .await?
+        .ok_or(ExecutionError::MissingTransaction(hash))?;
+
+    let call = CallRequest {
+        from: tx.from,
+        to: tx.to,
+        gas: Some(tx.gas),
+        gas_price: Some(tx.gas_price),
+        value: Some(tx.value),
+        data: Some(tx.input),
+        transaction_type: None,
+        access_list: None,
+    };
+    let parent = BlockId::Number(block.saturating_sub(U64::one()).into());
+
+    match web3.eth().call(call, Some(parent)).await {
+        // NOTE: Some nodes return the revert data as the result of the call
+        //   instead of an error, so only consider encoded revert reasons and
+        //   panics in that case.
+        Ok(data) => Ok(revert::decode_revert_data(&data.0)
+            .filter(|reason| !matches!(reason, RevertReason::Custom(_)))),
+        Err(err) => match errors::get_revert_data(&err) {
+            Some(data) => Ok(revert::decode_revert_data(&data)),
+            None => match ExecutionError::from(err) {
+                ExecutionError::Revert(reason) => Ok(reason.map(RevertReason::Message)),
+                err => Err(err),
+            },
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test::prelude::*;
+    use jsonrpc_core::{Error as JsonrpcError, ErrorCode};
+    use web3::error::Error as Web3Error;
+    use web3::types::{H2048, H256};
+
+    fn failed_receipt(hash: H256) -> TransactionReceipt {
+        serde_json::from_value(json!({
+            "transactionHash": hash,
+            "transactionIndex": "0x1",
+            "blockNumber": "0x2",
+            "blockHash": H256::repeat_byte(2),
+            "cumulativeGasUsed": "0x1337",
+            "gasUsed": "0x1337",
+            "logsBloom": H2048::zero(),
+            "logs": [],
+            "status": "0x0",
+        }))
+        .unwrap()
+    }
+
+    fn transaction(hash: H256) -> serde_json::Value {
+        json!({
+            "hash": hash,
+            "nonce": "0x42",
+            "blockHash": H256::repeat_byte(2),
+            "blockNumber": "0x2",
+            "transactionIndex": "0x1",
+            "from": "0x9876543210987654321098765432109876543210",
+            "to": "0x0123456789012345678901234567890123456789",
+            "value": "0x0",
+            "gasPrice": "0x1",
+            "gas": "0x1337",
+            "input": "0x1337",
+        })
+    }
+
+    #[test]
+    fn recover_revert_reason_from_error() {
+        let mut transport = TestTransport::new();
+        let web3 = Web3::new(transport.clone());
+
+        let hash = H256::repeat_byte(0x42);
+        transport.add_response(transaction(hash));
+        transport.add_error(Web3Error::Rpc(JsonrpcError {
+            code: ErrorCode::from(3),
+            message: "execution reverted".to_owned(),
+            data: Some(json!(format!(
+                "0x{}",
+                hex::encode(revert::encode_panic(0x11))
+            ))),
+        }));
+
+        let reason = recover_revert_reason(&web3, &failed_receipt(hash))
+            .immediate()
+            .expect("failed to recover revert reason");
+
+        transport.assert_request("eth_getTransactionByHash", &[json!(hash)]);
+        transport.assert_request(
+            "eth_call",
+            &[
+                json!({
+                    "from": "0x9876543210987654321098765432109876543210",
+                    "to": "0x0123456789012345678901234567890123456789",
+                    "gas": "0x1337",
+                    "gasPrice": "0x1",
+                    "value": "0x0",
+                    "data": "0x1337",
+                }),
+                json!("0x1"),
+            ],
+        );
+        transport.assert_no_more_requests();
+
+        assert_eq!(reason, Some(RevertReason::Panic(0x11.into())));
+    }
+
+    #[test]
+    fn recover_revert_reason_from_message() {
+        let mut transport = TestTransport::new();
+        let web3 = Web3::new(transport.clone());
+
+        let hash = H256::repeat_byte(0x42);
+        transport.add_response(transaction(hash));
+        transport.add_error(Web3Error::Rpc(JsonrpcError {
+            code: ErrorCode::from(-32000),
+            message: "execution reverted: not enough funds".to_owned(),
+            data: None,
+        }));
+
+        let reason = recover_revert_reason(&web3, &failed_receipt(hash))
+            .immediate()
+            .expect("failed to recover revert reason");
+
+        assert_eq!(
+            reason,
+            Some(RevertReason::Message("not enough funds".to_owned()))
+        );
+    }
+
+    #[test]
+    fn recover_revert_reason_successful_replay() {
+        let mut transport = TestTransport::new();
+        let web3 = Web3::new(transport.clone());
+
+        let hash = H256::repeat_byte(0x42);
+        transport.add_response(transaction(hash));
+        transport.add_response(json!("0x"));
+
+        let reason = recover_revert_reason(&web3, &failed_receipt(hash))
+            .immediate()
+            .expect("failed to recover revert reason");
+
+        assert_eq!(reason, None);
+    }
+}
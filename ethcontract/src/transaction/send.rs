@@ -2,44 +2,207 @@
 //! confirmation.
 
 use crate::errors::ExecutionError;
-use crate::transaction::confirm;
-use crate::transaction::{ResolveCondition, Transaction, TransactionBuilder};
+use crate::transaction::build::resolve_sender;
+use crate::transaction::confirm::{self, ConfirmParams, TransactionEvent};
+use crate::transaction::replay;
+use crate::transaction::{Account, ResolveCondition, Transaction, TransactionBuilder};
+use futures::future;
+use futures::stream::{self, Stream, StreamExt as _};
+use web3::api::Web3;
 use web3::types::{TransactionReceipt, H256, U64};
-use web3::Transport;
+use web3::{DuplexTransport, Transport};
 
 impl<T: Transport> TransactionBuilder<T> {
     /// Sign (if required) and send the transaction. Returns the transaction
     /// hash that can be used to retrieve transaction information.
-    pub async fn send(mut self) -> Result<TransactionResult, ExecutionError> {
+    pub async fn send(self) -> Result<TransactionResult, ExecutionError> {
+        transaction_result(self.send_progress()).await
+    }
+
+    /// Sign (if required) and send the transaction, returning a stream of
+    /// events for the lifecycle of the transaction.
+    ///
+    /// The stream starts with a `Broadcast` event once the transaction was
+    /// sent. If the transaction is configured to wait for confirmations, it is
+    /// followed by events for the transaction being mined, confirmed by
+    /// additional blocks, reorged out or re-mined, and ends with a
+    /// `Confirmed` event. Errors, including the transaction failing, end the
+    /// stream.
+    pub fn send_progress(self) -> impl Stream<Item = Result<TransactionEvent, ExecutionError>> {
+        self.send_progress_with(confirm::confirmation_events)
+    }
+
+    /// Sign (if required) and send the transaction, returning a stream of
+    /// events with the specified confirmation event stream.
+    fn send_progress_with<F, S>(
+        self,
+        confirm: F,
+    ) -> impl Stream<Item = Result<TransactionEvent, ExecutionError>>
+    where
+        F: FnOnce(Web3<T>, H256, ConfirmParams) -> S,
+        S: Stream<Item = Result<TransactionEvent, ExecutionError>>,
+    {
+        stream::once(async move {
+            let web3 = self.web3.clone();
+            let recover_revert_reason = self.recover_revert_reason;
+            match self.broadcast().await {
+                Ok((tx_hash, resolve)) => {
+                    let confirmations = match resolve {
+                        ResolveCondition::Pending => None,
+                        ResolveCondition::Confirmed(params) => {
+                            Some(confirm(web3.clone(), tx_hash, params))
+                        }
+                    };
+
+                    sent_progress(web3, tx_hash, confirmations, recover_revert_reason).left_stream()
+                }
+                Err(err) => stream::once(future::err(err)).right_stream(),
+            }
+        })
+        .flatten()
+    }
+
+    /// Sign (if required) and send the transaction, returning the transaction
+    /// hash along with the condition for resolving it.
+    async fn broadcast(self) -> Result<(H256, ResolveCondition), ExecutionError> {
         let web3 = self.web3.clone();
+        let (tx, resolve) = self.prepare().await?;
+        let tx_hash = send_built(&web3, tx).await?;
+
+        Ok((tx_hash, resolve))
+    }
+
+    /// Simulate the transaction if preflight checks are enabled and build it,
+    /// returning it along with the condition for resolving it.
+    pub(crate) async fn prepare(
+        mut self,
+    ) -> Result<(Transaction, ResolveCondition), ExecutionError> {
         let resolve = self.resolve.take().unwrap_or_default();
 
-        let tx = self.build().await?;
-        let tx_hash = match tx {
-            Transaction::Request(tx) => web3.eth().send_transaction(tx).await?,
-            Transaction::Raw { bytes, hash } => {
-                let node_hash = web3.eth().send_raw_transaction(bytes).await?;
-                if node_hash != hash {
-                    return Err(ExecutionError::UnexpectedTransactionHash);
-                }
-                hash
+        if self.preflight {
+            // NOTE: Resolve the default sender before simulating so that it
+            //   is not requested from the node again when building.
+            if self.from.is_none() {
+                let from = resolve_sender(&self.web3, None).await?;
+                self.from = Some(Account::Local(from, None));
             }
-        };
+            self.clone().simulate().await?;
+        }
+
+        let tx = self.build().await?;
+
+        Ok((tx, resolve))
+    }
+}
+
+impl<T: DuplexTransport> TransactionBuilder<T> {
+    /// Sign (if required) and send the transaction. This is the same as
+    /// [`send`](Self::send), except that confirmations are waited for with a
+    /// `newHeads` subscription instead of by polling the node for new blocks,
+    /// falling back to polling if the node does not support it.
+    ///
+    /// This is also available for generated contract bindings, which use a
+    /// [`DynTransport`](crate::transport::DynTransport). Subscriptions are
+    /// used when it wraps a duplex transport, otherwise confirmations are
+    /// polled for.
+    pub async fn send_with_subscription(self) -> Result<TransactionResult, ExecutionError> {
+        transaction_result(self.send_progress_with_subscription()).await
+    }
+
+    /// Sign (if required) and send the transaction, returning a stream of
+    /// events for the lifecycle of the transaction. This is the same as
+    /// [`send_progress`](Self::send_progress), except that confirmations are
+    /// waited for with a `newHeads` subscription.
+    pub fn send_progress_with_subscription(
+        self,
+    ) -> impl Stream<Item = Result<TransactionEvent, ExecutionError>> {
+        self.send_progress_with(confirm::confirmation_events_with_subscription)
+    }
+}
 
-        let tx_receipt = match resolve {
-            ResolveCondition::Pending => return Ok(TransactionResult::Hash(tx_hash)),
-            ResolveCondition::Confirmed(params) => {
-                confirm::wait_for_confirmation(&web3, tx_hash, params).await
+/// Send a built transaction to the node, returning the transaction hash.
+pub(crate) async fn send_built<T: Transport>(
+    web3: &Web3<T>,
+    tx: Transaction,
+) -> Result<H256, ExecutionError> {
+    match tx {
+        Transaction::Request(tx) => Ok(web3.eth().send_transaction(tx).await?),
+        Transaction::Raw { bytes, hash } => {
+            let node_hash = web3.eth().send_raw_transaction(bytes).await?;
+            if node_hash != hash {
+                return Err(ExecutionError::UnexpectedTransactionHash);
             }
-        }?;
+            Ok(hash)
+        }
+    }
+}
 
-        match tx_receipt.status {
-            Some(U64([1])) => Ok(TransactionResult::Receipt(tx_receipt)),
-            _ => Err(ExecutionError::Failure(Box::new(tx_receipt))),
+/// Returns a stream of events for a sent transaction, starting with its
+/// broadcast and followed by the events of its confirmation, if any.
+pub(crate) fn sent_progress<T, S>(
+    web3: Web3<T>,
+    tx_hash: H256,
+    confirmations: Option<S>,
+    recover_revert_reason: bool,
+) -> impl Stream<Item = Result<TransactionEvent, ExecutionError>>
+where
+    T: Transport,
+    S: Stream<Item = Result<TransactionEvent, ExecutionError>>,
+{
+    stream::once(future::ok(TransactionEvent::Broadcast(tx_hash)))
+        .chain(stream::iter(confirmations).flatten())
+        .then(move |event| {
+            let web3 = web3.clone();
+            async move { check_status(&web3, event?, recover_revert_reason).await }
+        })
+}
+
+/// Checks the status of confirmed transactions, turning failed transactions
+/// into errors.
+async fn check_status<T: Transport>(
+    web3: &Web3<T>,
+    event: TransactionEvent,
+    recover_revert_reason: bool,
+) -> Result<TransactionEvent, ExecutionError> {
+    let tx_receipt = match event {
+        TransactionEvent::Confirmed(tx_receipt) => tx_receipt,
+        event => return Ok(event),
+    };
+
+    match tx_receipt.status {
+        Some(U64([1])) => Ok(TransactionEvent::Confirmed(tx_receipt)),
+        _ if recover_revert_reason => {
+            match replay::recover_revert_reason(web3, &tx_receipt).await {
+                Ok(Some(reason)) => Err(ExecutionError::FailureWithReason(tx_receipt, reason)),
+                _ => Err(ExecutionError::Failure(tx_receipt)),
+            }
         }
+        _ => Err(ExecutionError::Failure(tx_receipt)),
     }
 }
 
+/// Drives a transaction event stream to completion, returning the result of
+/// the sent transaction.
+pub(crate) async fn transaction_result(
+    events: impl Stream<Item = Result<TransactionEvent, ExecutionError>>,
+) -> Result<TransactionResult, ExecutionError> {
+    futures::pin_mut!(events);
+
+    let mut result = None;
+    while let Some(event) = events.next().await {
+        result = Some(match event {
+            Ok(TransactionEvent::Broadcast(hash)) => Ok(TransactionResult::Hash(hash)),
+            Ok(TransactionEvent::Confirmed(tx_receipt)) => {
+                Ok(TransactionResult::Receipt(*tx_receipt))
+            }
+            Ok(_) => continue,
+            Err(err) => Err(err),
+        });
+    }
+
+    result.expect("transaction ended without a result or an error")
+}
+
 /// Represents the result of a sent transaction that can either be a transaction
 /// hash, in the case the transaction was not confirmed, or a full transaction
 /// receipt if the `TransactionBuilder` was configured to wait for confirmation
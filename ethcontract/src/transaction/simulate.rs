@@ -0,0 +1,321 @@
+//! Implementation of transaction simulation. Simulating a transaction executes
+//! it with an `eth_call` against the pending block, allowing reverting
+//! transactions to be detected, along with their revert reason, before any gas
+//! is spent on them.
+
+use crate::errors::{self, ExecutionError};
+use crate::transaction::build::resolve_sender;
+use crate::transaction::{Account, TransactionBuilder};
+use web3::helpers::{self, CallFuture};
+use web3::types::{BlockNumber, Bytes, CallRequest};
+use web3::Transport;
+
+impl<T: Transport> TransactionBuilder<T> {
+    /// Simulate the transaction by executing it with an `eth_call` against the
+    /// pending block. The call uses the same sender, value, gas, nonce and data
+    /// that would be used when sending the transaction and resolves to the
+    /// data returned by the call. Reverts are returned as
+    /// `ExecutionError::RevertWithReason` when the node provides the revert
+    /// data, so that panics and custom errors can be inspected.
+    ///
+    /// Note that the gas price is only used for the simulation when it is
+    /// explicitly specified with `GasPrice::Value`.
+    pub async fn simulate(self) -> Result<Bytes, ExecutionError> {
+        let from = resolve_sender(&self.web3, self.from.as_ref().map(Account::address)).await?;
+
+        let mut request = helpers::serialize(&CallRequest {
+            from: Some(from),
+            to: self.to,
+            gas: self.gas,
+            gas_price: self.gas_price.and_then(|gas_price| gas_price.value()),
+            value: self.value,
+            data: self.data,
+            transaction_type: None,
+            access_list: None,
+        });
+        if let Some(nonce) = self.nonce {
+            // NOTE: `CallRequest` has no nonce field, so add it to the request
+            //   manually so that nodes supporting it can simulate with it.
+            request["nonce"] = helpers::serialize(&nonce);
+        }
+        let block = helpers::serialize(&BlockNumber::Pending);
+
+        let data = CallFuture::new(
+            self.web3
+                .transport()
+                .execute("eth_call", vec![request, block]),
+        )
+        .await
+        .map_err(errors::from_call_error)?;
+
+        Ok(data)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::errors::{revert, RevertReason};
+    use crate::test::prelude::*;
+    use crate::transaction::ResolveCondition;
+    use jsonrpc_core::{Error as JsonrpcError, ErrorCode};
+    use web3::error::Error as Web3Error;
+
+    #[test]
+    fn tx_simulate() {
+        let mut transport = TestTransport::new();
+        let web3 = Web3::new(transport.clone());
+
+        let from = addr!("0x9876543210987654321098765432109876543210");
+        let to = addr!("0x0123456789012345678901234567890123456789");
+
+        transport.add_response(json!("0x1337"));
+        let data = TransactionBuilder::new(web3)
+            .from(Account::Local(from, None))
+            .to(to)
+            .gas(1.into())
+            .gas_price(2.into())
+            .value(28.into())
+            .data(Bytes(vec![0x13, 0x37]))
+            .nonce(42.into())
+            .simulate()
+            .immediate()
+            .expect("simulation failed");
+
+        transport.assert_request(
+            "eth_call",
+            &[
+                json!({
+                    "from": from,
+                    "to": to,
+                    "gas": "0x1",
+                    "gasPrice": "0x2",
+                    "value": "0x1c",
+                    "data": "0x1337",
+                    "nonce": "0x2a",
+                }),
+                json!("pending"),
+            ],
+        );
+        transport.assert_no_more_requests();
+
+        assert_eq!(data, Bytes(vec![0x13, 0x37]));
+    }
+
+    #[test]
+    fn tx_simulate_default_account() {
+        let mut transport = TestTransport::new();
+        let web3 = Web3::new(transport.clone());
+
+        let from = addr!("0x9876543210987654321098765432109876543210");
+        let to = addr!("0x0123456789012345678901234567890123456789");
+
+        transport.add_response(json!([from])); // get accounts
+        transport.add_response(json!("0x"));
+        TransactionBuilder::new(web3)
+            .to(to)
+            .simulate()
+            .immediate()
+            .expect("simulation failed");
+
+        transport.assert_request("eth_accounts", &[]);
+        transport.assert_request(
+            "eth_call",
+            &[json!({ "from": from, "to": to }), json!("pending")],
+        );
+        transport.assert_no_more_requests();
+    }
+
+    #[test]
+    fn tx_send_preflight_revert() {
+        let mut transport = TestTransport::new();
+        let web3 = Web3::new(transport.clone());
+
+        let from = addr!("0x9876543210987654321098765432109876543210");
+        let to = addr!("0x0123456789012345678901234567890123456789");
+
+        transport.add_error(Web3Error::Rpc(JsonrpcError {
+            code: ErrorCode::from(3),
+            message: "execution reverted: not enough funds".to_owned(),
+            data: Some(json!(revert::encode_reason_hex("not enough funds"))),
+        }));
+        let result = TransactionBuilder::new(web3)
+            .from(Account::Local(from, None))
+            .to(to)
+            .gas(1.into())
+            .preflight(true)
+            .send()
+            .immediate();
+
+        transport.assert_request(
+            "eth_call",
+            &[
+                json!({ "from": from, "to": to, "gas": "0x1" }),
+                json!("pending"),
+            ],
+        );
+        transport.assert_no_more_requests();
+
+        assert!(
+            matches!(
+                &result,
+                Err(ExecutionError::RevertWithReason(RevertReason::Message(reason)))
+                    if reason == "not enough funds"
+            ),
+            "expected revert with reason but got {:?}",
+            result
+        );
+    }
+
+    #[test]
+    fn tx_simulate_panic() {
+        let mut transport = TestTransport::new();
+        let web3 = Web3::new(transport.clone());
+
+        let from = addr!("0x9876543210987654321098765432109876543210");
+        let to = addr!("0x0123456789012345678901234567890123456789");
+
+        transport.add_error(Web3Error::Rpc(JsonrpcError {
+            code: ErrorCode::from(3),
+            message: "execution reverted".to_owned(),
+            data: Some(json!(format!(
+                "0x{}",
+                hex::encode(revert::encode_panic(0x11))
+            ))),
+        }));
+        let result = TransactionBuilder::new(web3)
+            .from(Account::Local(from, None))
+            .to(to)
+            .simulate()
+            .immediate();
+
+        transport.assert_request(
+            "eth_call",
+            &[json!({ "from": from, "to": to }), json!("pending")],
+        );
+        transport.assert_no_more_requests();
+
+        assert!(
+            matches!(
+                &result,
+                Err(ExecutionError::RevertWithReason(RevertReason::Panic(code)))
+                    if *code == 0x11.into()
+            ),
+            "expected revert with panic but got {:?}",
+            result
+        );
+    }
+
+    #[test]
+    fn tx_simulate_custom_error() {
+        let mut transport = TestTransport::new();
+        let web3 = Web3::new(transport.clone());
+
+        let from = addr!("0x9876543210987654321098765432109876543210");
+        let to = addr!("0x0123456789012345678901234567890123456789");
+
+        transport.add_error(Web3Error::Rpc(JsonrpcError {
+            code: ErrorCode::from(3),
+            message: "execution reverted".to_owned(),
+            data: Some(json!("0xdeadbeef")),
+        }));
+        let result = TransactionBuilder::new(web3)
+            .from(Account::Local(from, None))
+            .to(to)
+            .simulate()
+            .immediate();
+
+        transport.assert_request(
+            "eth_call",
+            &[json!({ "from": from, "to": to }), json!("pending")],
+        );
+        transport.assert_no_more_requests();
+
+        assert!(
+            matches!(
+                &result,
+                Err(ExecutionError::RevertWithReason(RevertReason::Custom(data)))
+                    if data.0 == [0xde, 0xad, 0xbe, 0xef]
+            ),
+            "expected revert with custom error but got {:?}",
+            result
+        );
+    }
+
+    #[test]
+    fn tx_send_preflight_default_account() {
+        let mut transport = TestTransport::new();
+        let web3 = Web3::new(transport.clone());
+
+        let from = addr!("0x9876543210987654321098765432109876543210");
+        let to = addr!("0x0123456789012345678901234567890123456789");
+        let hash = hash!("0x4242424242424242424242424242424242424242424242424242424242424242");
+
+        transport.add_response(json!([from])); // get accounts
+        transport.add_response(json!("0x"));
+        transport.add_response(json!(hash));
+        TransactionBuilder::new(web3)
+            .to(to)
+            .gas(1.into())
+            .gas_price(2.into())
+            .preflight(true)
+            .resolve(ResolveCondition::Pending)
+            .send()
+            .immediate()
+            .expect("send failed");
+
+        // NOTE: The default account is only requested once for both the
+        //   simulation and the transaction.
+        transport.assert_request("eth_accounts", &[]);
+        transport.assert_request(
+            "eth_call",
+            &[
+                json!({ "from": from, "to": to, "gas": "0x1", "gasPrice": "0x2" }),
+                json!("pending"),
+            ],
+        );
+        transport.assert_request(
+            "eth_sendTransaction",
+            &[json!({ "from": from, "to": to, "gas": "0x1", "gasPrice": "0x2" })],
+        );
+        transport.assert_no_more_requests();
+    }
+
+    #[test]
+    fn tx_send_preflight_success() {
+        let mut transport = TestTransport::new();
+        let web3 = Web3::new(transport.clone());
+
+        let from = addr!("0x9876543210987654321098765432109876543210");
+        let to = addr!("0x0123456789012345678901234567890123456789");
+        let hash = hash!("0x4242424242424242424242424242424242424242424242424242424242424242");
+
+        transport.add_response(json!("0x"));
+        transport.add_response(json!(hash));
+        let result = TransactionBuilder::new(web3)
+            .from(Account::Local(from, None))
+            .to(to)
+            .gas(1.into())
+            .gas_price(2.into())
+            .preflight(true)
+            .resolve(ResolveCondition::Pending)
+            .send()
+            .immediate()
+            .expect("send failed");
+
+        transport.assert_request(
+            "eth_call",
+            &[
+                json!({ "from": from, "to": to, "gas": "0x1", "gasPrice": "0x2" }),
+                json!("pending"),
+            ],
+        );
+        transport.assert_request(
+            "eth_sendTransaction",
+            &[json!({ "from": from, "to": to, "gas": "0x1", "gasPrice": "0x2" })],
+        );
+        transport.assert_no_more_requests();
+
+        assert_eq!(result.hash(), hash);
+    }
+}
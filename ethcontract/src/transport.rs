@@ -5,22 +5,30 @@
 //! to help create a more ergonimic experience by making the generated struct not
 //! be generic on the underlying transport (at the small cost of some dynamic
 //! dispatch and extra allocations).
+//!
+//! `DynTransport` also implements `DuplexTransport` so that generated contract
+//! APIs can confirm transactions with `newHeads` subscriptions. Subscriptions
+//! are only supported when wrapping a duplex transport, either explicitly with
+//! `DynTransport::duplex` or for `web3`'s WebSocket and IPC transports.
 
 use futures::future::BoxFuture;
-use futures::FutureExt as _;
+use futures::stream::BoxStream;
+use futures::{FutureExt as _, StreamExt as _};
 use jsonrpc_core::Call;
 use serde_json::Value;
 use std::any::Any;
 use std::fmt::Debug;
 use std::future::Future;
 use std::sync::Arc;
+use web3::api::SubscriptionId;
 use web3::error::Error as Web3Error;
-use web3::{BatchTransport, RequestId, Transport};
+use web3::{BatchTransport, DuplexTransport, RequestId, Transport};
 
 /// Type alias for the output future in for the `DynTransport`'s `Transport`
 /// implementation.
 type BoxedFuture = BoxFuture<'static, Result<Value, Web3Error>>;
 type BoxedBatch = BoxFuture<'static, Result<Vec<Result<Value, Web3Error>>, Web3Error>>;
+type BoxedNotificationStream = BoxStream<'static, Value>;
 
 /// Helper trait that wraps `Transport` trait so it can be used as a trait
 /// object. This trait is implemented for all `Transport`'s.
@@ -37,6 +45,14 @@ trait TransportBoxed: Debug + Send + Sync + 'static {
     /// Wraps `BatchTransport::send_batch`
     fn send_batch_boxed(&self, requests: Vec<(RequestId, Call)>) -> BoxedBatch;
 
+    /// Wraps `DuplexTransport::subscribe`, failing for transports that do not
+    /// support subscriptions.
+    fn subscribe_boxed(&self, id: SubscriptionId) -> Result<BoxedNotificationStream, Web3Error>;
+
+    /// Wraps `DuplexTransport::unsubscribe`, failing for transports that do
+    /// not support subscriptions.
+    fn unsubscribe_boxed(&self, id: SubscriptionId) -> Result<(), Web3Error>;
+
     /// Returns reference to inner transport.
     fn inner(&self) -> &(dyn Any + Send + Sync);
 }
@@ -67,12 +83,77 @@ where
         self.send_batch(requests.into_iter()).boxed()
     }
 
+    fn subscribe_boxed(&self, _: SubscriptionId) -> Result<BoxedNotificationStream, Web3Error> {
+        Err(subscriptions_unsupported())
+    }
+
+    fn unsubscribe_boxed(&self, _: SubscriptionId) -> Result<(), Web3Error> {
+        Err(subscriptions_unsupported())
+    }
+
     #[inline(always)]
     fn inner(&self) -> &(dyn Any + Send + Sync) {
         self
     }
 }
 
+/// Wrapper around a duplex transport so that it can be used as a trait object
+/// that supports subscriptions.
+#[derive(Debug)]
+struct Duplex<T>(T);
+
+impl<F, B, S, T> TransportBoxed for Duplex<T>
+where
+    F: Future<Output = Result<Value, Web3Error>> + Send + 'static,
+    B: Future<Output = Result<Vec<Result<Value, Web3Error>>, Web3Error>> + Send + 'static,
+    S: futures::Stream<Item = Value> + Send + 'static,
+    T: Transport<Out = F>
+        + BatchTransport<Batch = B>
+        + DuplexTransport<NotificationStream = S>
+        + Send
+        + Sync
+        + 'static,
+{
+    #[inline(always)]
+    fn prepare_boxed(&self, method: &str, params: Vec<Value>) -> (RequestId, Call) {
+        self.0.prepare_boxed(method, params)
+    }
+
+    #[inline(always)]
+    fn send_boxed(&self, id: RequestId, request: Call) -> BoxedFuture {
+        self.0.send_boxed(id, request)
+    }
+
+    #[inline(always)]
+    fn execute_boxed(&self, method: &str, params: Vec<Value>) -> BoxedFuture {
+        self.0.execute_boxed(method, params)
+    }
+
+    #[inline(always)]
+    fn send_batch_boxed(&self, requests: Vec<(RequestId, Call)>) -> BoxedBatch {
+        self.0.send_batch_boxed(requests)
+    }
+
+    fn subscribe_boxed(&self, id: SubscriptionId) -> Result<BoxedNotificationStream, Web3Error> {
+        Ok(self.0.subscribe(id)?.boxed())
+    }
+
+    fn unsubscribe_boxed(&self, id: SubscriptionId) -> Result<(), Web3Error> {
+        self.0.unsubscribe(id)
+    }
+
+    #[inline(always)]
+    fn inner(&self) -> &(dyn Any + Send + Sync) {
+        &self.0
+    }
+}
+
+/// Returns the error for subscribing with a transport that does not support
+/// subscriptions.
+fn subscriptions_unsupported() -> Web3Error {
+    Web3Error::Transport("transport does not support subscriptions".to_string())
+}
+
 /// Dynamic `Transport` implementation to allow for a generic-free contract API.
 /// This type wraps any `Transport` type and implements `Transport` itself.
 #[derive(Debug)]
@@ -94,18 +175,61 @@ impl DynTransport {
             //   `DynTransport`, then just clone its inner transport instead of
             //   re-wrapping it.
             Some(dyn_transport) => dyn_transport.inner.clone(),
-            None => Arc::new(inner),
+            None => match duplex_transport(inner_ref) {
+                Some(duplex) => duplex,
+                None => Arc::new(inner),
+            },
         };
 
         DynTransport { inner: inner_arc }
     }
 
+    /// Wrap a `DuplexTransport` in a `DynTransport` that supports
+    /// subscriptions.
+    pub fn duplex<F, B, S, T>(inner: T) -> Self
+    where
+        F: Future<Output = Result<Value, Web3Error>> + Send + 'static,
+        B: Future<Output = Result<Vec<Result<Value, Web3Error>>, Web3Error>> + Send + 'static,
+        S: futures::Stream<Item = Value> + Send + 'static,
+        T: Transport<Out = F>
+            + BatchTransport<Batch = B>
+            + DuplexTransport<NotificationStream = S>
+            + Send
+            + Sync
+            + 'static,
+    {
+        DynTransport {
+            inner: Arc::new(Duplex(inner)),
+        }
+    }
+
     /// Casts this transport into the underlying type.
     pub fn downcast<T: Any + Send + Sync + 'static>(&self) -> Option<&T> {
         self.inner.inner().downcast_ref()
     }
 }
 
+/// Wraps `web3`'s built-in duplex transports so that they support
+/// subscriptions when wrapped with `DynTransport::new`.
+#[allow(unused_variables)]
+fn duplex_transport(inner: &dyn Any) -> Option<Arc<dyn TransportBoxed>> {
+    #[cfg(any(
+        feature = "ws-tokio",
+        feature = "ws-async-std",
+        feature = "ws-tls-tokio",
+        feature = "ws-tls-async-std",
+    ))]
+    if let Some(ws) = inner.downcast_ref::<web3::transports::WebSocket>() {
+        return Some(Arc::new(Duplex(ws.clone())));
+    }
+    #[cfg(feature = "ipc-tokio")]
+    if let Some(ipc) = inner.downcast_ref::<web3::transports::Ipc>() {
+        return Some(Arc::new(Duplex(ipc.clone())));
+    }
+
+    None
+}
+
 impl Clone for DynTransport {
     fn clone(&self) -> Self {
         DynTransport {
@@ -144,6 +268,18 @@ impl BatchTransport for DynTransport {
     }
 }
 
+impl DuplexTransport for DynTransport {
+    type NotificationStream = BoxedNotificationStream;
+
+    fn subscribe(&self, id: SubscriptionId) -> Result<Self::NotificationStream, Web3Error> {
+        self.inner.subscribe_boxed(id)
+    }
+
+    fn unsubscribe(&self, id: SubscriptionId) -> Result<(), Web3Error> {
+        self.inner.unsubscribe_boxed(id)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -175,6 +311,34 @@ mod tests {
         transport.assert_no_more_requests();
     }
 
+    #[test]
+    fn dyn_transport_subscriptions() {
+        let id = || SubscriptionId::from("0x42".to_string());
+        let mut transport = TestTransport::new();
+        transport.add_notification(json!(1));
+        transport.add_notification(json!(2));
+
+        let dyn_transport = DynTransport::new(transport.clone());
+        assert!(dyn_transport.subscribe(id()).is_err());
+        assert!(dyn_transport.unsubscribe(id()).is_err());
+
+        let dyn_transport = DynTransport::duplex(transport);
+        let notifications = dyn_transport
+            .subscribe(id())
+            .expect("failed to subscribe")
+            .collect::<Vec<_>>()
+            .immediate();
+        assert_eq!(notifications, [json!(1), json!(2)]);
+        dyn_transport
+            .unsubscribe(id())
+            .expect("failed to unsubscribe");
+
+        // NOTE: Re-wrapping a duplex `DynTransport` keeps its subscription
+        //   support.
+        let dyn_dyn_transport = DynTransport::new(dyn_transport);
+        assert!(dyn_dyn_transport.subscribe(id()).is_ok());
+    }
+
     #[test]
     #[allow(clippy::redundant_clone)]
     fn dyn_transport_does_not_double_wrap() {